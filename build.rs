@@ -0,0 +1,19 @@
+// Sets `cfg(http_backend)` when any of the backends which send requests through
+// `http::Client` is enabled, so that code shared by them needs a single cfg.
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rustc-check-cfg=cfg(http_backend)");
+    let http_backends = [
+        "REQWEST_BACKEND",
+        "WORKERS_BACKEND",
+        "SPIN_BACKEND",
+        "UNIX_BACKEND",
+        "MOCK",
+    ];
+    if http_backends
+        .iter()
+        .any(|feature| std::env::var_os(format!("CARGO_FEATURE_{feature}")).is_some())
+    {
+        println!("cargo:rustc-cfg=http_backend");
+    }
+}
@@ -1,23 +1,7 @@
 use anyhow::Result;
-use libsql_client::{args, Client, ResultSet, Statement};
+use libsql_client::{args, Client, Statement};
 use rand::prelude::SliceRandom;
 
-fn result_to_string(query_result: ResultSet) -> Result<String> {
-    let mut ret = String::new();
-    let ResultSet { columns, rows, .. } = query_result;
-    for column in &columns {
-        ret += &format!("| {:16} |", column);
-    }
-    ret += "\n| -------------------------------------------------------- |\n";
-    for row in rows {
-        for cell in row.values {
-            ret += &format!("| {:16} |", cell);
-        }
-        ret += "\n";
-    }
-    Ok(ret)
-}
-
 // Bumps a counter for one of the geographic locations picked at random.
 async fn bump_counter(db: Client) -> Result<String> {
     // Recreate the tables if they do not exist yet
@@ -57,19 +41,20 @@ async fn bump_counter(db: Client) -> Result<String> {
     .await?;
 
     let counter_response = db.execute("SELECT * FROM counter").await?;
-    let scoreboard = result_to_string(counter_response)?;
+    let scoreboard = counter_response.to_table_string();
     let html = format!("Scoreboard:\n{scoreboard}");
     Ok(html)
 }
 
 #[tokio::main]
 async fn main() {
-    let db = Client::from_config(libsql_client::Config {
+    // LIBSQL_CLIENT_BACKEND, if set, picks the backend, e.g. `reqwest` or `hrana`
+    let config = libsql_client::Config::from_env().unwrap_or_else(|_| libsql_client::Config {
         url: url::Url::parse("libsql://localhost:8080").unwrap(),
         auth_token: None,
-    })
-    .await
-    .unwrap();
+        options: Default::default(),
+    });
+    let db = Client::from_config(config).await.unwrap();
     let response = bump_counter(db)
         .await
         .unwrap_or_else(|e| format!("Error: {e}"));
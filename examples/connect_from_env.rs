@@ -1,23 +1,7 @@
 use anyhow::Result;
-use libsql_client::{args, Client, ResultSet, Statement};
+use libsql_client::{args, Client, Statement};
 use rand::prelude::SliceRandom;
 
-fn result_to_string(query_result: ResultSet) -> Result<String> {
-    let mut ret = String::new();
-    let ResultSet { columns, rows, .. } = query_result;
-    for column in &columns {
-        ret += &format!("| {:16} |", column);
-    }
-    ret += "\n| -------------------------------------------------------- |\n";
-    for row in rows {
-        for cell in row.values {
-            ret += &format!("| {:16} |", cell);
-        }
-        ret += "\n";
-    }
-    Ok(ret)
-}
-
 // Bumps a counter for one of the geographic locations picked at random.
 async fn bump_counter(db: Client) -> Result<String> {
     // Recreate the tables if they do not exist yet
@@ -63,7 +47,7 @@ async fn bump_counter(db: Client) -> Result<String> {
     }
 
     let counter_response = db.execute("SELECT * FROM counter").await?;
-    let scoreboard = result_to_string(counter_response)?;
+    let scoreboard = counter_response.to_table_string();
     let html = format!("Scoreboard:\n{scoreboard}");
     Ok(html)
 }
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use libsql_client::{args, de, Client, Statement};
+use libsql_client::{args, de, Client, Statement, Value, ValueExt};
 use rand::prelude::SliceRandom;
 
 #[derive(Debug, serde::Deserialize)]
@@ -73,10 +73,27 @@ async fn bump_counter(db: Client) -> Result<String> {
         .collect::<Result<Vec<Counter>, _>>()?;
 
     let scoreboard = result_to_string(counter_response)?;
-    let html = format!("Scoreboard:\n{scoreboard}");
+    let matching_cities = count_cities_starting_with(&db, &city[..2]).await?;
+    let html = format!(
+        "Scoreboard:\n{scoreboard}\nCities starting with {}: {matching_cities}",
+        &city[..2]
+    );
     Ok(html)
 }
 
+// Counts the cities whose name starts with the given prefix, e.g. typed by a user.
+// The prefix is escaped, so that `%` or `_` in it don't act as wildcards.
+async fn count_cities_starting_with(db: &Client, prefix: &str) -> Result<i64> {
+    let (pattern, escape) = Value::like_pattern(prefix, '\\');
+    let rs = db
+        .execute(Statement::with_args(
+            format!("SELECT count(*) FROM counter WHERE city LIKE ? || '%' {escape}"),
+            &[pattern],
+        ))
+        .await?;
+    Ok(rs.rows[0].try_get(0)?)
+}
+
 #[tokio::main]
 async fn main() {
     match libsql_client::Client::from_env().await {
@@ -0,0 +1,231 @@
+//! `ResultSet::into_arrow()` converts query results into an Arrow [RecordBatch],
+//! e.g. to hand them over to DataFusion or Polars.
+//!
+//! Each column gets the Arrow type of its non-NULL values:
+//!
+//! | Values                    | Arrow type |
+//! |---------------------------|------------|
+//! | only NULLs, or no rows    | `Null`     |
+//! | integers                  | `Int64`    |
+//! | integers and floats       | `Float64`  |
+//! | text                      | `Utf8`     |
+//! | blobs                     | `Binary`   |
+//! | any other mix             | `Utf8`     |
+//!
+//! In mixed columns, numbers are formatted in decimal and blobs as lowercase hex.
+//! NULLs stay null in every column, and all fields are nullable.
+
+use std::sync::Arc;
+
+use arrow_array::{
+    ArrayRef, BinaryArray, Float64Array, Int64Array, NullArray, RecordBatch, RecordBatchOptions,
+    StringArray,
+};
+use arrow_schema::{DataType, Field, Schema};
+
+use crate::{utils, ResultSet, Value};
+
+impl ResultSet {
+    /// Converts the result set into an Arrow [RecordBatch], inferring the type
+    /// of each column as described in the [module documentation](crate::arrow).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # async fn run() -> anyhow::Result<()> {
+    /// let db = libsql_client::Client::in_memory()?;
+    /// let batch = db.execute("SELECT 1 AS n, 'a' AS s").await?.into_arrow()?;
+    /// assert_eq!(batch.num_rows(), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn into_arrow(self) -> anyhow::Result<RecordBatch> {
+        let row_count = self.rows.len();
+        let mut columns: Vec<Vec<Value>> = self
+            .columns
+            .iter()
+            .map(|_| Vec::with_capacity(row_count))
+            .collect();
+        for row in self.rows {
+            for (column, value) in columns.iter_mut().zip(row.values) {
+                column.push(value);
+            }
+        }
+        let (fields, arrays): (Vec<Field>, Vec<ArrayRef>) = self
+            .columns
+            .into_iter()
+            .zip(columns)
+            .map(|(name, values)| {
+                let array = to_array(values);
+                (Field::new(name, array.data_type().clone(), true), array)
+            })
+            .unzip();
+        let options = RecordBatchOptions::new().with_row_count(Some(row_count));
+        Ok(RecordBatch::try_new_with_options(
+            Arc::new(Schema::new(fields)),
+            arrays,
+            &options,
+        )?)
+    }
+}
+
+// Infers the Arrow type of a column from its non-NULL values
+fn column_type(values: &[Value]) -> DataType {
+    let mut column_type = DataType::Null;
+    for value in values {
+        let value_type = match value {
+            Value::Null => continue,
+            Value::Integer { .. } => DataType::Int64,
+            Value::Float { .. } => DataType::Float64,
+            Value::Text { .. } => DataType::Utf8,
+            Value::Blob { .. } => DataType::Binary,
+        };
+        column_type = match (column_type, value_type) {
+            (DataType::Null, value_type) => value_type,
+            (DataType::Int64, DataType::Float64) | (DataType::Float64, DataType::Int64) => {
+                DataType::Float64
+            }
+            (column_type, value_type) if column_type == value_type => column_type,
+            _ => return DataType::Utf8,
+        };
+    }
+    column_type
+}
+
+fn to_array(values: Vec<Value>) -> ArrayRef {
+    match column_type(&values) {
+        DataType::Int64 => Arc::new(
+            values
+                .into_iter()
+                .map(|value| match value {
+                    Value::Integer { value } => Some(value),
+                    _ => None,
+                })
+                .collect::<Int64Array>(),
+        ),
+        DataType::Float64 => Arc::new(
+            values
+                .into_iter()
+                .map(|value| match value {
+                    Value::Integer { value } => Some(value as f64),
+                    Value::Float { value } => Some(value),
+                    _ => None,
+                })
+                .collect::<Float64Array>(),
+        ),
+        DataType::Binary => Arc::new(
+            values
+                .into_iter()
+                .map(|value| match value {
+                    Value::Blob { value } => Some(value),
+                    _ => None,
+                })
+                .collect::<BinaryArray>(),
+        ),
+        DataType::Utf8 => Arc::new(
+            values
+                .into_iter()
+                .map(|value| match value {
+                    Value::Null => None,
+                    Value::Integer { value } => Some(value.to_string()),
+                    Value::Float { value } => Some(value.to_string()),
+                    Value::Text { value } => Some(value),
+                    Value::Blob { value } => Some(utils::encode_hex(&value)),
+                })
+                .collect::<StringArray>(),
+        ),
+        _ => Arc::new(NullArray::new(values.len())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::Array;
+
+    fn result_set(columns: &[&str], rows: Vec<Vec<Value>>) -> ResultSet {
+        ResultSet {
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            rows: rows
+                .into_iter()
+                .map(|values| crate::Row {
+                    values,
+                    #[cfg(feature = "mapping_names_to_values_in_rows")]
+                    value_map: Default::default(),
+                })
+                .collect(),
+            rows_affected: 0,
+            last_insert_rowid: None,
+        }
+    }
+
+    #[test]
+    fn test_column_types() {
+        let int = |value| Value::Integer { value };
+        let text = |value: &str| Value::Text {
+            value: value.to_string(),
+        };
+        let rs = result_set(
+            &["i", "f", "s", "b", "n", "mixed"],
+            vec![
+                vec![
+                    int(1),
+                    int(1),
+                    text("a"),
+                    Value::Blob { value: vec![0xab] },
+                    Value::Null,
+                    int(1),
+                ],
+                vec![
+                    Value::Null,
+                    Value::Float { value: 0.5 },
+                    Value::Null,
+                    Value::Null,
+                    Value::Null,
+                    Value::Blob { value: vec![0xff] },
+                ],
+            ],
+        );
+        let batch = rs.into_arrow().unwrap();
+        let types: Vec<DataType> = batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.data_type().clone())
+            .collect();
+        assert_eq!(
+            types,
+            [
+                DataType::Int64,
+                DataType::Float64,
+                DataType::Utf8,
+                DataType::Binary,
+                DataType::Null,
+                DataType::Utf8
+            ]
+        );
+        assert!(batch.column(0).is_null(1));
+        let floats = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert_eq!(floats.value(0), 1.0);
+        let mixed = batch
+            .column(5)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(mixed.value(0), "1");
+        assert_eq!(mixed.value(1), "ff");
+    }
+
+    #[test]
+    fn test_empty_result_set() {
+        let batch = result_set(&["a"], vec![]).into_arrow().unwrap();
+        assert_eq!(batch.num_rows(), 0);
+        assert_eq!(batch.schema().field(0).data_type(), &DataType::Null);
+        let batch = result_set(&[], vec![vec![], vec![]]).into_arrow().unwrap();
+        assert_eq!(batch.num_rows(), 2);
+    }
+}
@@ -0,0 +1,271 @@
+//! `BatchBuilder` assembles batches whose steps only run when conditions
+//! on the outcomes of earlier steps hold.
+
+use std::collections::HashMap;
+
+use crate::{proto, ResultSet, Statement};
+
+/// A condition on the outcome of earlier steps of a batch.
+/// Steps are referred to by their 0-based index, and may only refer to earlier steps.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Condition {
+    /// The step succeeded.
+    Ok(usize),
+    /// The step failed. A step which didn't run neither succeeded nor failed.
+    Error(usize),
+    Not(Box<Condition>),
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+}
+
+impl Condition {
+    /// Negates the condition.
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Self {
+        Condition::Not(Box::new(self))
+    }
+
+    pub(crate) fn to_proto(&self) -> proto::BatchCond {
+        match self {
+            Condition::Ok(step) => proto::BatchCond::Ok { step: *step as _ },
+            Condition::Error(step) => proto::BatchCond::Error { step: *step as _ },
+            Condition::Not(cond) => proto::BatchCond::Not {
+                cond: Box::new(cond.to_proto()),
+            },
+            Condition::And(conds) => proto::BatchCond::And {
+                conds: conds.iter().map(Condition::to_proto).collect(),
+            },
+            Condition::Or(conds) => proto::BatchCond::Or {
+                conds: conds.iter().map(Condition::to_proto).collect(),
+            },
+        }
+    }
+
+    // Inverse of to_proto(), for backends which evaluate conditions themselves
+    #[cfg_attr(not(feature = "mock"), allow(dead_code))]
+    pub(crate) fn from_proto(cond: &proto::BatchCond) -> Self {
+        match cond {
+            proto::BatchCond::Ok { step } => Condition::Ok(*step as _),
+            proto::BatchCond::Error { step } => Condition::Error(*step as _),
+            proto::BatchCond::Not { cond } => Condition::Not(Box::new(Self::from_proto(cond))),
+            proto::BatchCond::And { conds } => {
+                Condition::And(conds.iter().map(Self::from_proto).collect())
+            }
+            proto::BatchCond::Or { conds } => {
+                Condition::Or(conds.iter().map(Self::from_proto).collect())
+            }
+        }
+    }
+
+    /// Evaluates the condition, given the outcomes of the steps executed so far:
+    /// `Some(true)` for success, `Some(false)` for failure and `None` for skipped steps.
+    #[cfg_attr(
+        not(any(feature = "local_backend", feature = "mock")),
+        allow(dead_code)
+    )]
+    pub(crate) fn eval(&self, outcomes: &[Option<bool>]) -> bool {
+        match self {
+            Condition::Ok(step) => outcomes.get(*step).copied().flatten() == Some(true),
+            Condition::Error(step) => outcomes.get(*step).copied().flatten() == Some(false),
+            Condition::Not(cond) => !cond.eval(outcomes),
+            Condition::And(conds) => conds.iter().all(|c| c.eval(outcomes)),
+            Condition::Or(conds) => conds.iter().any(|c| c.eval(outcomes)),
+        }
+    }
+}
+
+/// Builds a batch of statements executed in a single round trip,
+/// where each step may be conditioned on the outcomes of earlier steps.
+///
+/// # Examples
+///
+/// ```
+/// # async fn run() {
+/// use libsql_client::batch::{BatchBuilder, Condition};
+///
+/// let db = libsql_client::Client::in_memory().unwrap();
+/// # db.execute("create table users(name text unique)").await.unwrap();
+/// # db.execute("create table log(msg text)").await.unwrap();
+/// let batch = BatchBuilder::new()
+///     .step("insert into users values ('ann')")
+///     .step("insert into log values ('ann signed up')")
+///     .when(Condition::Ok(0));
+/// let result = db.execute_batch(batch).await.unwrap();
+/// assert!(result.step_results[1].is_some());
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct BatchBuilder {
+    pub(crate) steps: Vec<(Statement, Option<Condition>)>,
+    pub(crate) labels: HashMap<String, usize>,
+}
+
+impl BatchBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends an unconditional step.
+    pub fn step(mut self, stmt: impl Into<Statement>) -> Self {
+        self.steps.push((stmt.into(), None));
+        self
+    }
+
+    /// Makes the most recently added step run only if `cond` holds.
+    ///
+    /// # Panics
+    /// Panics if no step was added yet.
+    pub fn when(mut self, cond: Condition) -> Self {
+        let (_, step_cond) = self
+            .steps
+            .last_mut()
+            .expect("BatchBuilder::when() called before step()");
+        *step_cond = Some(cond);
+        self
+    }
+
+    /// Names the most recently added step, so that its result can be looked up
+    /// with [`MultiResultSet::get_named()`]. A label given to several steps refers to the last one.
+    ///
+    /// # Panics
+    /// Panics if no step was added yet.
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        let index = self
+            .steps
+            .len()
+            .checked_sub(1)
+            .expect("BatchBuilder::label() called before step()");
+        self.labels.insert(label.into(), index);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+/// Results of the steps of a batch, returned by [`Client::batch_query()`](crate::Client::batch_query).
+/// Each step has either a result set or the error which made it fail or skipped it.
+///
+/// # Examples
+///
+/// ```
+/// # async fn run() {
+/// use libsql_client::BatchBuilder;
+///
+/// let db = libsql_client::Client::in_memory().unwrap();
+/// let batch = BatchBuilder::new()
+///     .step("select 1").label("users")
+///     .step("select x").label("orders");
+/// let results = db.batch_query(batch).await.unwrap();
+/// assert_eq!(results.get_named("users").unwrap().rows.len(), 1);
+/// assert!(results.error(1).is_some());
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct MultiResultSet {
+    steps: Vec<anyhow::Result<ResultSet>>,
+    labels: HashMap<String, usize>,
+}
+
+impl MultiResultSet {
+    pub(crate) fn new(
+        steps: Vec<anyhow::Result<ResultSet>>,
+        labels: HashMap<String, usize>,
+    ) -> Self {
+        Self { steps, labels }
+    }
+
+    /// Returns the result set of the step at `index`,
+    /// or `None` if the step failed or there's no such step.
+    pub fn get(&self, index: usize) -> Option<&ResultSet> {
+        self.steps.get(index)?.as_ref().ok()
+    }
+
+    /// Returns the result set of the step labeled with [`BatchBuilder::label()`],
+    /// or `None` if the step failed or no step has this label.
+    pub fn get_named(&self, label: &str) -> Option<&ResultSet> {
+        self.get(self.index_of(label)?)
+    }
+
+    /// Returns the index of the step labeled with [`BatchBuilder::label()`].
+    pub fn index_of(&self, label: &str) -> Option<usize> {
+        self.labels.get(label).copied()
+    }
+
+    /// Returns the error of the step at `index`, or `None` if it succeeded or there's no such step.
+    pub fn error(&self, index: usize) -> Option<&anyhow::Error> {
+        self.steps.get(index)?.as_ref().err()
+    }
+
+    /// Returns the outcomes of all steps, in order.
+    pub fn into_results(self) -> Vec<anyhow::Result<ResultSet>> {
+        self.steps
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eval() {
+        let outcomes = [Some(true), Some(false), None];
+        assert!(Condition::Ok(0).eval(&outcomes));
+        assert!(Condition::Error(1).eval(&outcomes));
+        assert!(!Condition::Ok(2).eval(&outcomes));
+        assert!(!Condition::Error(2).eval(&outcomes));
+        assert!(Condition::Or(vec![Condition::Ok(1), Condition::Ok(0)]).eval(&outcomes));
+        assert!(!Condition::And(vec![Condition::Ok(1), Condition::Ok(0)]).eval(&outcomes));
+        assert!(Condition::Ok(2).not().eval(&outcomes));
+    }
+
+    #[test]
+    fn test_when_applies_to_last_step() {
+        let batch = BatchBuilder::new()
+            .step("SELECT 1")
+            .step("SELECT 2")
+            .when(Condition::Ok(0));
+        assert_eq!(batch.steps[0].1, None);
+        assert_eq!(batch.steps[1].1, Some(Condition::Ok(0)));
+    }
+
+    #[test]
+    fn test_labels() {
+        let batch = BatchBuilder::new()
+            .step("SELECT 1")
+            .label("first")
+            .step("SELECT 2")
+            .label("second")
+            .label("other");
+        let results = MultiResultSet::new(
+            vec![
+                Ok(ResultSet::from(proto::StmtResult {
+                    cols: vec![],
+                    rows: vec![],
+                    affected_row_count: 0,
+                    last_insert_rowid: None,
+                })),
+                Err(anyhow::anyhow!("no such column: x")),
+            ],
+            batch.labels,
+        );
+        assert!(results.get_named("first").is_some());
+        assert_eq!(results.index_of("other"), Some(1));
+        assert!(results.get_named("second").is_none());
+        assert_eq!(results.error(1).unwrap().to_string(), "no such column: x");
+        assert!(results.get(2).is_none());
+    }
+}
@@ -0,0 +1,165 @@
+//! `ClientBuilder` combines the options of a [Client] in one place.
+
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::{Client, Config, RetryPolicy};
+
+/// Builds a [Client] from options which the other constructors can't combine.
+///
+/// The backend is picked from the URL scheme, like in [`Client::from_config()`],
+/// unless an HTTP backend is passed explicitly with [`ClientBuilder::inner()`].
+/// Custom headers, the retry policy and token providers only apply to HTTP backends,
+/// and building any other backend with them set fails.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn f() -> anyhow::Result<()> {
+/// use libsql_client::{ClientBuilder, RetryPolicy};
+/// use std::time::Duration;
+///
+/// let db = ClientBuilder::new("https://example.com/db")
+///     .token("secret")
+///     .timeout(Duration::from_secs(10))
+///     .retry_policy(RetryPolicy::new(3))
+///     .header("x-tenant", "acme")
+///     .build()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ClientBuilder {
+    url: String,
+    token: Option<String>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    retry_policy: Option<RetryPolicy>,
+    headers: Vec<(String, String)>,
+    #[cfg(http_backend)]
+    inner: Option<crate::http::InnerClient>,
+    #[cfg(http_backend)]
+    token_provider: Option<crate::http::TokenProvider>,
+}
+
+impl ClientBuilder {
+    /// Starts building a client for the database at `url`.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            token: None,
+            timeout: None,
+            connect_timeout: None,
+            retry_policy: None,
+            headers: Vec::new(),
+            #[cfg(http_backend)]
+            inner: None,
+            #[cfg(http_backend)]
+            token_provider: None,
+        }
+    }
+
+    /// Sets the auth token.
+    pub fn token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Sets a timeout for whole requests. See [`Config::with_request_timeout()`].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets a timeout for establishing connections. See [`Config::with_connect_timeout()`].
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the policy for retrying failed requests.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Adds a header sent with every request.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Uses the given HTTP backend, instead of picking one from the URL scheme.
+    /// Timeouts are then up to the backend's own configuration.
+    #[cfg(http_backend)]
+    pub fn inner(mut self, inner: crate::http::InnerClient) -> Self {
+        self.inner = Some(inner);
+        self
+    }
+
+    /// Fetches auth tokens from `provider` instead of using a fixed token.
+    /// See [`crate::http::Client::with_token_provider()`].
+    #[cfg(http_backend)]
+    pub fn token_provider<F, Fut>(mut self, provider: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<String>> + Send + 'static,
+    {
+        self.token_provider = Some(crate::http::TokenProvider::new(provider));
+        self
+    }
+
+    fn config(&self) -> Result<Config> {
+        let mut config = Config::new(self.url.as_str())?;
+        if let Some(token) = &self.token {
+            config = config.with_auth_token(token);
+        }
+        if let Some(timeout) = self.timeout {
+            config = config.with_request_timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            config = config.with_connect_timeout(timeout);
+        }
+        Ok(config)
+    }
+
+    /// Creates the client.
+    #[allow(unused_mut)]
+    pub async fn build(mut self) -> Result<Client> {
+        let config = self.config()?;
+        #[cfg(http_backend)]
+        let client = match self.inner.take() {
+            Some(inner) => Client::Http(crate::http::Client::from_config(inner, config)?),
+            None => Client::from_config(config).await?,
+        };
+        #[cfg(not(http_backend))]
+        let client = Client::from_config(config).await?;
+        match client {
+            #[cfg(http_backend)]
+            Client::Http(mut http) => {
+                for (name, value) in self.headers {
+                    http = http.with_header(name, value);
+                }
+                if let Some(retry_policy) = self.retry_policy {
+                    http = http.with_retry_policy(retry_policy);
+                }
+                if let Some(provider) = self.token_provider {
+                    http = http.with_token_provider_boxed(provider);
+                }
+                Ok(Client::Http(http))
+            }
+            client => {
+                if !self.headers.is_empty() || self.retry_policy.is_some() {
+                    anyhow::bail!("Custom headers and retry policies require an HTTP backend");
+                }
+                #[cfg(http_backend)]
+                if self.token_provider.is_some() {
+                    anyhow::bail!("Token providers require an HTTP backend");
+                }
+                Ok(client)
+            }
+        }
+    }
+}
@@ -0,0 +1,330 @@
+//! `QueryCache` caches the results of queries, for read-heavy workloads on slowly changing data.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use fallible_iterator::FallibleIterator;
+use futures::future::BoxFuture;
+use sqlite3_parser::ast::{Cmd, Stmt};
+use sqlite3_parser::lexer::sql::Parser;
+
+use crate::{BatchResult, DatabaseClient, ResultSet, Statement, ValueKey};
+
+/// [DatabaseClient] which caches the results of `SELECT` statements, keyed by their
+/// SQL and parameters, for up to `ttl`. At most `capacity` results are kept,
+/// and the least recently used one is evicted to make room for a new one.
+///
+/// Every other statement, i.e. writes, DDL and transaction control, as well as all
+/// batches, bypasses the cache and clears it once executed. Only writes made through
+/// this client are noticed: changes made by other clients show up when entries expire.
+/// Queries calling non-deterministic functions, e.g. `random()` or `datetime('now')`,
+/// are cached like the others.
+///
+/// # Examples
+///
+/// ```
+/// # async fn run() {
+/// use libsql_client::{cache::QueryCache, DatabaseClient};
+/// use std::time::Duration;
+///
+/// let db = libsql_client::Client::in_memory().unwrap();
+/// let db = QueryCache::new(db, 1000, Duration::from_secs(60));
+/// db.execute("CREATE TABLE t(x)".into()).await.unwrap();
+/// let rs = db.execute("SELECT * FROM t".into()).await.unwrap();
+/// // Served from the cache
+/// let rs = db.execute("SELECT * FROM t".into()).await.unwrap();
+/// # }
+/// ```
+pub struct QueryCache<D> {
+    inner: D,
+    capacity: usize,
+    ttl: Duration,
+    state: Mutex<CacheState>,
+}
+
+type CacheKey = (String, Vec<ValueKey>);
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<CacheKey, CacheEntry>,
+    // Incremented on every access, to find the least recently used entry
+    clock: u64,
+    // Incremented by invalidate(), so that results read before it are not cached after it
+    generation: u64,
+}
+
+struct CacheEntry {
+    result: ResultSet,
+    inserted_at: Instant,
+    last_used: u64,
+}
+
+impl<D: std::fmt::Debug> std::fmt::Debug for QueryCache<D> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QueryCache")
+            .field("inner", &self.inner)
+            .field("capacity", &self.capacity)
+            .field("ttl", &self.ttl)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<D: DatabaseClient> QueryCache<D> {
+    /// Wraps a client, caching up to `capacity` results for `ttl` each.
+    pub fn new(inner: D, capacity: usize, ttl: Duration) -> Self {
+        Self {
+            inner,
+            capacity,
+            ttl,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Returns the wrapped client.
+    pub fn inner(&self) -> &D {
+        &self.inner
+    }
+
+    /// Returns the number of cached results, including expired ones not evicted yet.
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Clears the cache, e.g. after the data was changed by another client.
+    pub fn invalidate(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.entries.clear();
+        state.generation += 1;
+    }
+
+    fn generation(&self) -> u64 {
+        self.state.lock().unwrap().generation
+    }
+
+    fn get(&self, key: &CacheKey, now: Instant) -> Option<ResultSet> {
+        let mut state = self.state.lock().unwrap();
+        state.clock += 1;
+        let clock = state.clock;
+        let entry = state.entries.get_mut(key)?;
+        if now.duration_since(entry.inserted_at) >= self.ttl {
+            state.entries.remove(key);
+            return None;
+        }
+        entry.last_used = clock;
+        Some(entry.result.clone())
+    }
+
+    // Caches a result read in the given generation, unless the cache was invalidated since
+    fn insert(&self, key: CacheKey, result: ResultSet, now: Instant, generation: u64) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        if state.generation != generation {
+            return;
+        }
+        state.clock += 1;
+        let clock = state.clock;
+        if state.entries.len() >= self.capacity && !state.entries.contains_key(&key) {
+            let ttl = self.ttl;
+            state
+                .entries
+                .retain(|_, entry| now.duration_since(entry.inserted_at) < ttl);
+            if state.entries.len() >= self.capacity {
+                let least_recently_used = state
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.last_used)
+                    .map(|(key, _)| key.clone());
+                if let Some(key) = least_recently_used {
+                    state.entries.remove(&key);
+                }
+            }
+        }
+        state.entries.insert(
+            key,
+            CacheEntry {
+                result,
+                inserted_at: now,
+                last_used: clock,
+            },
+        );
+    }
+
+    async fn execute_cached(&self, stmt: Statement) -> Result<ResultSet> {
+        if !is_cacheable(&stmt.sql) {
+            let result = self.inner.execute(stmt).await;
+            self.invalidate();
+            return result;
+        }
+        let key = (
+            stmt.sql.clone(),
+            stmt.args.iter().cloned().map(ValueKey).collect(),
+        );
+        if let Some(result) = self.get(&key, Instant::now()) {
+            return Ok(result);
+        }
+        let generation = self.generation();
+        let result = self.inner.execute(stmt).await?;
+        self.insert(key, result.clone(), Instant::now(), generation);
+        Ok(result)
+    }
+}
+
+impl<D: DatabaseClient> DatabaseClient for QueryCache<D> {
+    fn execute(&self, stmt: Statement) -> BoxFuture<'_, Result<ResultSet>> {
+        Box::pin(self.execute_cached(stmt))
+    }
+
+    fn raw_batch(&self, stmts: Vec<Statement>) -> BoxFuture<'_, Result<BatchResult>> {
+        Box::pin(async move {
+            let result = self.inner.raw_batch(stmts).await;
+            self.invalidate();
+            result
+        })
+    }
+
+    fn batch(&self, stmts: Vec<Statement>) -> BoxFuture<'_, Result<Vec<ResultSet>>> {
+        Box::pin(async move {
+            let result = self.inner.batch(stmts).await;
+            self.invalidate();
+            result
+        })
+    }
+}
+
+// Only plain queries are cached: everything else may change the data, or the
+// state of a transaction which later queries would observe
+fn is_cacheable(sql: &str) -> bool {
+    let mut parser = Parser::new(sql.as_bytes());
+    let mut has_query = false;
+    loop {
+        match parser.next() {
+            Ok(None) => return has_query,
+            Ok(Some(Cmd::Stmt(Stmt::Select(_)))) => has_query = true,
+            Ok(Some(_)) | Err(_) => return false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_cacheable() {
+        assert!(is_cacheable("SELECT * FROM t WHERE x = ?"));
+        assert!(is_cacheable("WITH c AS (SELECT 1) SELECT * FROM c"));
+        assert!(!is_cacheable("INSERT INTO t VALUES (1) RETURNING *"));
+        assert!(!is_cacheable("BEGIN"));
+        assert!(!is_cacheable("SAVEPOINT a"));
+        assert!(!is_cacheable("EXPLAIN SELECT 1"));
+        assert!(!is_cacheable(""));
+    }
+
+    #[cfg(feature = "local_backend")]
+    #[test]
+    fn test_cache_invalidated_by_writes() {
+        let db = QueryCache::new(
+            crate::Client::in_memory().unwrap(),
+            2,
+            Duration::from_secs(60),
+        );
+        let execute = |sql: &str| futures::executor::block_on(db.execute(sql.into())).unwrap();
+        let count = |sql: &str| execute(sql).rows[0].try_get::<i64>(0).unwrap();
+        execute("CREATE TABLE t(x)");
+        assert_eq!(count("SELECT count(*) FROM t"), 0);
+        assert_eq!(db.len(), 1);
+
+        // Writes bypassing the cache go unnoticed
+        futures::executor::block_on(db.inner().execute("INSERT INTO t VALUES (1)")).unwrap();
+        assert_eq!(count("SELECT count(*) FROM t"), 0);
+
+        // Writes through the cache invalidate it
+        execute("INSERT INTO t VALUES (2)");
+        assert!(db.is_empty());
+        assert_eq!(count("SELECT count(*) FROM t"), 2);
+
+        // The least recently used entry is evicted
+        count("SELECT 1");
+        count("SELECT count(*) FROM t");
+        count("SELECT 2");
+        assert_eq!(db.len(), 2);
+        let state = db.state.lock().unwrap();
+        assert!(!state
+            .entries
+            .contains_key(&("SELECT 1".to_string(), Vec::new())));
+    }
+
+    #[cfg(feature = "local_backend")]
+    #[test]
+    fn test_cache_entries_expire() {
+        let db = QueryCache::new(
+            crate::Client::in_memory().unwrap(),
+            10,
+            Duration::from_secs(60),
+        );
+        let key: CacheKey = ("SELECT 1".to_string(), Vec::new());
+        let now = Instant::now();
+        let rs = futures::executor::block_on(db.inner().execute("SELECT 1")).unwrap();
+        db.insert(key.clone(), rs, now, db.generation());
+        assert!(db.get(&key, now + Duration::from_secs(59)).is_some());
+        assert!(db.get(&key, now + Duration::from_secs(60)).is_none());
+        assert!(db.is_empty());
+    }
+
+    #[test]
+    fn test_read_racing_invalidate_is_not_cached() {
+        // Answers queries once released, so that the cache is invalidated mid-read
+        struct GatedClient(Mutex<Option<futures::channel::oneshot::Receiver<()>>>);
+
+        impl DatabaseClient for GatedClient {
+            fn execute(&self, _stmt: Statement) -> BoxFuture<'_, Result<ResultSet>> {
+                let gate = self.0.lock().unwrap().take();
+                Box::pin(async move {
+                    if let Some(gate) = gate {
+                        gate.await?;
+                    }
+                    Ok(ResultSet {
+                        columns: vec![],
+                        rows: vec![],
+                        rows_affected: 0,
+                        last_insert_rowid: None,
+                    })
+                })
+            }
+
+            fn raw_batch(&self, _stmts: Vec<Statement>) -> BoxFuture<'_, Result<BatchResult>> {
+                Box::pin(async { Err(anyhow::anyhow!("GatedClient only executes statements")) })
+            }
+
+            fn batch(&self, _stmts: Vec<Statement>) -> BoxFuture<'_, Result<Vec<ResultSet>>> {
+                Box::pin(async { Err(anyhow::anyhow!("GatedClient only executes statements")) })
+            }
+        }
+
+        let (release, gate) = futures::channel::oneshot::channel();
+        let db = QueryCache::new(
+            GatedClient(Mutex::new(Some(gate))),
+            10,
+            Duration::from_secs(60),
+        );
+        let read = db.execute("SELECT * FROM t".into());
+        let write = async {
+            db.invalidate();
+            release.send(()).unwrap();
+        };
+        let (result, ()) = futures::executor::block_on(futures::future::join(read, write));
+        result.unwrap();
+        assert!(db.is_empty());
+        // Reads started after the invalidation are cached again
+        futures::executor::block_on(db.execute("SELECT * FROM t".into())).unwrap();
+        assert_eq!(db.len(), 1);
+    }
+}
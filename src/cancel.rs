@@ -0,0 +1,100 @@
+//! `CancellationToken` lets callers abort in-flight requests.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    wakers: Mutex<Vec<Waker>>,
+}
+
+/// A handle which cancels the requests it was passed to.
+///
+/// Clones share the same state, so a clone can be handed to whichever task
+/// decides that the results are no longer needed. The token is runtime-agnostic.
+///
+/// # Examples
+///
+/// ```
+/// # async fn run() {
+/// use libsql_client::CancellationToken;
+///
+/// let db = libsql_client::Client::in_memory().unwrap();
+/// let token = CancellationToken::new();
+/// token.cancel();
+/// assert!(db.execute_with_cancel("SELECT 1", &token).await.is_err());
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels all requests using this token. Cancelling twice is a no-op.
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        for waker in self.inner.wakers.lock().unwrap().drain(..) {
+            waker.wake();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Returns a future which completes once the token is cancelled.
+    pub fn cancelled(&self) -> Cancelled<'_> {
+        Cancelled { token: self }
+    }
+}
+
+/// Future returned by [`CancellationToken::cancelled()`].
+pub struct Cancelled<'a> {
+    token: &'a CancellationToken,
+}
+
+impl Future for Cancelled<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.token.is_cancelled() {
+            return Poll::Ready(());
+        }
+        self.token
+            .inner
+            .wakers
+            .lock()
+            .unwrap()
+            .push(cx.waker().clone());
+        // Re-check, in case cancel() ran before the waker was registered
+        if self.token.is_cancelled() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancel_wakes_waiters() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!token.is_cancelled());
+        std::thread::spawn(move || clone.cancel());
+        futures::executor::block_on(token.cancelled());
+        assert!(token.is_cancelled());
+    }
+}
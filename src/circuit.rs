@@ -0,0 +1,208 @@
+//! `CircuitBreakerPolicy` stops sending requests to a server which keeps failing.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Opt-in protection against a failing server, see [`crate::http::Client::with_circuit_breaker()`].
+///
+/// After `failure_threshold` consecutive failures within `window`, the circuit opens:
+/// requests fail immediately with [crate::Error::CircuitOpen] instead of waiting for the
+/// server. Once `cooldown` passes, a single probe request is let through. If it succeeds,
+/// the circuit closes, otherwise it stays open for another `cooldown`.
+///
+/// Failures are network errors and responses with status 5xx or 429. Errors reported
+/// by a working server, e.g. failing SQL or rejected credentials, don't count.
+///
+/// # Examples
+///
+/// ```
+/// use libsql_client::circuit::CircuitBreakerPolicy;
+/// use std::time::Duration;
+///
+/// let policy = CircuitBreakerPolicy::new(5).with_cooldown(Duration::from_secs(10));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CircuitBreakerPolicy {
+    /// How many consecutive failures open the circuit.
+    pub failure_threshold: u32,
+    /// Time within which the failures must happen to open the circuit.
+    pub window: Duration,
+    /// How long the circuit stays open before a probe request is let through.
+    pub cooldown: Duration,
+}
+
+impl CircuitBreakerPolicy {
+    /// Creates a policy opening the circuit after `failure_threshold` consecutive failures
+    /// within a minute, and probing the server every 30 seconds while it's open.
+    pub fn new(failure_threshold: u32) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            window: Duration::from_secs(60),
+            cooldown: Duration::from_secs(30),
+        }
+    }
+
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    failures: u32,
+    first_failure: Option<Instant>,
+    opened_at: Option<Instant>,
+    // Set while the probe request of an open circuit is in flight
+    probing: bool,
+}
+
+// State of the circuit, shared by all clones of a client
+#[derive(Debug)]
+pub(crate) struct CircuitBreaker {
+    policy: CircuitBreakerPolicy,
+    state: Mutex<State>,
+}
+
+impl CircuitBreaker {
+    pub(crate) fn new(policy: CircuitBreakerPolicy) -> Self {
+        Self {
+            policy,
+            state: Mutex::default(),
+        }
+    }
+
+    // Fails if the circuit is open, unless the request may probe the server
+    pub(crate) fn check(&self) -> anyhow::Result<()> {
+        self.check_at(Instant::now())
+    }
+
+    pub(crate) fn record(&self, e: Option<&anyhow::Error>) {
+        self.record_at(e.is_some_and(is_server_failure), Instant::now())
+    }
+
+    fn check_at(&self, now: Instant) -> anyhow::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        match state.opened_at {
+            None => Ok(()),
+            Some(opened_at) if now.duration_since(opened_at) < self.policy.cooldown => {
+                Err(crate::Error::CircuitOpen.into())
+            }
+            Some(_) => {
+                // Restarting the cooldown lets a single probe through, and another one
+                // later if the probe's outcome never gets recorded, e.g. because it was dropped
+                tracing::debug!("Probing the server through the open circuit");
+                state.opened_at = Some(now);
+                state.probing = true;
+                Ok(())
+            }
+        }
+    }
+
+    fn record_at(&self, failed: bool, now: Instant) {
+        let mut state = self.state.lock().unwrap();
+        if !failed {
+            *state = State::default();
+            return;
+        }
+        if state.probing {
+            tracing::debug!("The probe failed, keeping the circuit open");
+            state.probing = false;
+            state.opened_at = Some(now);
+            return;
+        }
+        match state.first_failure {
+            Some(first) if now.duration_since(first) <= self.policy.window => state.failures += 1,
+            _ => {
+                state.first_failure = Some(now);
+                state.failures = 1;
+            }
+        }
+        if state.failures >= self.policy.failure_threshold {
+            tracing::warn!(
+                "Opening the circuit after {} consecutive failures",
+                state.failures
+            );
+            state.opened_at = Some(now);
+            state.failures = 0;
+            state.first_failure = None;
+        }
+    }
+}
+
+// Whether the error means that the server is unreachable or failing,
+// as opposed to rejecting the request
+fn is_server_failure(e: &anyhow::Error) -> bool {
+    match e.downcast_ref::<crate::Error>() {
+        Some(crate::Error::Http { status, .. }) => *status >= 500 || *status == 429,
+        Some(_) => false,
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_circuit_opens_and_probes() {
+        let breaker = CircuitBreaker::new(
+            CircuitBreakerPolicy::new(2)
+                .with_window(Duration::from_secs(10))
+                .with_cooldown(Duration::from_secs(30)),
+        );
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        breaker.record_at(true, at(0));
+        assert!(breaker.check_at(at(1)).is_ok());
+        breaker.record_at(true, at(2));
+        let e = breaker.check_at(at(3)).unwrap_err();
+        assert!(matches!(
+            e.downcast_ref::<crate::Error>(),
+            Some(crate::Error::CircuitOpen)
+        ));
+        // After the cooldown, a single probe is let through
+        assert!(breaker.check_at(at(33)).is_ok());
+        assert!(breaker.check_at(at(33)).is_err());
+        breaker.record_at(true, at(34));
+        assert!(breaker.check_at(at(35)).is_err());
+        assert!(breaker.check_at(at(64)).is_ok());
+        breaker.record_at(false, at(65));
+        assert!(breaker.check_at(at(65)).is_ok());
+    }
+
+    #[test]
+    fn test_failures_outside_window_dont_add_up() {
+        let breaker =
+            CircuitBreaker::new(CircuitBreakerPolicy::new(2).with_window(Duration::from_secs(10)));
+        let start = Instant::now();
+        breaker.record_at(true, start);
+        breaker.record_at(true, start + Duration::from_secs(11));
+        assert!(breaker.check_at(start + Duration::from_secs(12)).is_ok());
+    }
+
+    #[test]
+    fn test_server_failures() {
+        let http = |status| -> anyhow::Error {
+            crate::Error::Http {
+                status,
+                message: String::new(),
+            }
+            .into()
+        };
+        assert!(is_server_failure(&http(503)));
+        assert!(is_server_failure(&anyhow::anyhow!("connection refused")));
+        assert!(!is_server_failure(&http(400)));
+        assert!(!is_server_failure(
+            &crate::Error::Unauthorized {
+                message: String::new()
+            }
+            .into()
+        ));
+    }
+}
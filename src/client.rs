@@ -1,7 +1,46 @@
 //! [Client] is the main structure to interact with the database.
 use anyhow::Result;
 
-use crate::{proto, BatchResult, ResultSet, Statement, SyncTransaction, Transaction};
+use crate::{
+    proto, BatchResult, ProtocolVersion, ResultSet, Statement, SyncTransaction, Transaction,
+};
+
+/// Environment variable holding the database URL, read by the `from_env()` constructors.
+pub(crate) const URL_ENV_VAR: &str = "LIBSQL_CLIENT_URL";
+/// Environment variable holding the optional auth token, read by the `from_env()` constructors.
+pub(crate) const TOKEN_ENV_VAR: &str = "LIBSQL_CLIENT_TOKEN";
+
+/// Environment variable selecting the backend used by [`Client::from_config()`].
+pub(crate) const BACKEND_ENV_VAR: &str = "LIBSQL_CLIENT_BACKEND";
+
+// Reads the database URL from the environment
+pub(crate) fn url_from_env() -> Result<String> {
+    std::env::var(URL_ENV_VAR).map_err(|_| {
+        anyhow::anyhow!("{URL_ENV_VAR} variable should point to your libSQL/sqld database")
+    })
+}
+
+// HTTP client talking to sqld over the Unix domain socket at the path of a `unix://` URL
+#[cfg(all(unix, feature = "unix_backend"))]
+fn unix_client(mut config: Config) -> Result<crate::http::Client> {
+    let inner = crate::http::InnerClient::Unix(crate::unix::HttpClient::new(config.url.path()));
+    // The host only ends up in the Host header
+    config.url = url::Url::parse("http://localhost")?;
+    crate::http::Client::from_config(inner, config)
+}
+
+fn unix_backend_disabled() -> anyhow::Error {
+    crate::Error::Misuse(
+        "Unix domain sockets need the `unix_backend` feature, which is only available on Unix"
+            .to_string(),
+    )
+    .into()
+}
+
+// Error of requests sent through Client::Default, which has no backend to send them to
+fn no_backend() -> anyhow::Error {
+    crate::Error::Misuse("no backend feature enabled".to_string()).into()
+}
 
 static TRANSACTION_IDS: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
 
@@ -12,11 +51,7 @@ static TRANSACTION_IDS: std::sync::atomic::AtomicU64 = std::sync::atomic::Atomic
 pub enum Client {
     #[cfg(feature = "local_backend")]
     Local(crate::local::Client),
-    #[cfg(any(
-        feature = "reqwest_backend",
-        feature = "workers_backend",
-        feature = "spin_backend"
-    ))]
+    #[cfg(http_backend)]
     Http(crate::http::Client),
     #[cfg(feature = "hrana_backend")]
     Hrana(crate::hrana::Client),
@@ -35,6 +70,11 @@ impl Client {
     /// Executes a batch of independent SQL statements.
     ///
     /// For a version in which statements execute transactionally, see [`Client::batch()`]
+    ///
+    /// Each statement commits on its own. If the connection drops before the results
+    /// arrive, remote backends can't tell which statements were applied, see
+    /// [crate::Error::BatchInterrupted].
+    ///
     /// # Arguments
     /// * `stmts` - SQL statements
     ///
@@ -55,14 +95,14 @@ impl Client {
         &self,
         stmts: impl IntoIterator<Item = impl Into<Statement> + Send> + Send,
     ) -> Result<BatchResult> {
+        let stmts: Vec<Statement> = stmts.into_iter().map(Into::into).collect();
+        for stmt in &stmts {
+            stmt.check_placeholders()?;
+        }
         match self {
             #[cfg(feature = "local_backend")]
             Self::Local(l) => l.raw_batch(stmts),
-            #[cfg(any(
-                feature = "reqwest_backend",
-                feature = "workers_backend",
-                feature = "spin_backend"
-            ))]
+            #[cfg(http_backend)]
             Self::Http(r) => r.raw_batch(stmts).await,
             #[cfg(feature = "hrana_backend")]
             Self::Hrana(h) => h.raw_batch(stmts).await,
@@ -70,6 +110,110 @@ impl Client {
         }
     }
 
+    /// Executes a batch whose steps may be conditioned on the outcomes of earlier steps,
+    /// in a single round trip. Steps whose condition doesn't hold are skipped,
+    /// and have neither a result nor an error in the returned [BatchResult].
+    /// See [BatchBuilder](crate::BatchBuilder) for an example.
+    pub async fn execute_batch(&self, batch: crate::BatchBuilder) -> Result<BatchResult> {
+        for (stmt, _) in &batch.steps {
+            stmt.check_placeholders()?;
+        }
+        match self {
+            #[cfg(feature = "local_backend")]
+            Self::Local(l) => l.execute_batch(batch),
+            #[cfg(http_backend)]
+            Self::Http(r) => r.execute_batch(batch).await,
+            #[cfg(feature = "hrana_backend")]
+            Self::Hrana(h) => h.execute_batch(batch).await,
+            _ => Err(no_backend()),
+        }
+    }
+
+    /// Executes a batch of SQL statements independently, like [`Client::raw_batch()`],
+    /// and returns one entry per statement, in order: its result set,
+    /// or the error which made it fail.
+    ///
+    /// The outer `Result` only fails if the batch as a whole could not be executed.
+    /// # Arguments
+    /// * `stmts` - SQL statements
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # async fn run() {
+    /// let db = libsql_client::Client::in_memory().unwrap();
+    /// # db.execute("create table foo(bar text not null)").await.unwrap();
+    /// let results = db.batch_results([
+    ///   "insert into foo(bar) values ('bar')",
+    ///   "insert into foo(bar) values (null)",
+    /// ]).await.unwrap();
+    /// assert!(results[0].is_ok());
+    /// assert!(results[1].is_err());
+    /// # }
+    /// ```
+    pub async fn batch_results(
+        &self,
+        stmts: impl IntoIterator<Item = impl Into<Statement> + Send> + Send,
+    ) -> Result<Vec<Result<ResultSet>>> {
+        let stmts: Vec<Statement> = stmts.into_iter().map(Into::into).collect();
+        let steps = stmts.len();
+        let batch_result = self.raw_batch(stmts).await?;
+        Ok(crate::utils::split_batch_result(batch_result, steps))
+    }
+
+    /// Executes a batch like [`Client::execute_batch()`], and returns the result set
+    /// or error of each step, which can be looked up by index or by the label
+    /// given with [`BatchBuilder::label()`](crate::BatchBuilder::label).
+    /// See [MultiResultSet](crate::MultiResultSet) for an example.
+    ///
+    /// The outer `Result` only fails if the batch as a whole could not be executed.
+    pub async fn batch_query(&self, batch: crate::BatchBuilder) -> Result<crate::MultiResultSet> {
+        let steps = batch.len();
+        let labels = batch.labels.clone();
+        let batch_result = self.execute_batch(batch).await?;
+        Ok(crate::MultiResultSet::new(
+            crate::utils::split_batch_result(batch_result, steps),
+            labels,
+        ))
+    }
+
+    /// Executes a batch of SQL statements independently, like [`Client::raw_batch()`],
+    /// and streams the outcome of each statement along with its position in `stmts`,
+    /// e.g. to report the progress of a long migration.
+    ///
+    /// The steps are yielded in order once the batch response arrives. If the batch
+    /// as a whole fails, its error is yielded once, at index 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # async fn run() {
+    /// use futures::StreamExt;
+    ///
+    /// let db = libsql_client::Client::in_memory().unwrap();
+    /// let mut steps = db.raw_batch_stream(["select 1", "select x"]);
+    /// while let Some((index, result)) = steps.next().await {
+    ///     println!("step {index}: {}", if result.is_ok() { "ok" } else { "failed" });
+    /// }
+    /// # }
+    /// ```
+    pub fn raw_batch_stream(
+        &self,
+        stmts: impl IntoIterator<Item = impl Into<Statement> + Send> + Send,
+    ) -> impl futures::Stream<Item = (usize, Result<proto::StmtResult>)> + '_ {
+        use futures::StreamExt;
+
+        let stmts: Vec<Statement> = stmts.into_iter().map(Into::into).collect();
+        let steps = stmts.len();
+        futures::stream::once(self.raw_batch(stmts)).flat_map(move |batch_result| {
+            let results = match batch_result {
+                Ok(batch_result) => crate::utils::split_batch_steps(batch_result, steps),
+                Err(e) => vec![Err(e)],
+            };
+            futures::stream::iter(results.into_iter().enumerate())
+        })
+    }
+
     /// Transactionally executes a batch of SQL statements.
     ///
     /// For a version in which statements can fail or succeed independently, see [`Client::raw_batch()`]
@@ -163,6 +307,16 @@ impl Client {
 
     /// Executes a single SQL statement
     ///
+    /// The statement never runs inside a transaction, even if one is open on this client:
+    /// use [`Transaction::execute()`] for that. Remote backends log a warning in debug builds
+    /// when a statement is executed outside of open transactions, as it's a common mistake.
+    ///
+    /// If the number of bound parameters doesn't match the `?` placeholders, the statement
+    /// isn't sent and the error is [crate::Error::Misuse].
+    ///
+    /// If the server rejects a bound parameter because of its type, the error
+    /// is reported as [crate::Error::TypeMismatch], listing the types of all bound parameters.
+    ///
     /// # Arguments
     /// * `stmt` - SQL statements
     ///
@@ -177,18 +331,370 @@ impl Client {
     /// # }
     /// ```
     pub async fn execute(&self, stmt: impl Into<Statement> + Send) -> Result<ResultSet> {
-        match self {
+        let stmt: Statement = stmt.into();
+        stmt.check_placeholders()?;
+        let arg_types = stmt.arg_types();
+        let result = match self {
             #[cfg(feature = "local_backend")]
             Self::Local(l) => l.execute(stmt),
-            #[cfg(any(
-                feature = "reqwest_backend",
-                feature = "workers_backend",
-                feature = "spin_backend"
-            ))]
+            #[cfg(http_backend)]
             Self::Http(r) => r.execute(stmt).await,
             #[cfg(feature = "hrana_backend")]
             Self::Hrana(h) => h.execute(stmt).await,
             _ => panic!("Must enable at least one feature"),
+        };
+        result.map_err(|e| crate::utils::with_arg_types_on_mismatch(e, arg_types))
+    }
+
+    /// Executes a statement like [`Client::execute()`], without consuming it,
+    /// so that a statement built once can be executed many times, e.g. in a loop.
+    /// The SQL and parameters are only copied where the backend needs to own them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # async fn run() {
+    /// use libsql_client::Statement;
+    ///
+    /// let db = libsql_client::Client::in_memory().unwrap();
+    /// db.execute("create table foo(bar integer)").await.unwrap();
+    /// let stmt = Statement::with_args("insert into foo values (?)", &[1]);
+    /// for _ in 0..3 {
+    ///     db.execute_ref(&stmt).await.unwrap();
+    /// }
+    /// # }
+    /// ```
+    pub async fn execute_ref(&self, stmt: &Statement) -> Result<ResultSet> {
+        stmt.check_placeholders()?;
+        let result = match self {
+            #[cfg(feature = "local_backend")]
+            Self::Local(l) => l.execute_ref(stmt),
+            #[cfg(http_backend)]
+            Self::Http(r) => r.execute_ref(stmt).await,
+            #[cfg(feature = "hrana_backend")]
+            Self::Hrana(h) => h.execute_ref(stmt).await,
+            _ => Err(no_backend()),
+        };
+        result.map_err(|e| crate::utils::with_arg_types_on_mismatch(e, stmt.arg_types()))
+    }
+
+    /// Returns the parameters and result columns of a statement, without executing it,
+    /// e.g. to build typed query layers or validate queries against the live schema.
+    ///
+    /// Only HTTP backends support describing statements: the others return an error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # async fn f() -> anyhow::Result<()> {
+    /// let db = libsql_client::Client::from_env().await?;
+    /// let description = db.describe("SELECT name FROM users WHERE id = :id").await?;
+    /// assert_eq!(description.param_count(), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn describe(&self, sql: impl Into<String>) -> Result<crate::StmtDescription> {
+        match self {
+            #[cfg(http_backend)]
+            Self::Http(r) => r.describe(sql).await,
+            _ => anyhow::bail!("Describing statements is only supported by the HTTP backends"),
+        }
+    }
+
+    /// Sets the default consistency of reads, for databases served by replicas.
+    /// With [crate::ConsistencyMode::Strong], reads observe the writes previously made
+    /// through this client, e.g. a counter read right after its increment.
+    ///
+    /// Only the HTTP backend routes requests through replicas: the other backends
+    /// always read their own writes and are returned unchanged.
+    pub fn with_consistency(self, consistency: crate::ConsistencyMode) -> Self {
+        match self {
+            #[cfg(http_backend)]
+            Self::Http(r) => Self::Http(r.with_consistency(consistency)),
+            client => client,
+        }
+    }
+
+    /// Sets the User-Agent sent with every request, see [`crate::http::Client::with_user_agent()`].
+    ///
+    /// Only HTTP backends send a User-Agent: the other backends are returned unchanged.
+    pub fn with_user_agent(self, user_agent: impl Into<String>) -> Self {
+        match self {
+            #[cfg(http_backend)]
+            Self::Http(r) => Self::Http(r.with_user_agent(user_agent)),
+            client => client,
+        }
+    }
+
+    /// Limits the size of the server's responses,
+    /// see [`crate::http::Client::with_max_response_bytes()`].
+    ///
+    /// Only HTTP backends support the limit: the other backends are returned unchanged.
+    pub fn with_max_response_bytes(self, max_response_bytes: usize) -> Self {
+        match self {
+            #[cfg(http_backend)]
+            Self::Http(r) => Self::Http(r.with_max_response_bytes(max_response_bytes)),
+            client => client,
+        }
+    }
+
+    /// Stores frequently executed SQL texts on the server,
+    /// see [`crate::http::Client::with_sql_cache()`].
+    ///
+    /// Only HTTP backends support storing SQL texts: the other backends are returned unchanged.
+    pub fn with_sql_cache(self, threshold: u32, capacity: usize) -> Self {
+        match self {
+            #[cfg(http_backend)]
+            Self::Http(r) => Self::Http(r.with_sql_cache(threshold, capacity)),
+            client => client,
+        }
+    }
+
+    /// Returns the counters of the cache enabled with [`Client::with_sql_cache()`],
+    /// or `None` if it's disabled or for backends which don't support it.
+    #[cfg(http_backend)]
+    pub fn sql_cache_stats(&self) -> Option<crate::sql_cache::SqlCacheStats> {
+        match self {
+            Self::Http(r) => r.sql_cache_stats(),
+            _ => None,
+        }
+    }
+
+    /// Enables a circuit breaker for requests to a failing server,
+    /// see [`crate::http::Client::with_circuit_breaker()`].
+    ///
+    /// Only HTTP backends support circuit breakers: the other backends are returned unchanged.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_circuit_breaker(self, policy: crate::circuit::CircuitBreakerPolicy) -> Self {
+        match self {
+            #[cfg(http_backend)]
+            Self::Http(r) => Self::Http(r.with_circuit_breaker(policy)),
+            client => client,
+        }
+    }
+
+    /// Targets a namespace of a multi-tenant sqld, see [`crate::http::Client::with_namespace()`].
+    ///
+    /// Only HTTP backends support namespaces: the other backends are returned unchanged.
+    pub fn with_namespace(self, namespace: impl Into<String>) -> Self {
+        match self {
+            #[cfg(http_backend)]
+            Self::Http(r) => Self::Http(r.with_namespace(namespace)),
+            client => client,
+        }
+    }
+
+    /// Executes a statement like [`Client::execute()`], with the given consistency
+    /// instead of the default set by [`Client::with_consistency()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # async fn run() {
+    /// use libsql_client::ConsistencyMode;
+    ///
+    /// let db = libsql_client::Client::in_memory().unwrap();
+    /// db.execute_with_consistency("SELECT 1", ConsistencyMode::Strong)
+    ///     .await
+    ///     .unwrap();
+    /// # }
+    /// ```
+    pub async fn execute_with_consistency(
+        &self,
+        stmt: impl Into<Statement> + Send,
+        consistency: crate::ConsistencyMode,
+    ) -> Result<ResultSet> {
+        match self {
+            #[cfg(http_backend)]
+            Self::Http(r) => {
+                let stmt: Statement = stmt.into();
+                stmt.check_placeholders()?;
+                let arg_types = stmt.arg_types();
+                r.execute_with_consistency(stmt, consistency)
+                    .await
+                    .map_err(|e| crate::utils::with_arg_types_on_mismatch(e, arg_types))
+            }
+            _ => self.execute(stmt).await,
+        }
+    }
+
+    /// Executes a statement like [`Client::execute()`], sending `request_id` along
+    /// with the request, e.g. the id of a distributed trace, so that the server's
+    /// logs can be matched with the client's.
+    /// See [`crate::http::Client::with_request_id()`].
+    ///
+    /// Only HTTP backends send the id: the other backends execute the statement as usual.
+    pub async fn execute_with_request_id(
+        &self,
+        stmt: impl Into<Statement> + Send,
+        request_id: impl Into<String>,
+    ) -> Result<ResultSet> {
+        match self {
+            #[cfg(http_backend)]
+            Self::Http(r) => {
+                Self::Http(r.clone().with_request_id(request_id))
+                    .execute(stmt)
+                    .await
+            }
+            _ => self.execute(stmt).await,
+        }
+    }
+
+    /// Executes a batch like [`Client::batch()`], sending `request_id` along with the request.
+    /// See [`Client::execute_with_request_id()`].
+    pub async fn batch_with_request_id<I: IntoIterator<Item = impl Into<Statement> + Send> + Send>(
+        &self,
+        stmts: I,
+        request_id: impl Into<String>,
+    ) -> Result<Vec<ResultSet>>
+    where
+        <I as IntoIterator>::IntoIter: Send,
+    {
+        match self {
+            #[cfg(http_backend)]
+            Self::Http(r) => {
+                Self::Http(r.clone().with_request_id(request_id))
+                    .batch(stmts)
+                    .await
+            }
+            _ => self.batch(stmts).await,
+        }
+    }
+
+    /// Executes a statement like [`Client::execute()`], unless `token` is cancelled first,
+    /// in which case it returns [crate::Error::Cancelled].
+    ///
+    /// Cancelling drops the in-flight request: the HTTP request is aborted, and the
+    /// Hrana stream executing the statement is closed, so the server can stop working on it.
+    /// With [`crate::hrana::Client::with_stream_reuse()`], that's the shared stream,
+    /// which is reopened by the next statement.
+    /// The statement runs outside of any transaction, so no transaction state is left
+    /// half-updated by a cancel. The local backend executes synchronously and can only be
+    /// cancelled before it starts.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # async fn run() {
+    /// let db = libsql_client::Client::in_memory().unwrap();
+    /// let token = libsql_client::CancellationToken::new();
+    /// db.execute_with_cancel("SELECT 1", &token).await.unwrap();
+    /// # }
+    /// ```
+    pub async fn execute_with_cancel(
+        &self,
+        stmt: impl Into<Statement> + Send,
+        token: &crate::CancellationToken,
+    ) -> Result<ResultSet> {
+        if token.is_cancelled() {
+            return Err(crate::Error::Cancelled.into());
+        }
+        let execute = std::pin::pin!(self.execute(stmt));
+        let cancelled = std::pin::pin!(token.cancelled());
+        match futures::future::select(execute, cancelled).await {
+            futures::future::Either::Left((result, _)) => result,
+            futures::future::Either::Right(((), _)) => {
+                tracing::debug!("Request cancelled");
+                #[cfg(feature = "hrana_backend")]
+                if let Self::Hrana(h) = self {
+                    h.drop_default_stream();
+                }
+                Err(crate::Error::Cancelled.into())
+            }
+        }
+    }
+
+    /// Returns a handle whose requests fail with [crate::Error::Timeout] once `deadline`
+    /// has passed, so that a multi-step operation as a whole takes bounded time.
+    /// See [crate::Deadline] for details.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_deadline(&self, deadline: std::time::Instant) -> crate::Deadline<'_> {
+        crate::Deadline::new(self, deadline)
+    }
+
+    /// Executes independent statements concurrently, with at most `concurrency` of them
+    /// in flight at a time. Unlike [`Client::batch()`], the statements are not atomic:
+    /// each one succeeds or fails on its own. Results are returned in input order.
+    ///
+    /// A `concurrency` of 0 is treated as 1. The local backend executes synchronously,
+    /// so its statements run one after another regardless.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # async fn run() {
+    /// let db = libsql_client::Client::in_memory().unwrap();
+    /// let results = db.execute_all(["select 1", "select 2", "select x"], 2).await;
+    /// assert_eq!(results.len(), 3);
+    /// assert_eq!(results[1].as_ref().unwrap().rows[0].try_get::<usize>(0).unwrap(), 2);
+    /// assert!(results[2].is_err());
+    /// # }
+    /// ```
+    pub async fn execute_all<I>(&self, stmts: I, concurrency: usize) -> Vec<Result<ResultSet>>
+    where
+        I: IntoIterator,
+        I::Item: Into<Statement> + Send,
+    {
+        use futures::StreamExt;
+
+        futures::stream::iter(stmts)
+            .map(|stmt| self.execute(stmt))
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    /// Establishes the connection to the database ahead of the first request,
+    /// so that it doesn't pay for the handshakes. Meant for the initialization
+    /// code of serverless functions, where cold starts are common.
+    ///
+    /// The HTTP backend sends a `SELECT 1` once, later calls return immediately.
+    /// The hrana backend opens a stream, which is kept for later requests if
+    /// stream reuse is enabled. Local databases need no warmup.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # async fn run() {
+    /// let db = libsql_client::Client::in_memory().unwrap();
+    /// db.warmup().await.unwrap();
+    /// # }
+    /// ```
+    pub async fn warmup(&self) -> Result<()> {
+        match self {
+            #[cfg(feature = "local_backend")]
+            Self::Local(l) => l.warmup(),
+            #[cfg(http_backend)]
+            Self::Http(r) => r.warmup().await,
+            #[cfg(feature = "hrana_backend")]
+            Self::Hrana(h) => h.warmup().await,
+            _ => Err(no_backend()),
+        }
+    }
+
+    /// Checks that the database is reachable and accepts our credentials,
+    /// doing as little work as the backend allows. Useful for readiness probes.
+    ///
+    /// Authentication failures are reported as [crate::Error::Unauthorized],
+    /// which makes them distinguishable from network failures.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # async fn run() {
+    /// let db = libsql_client::Client::in_memory().unwrap();
+    /// db.ping().await.unwrap();
+    /// # }
+    /// ```
+    pub async fn ping(&self) -> Result<()> {
+        match self {
+            #[cfg(feature = "local_backend")]
+            Self::Local(l) => l.ping(),
+            #[cfg(http_backend)]
+            Self::Http(r) => r.ping().await,
+            #[cfg(feature = "hrana_backend")]
+            Self::Hrana(h) => h.ping().await,
+            _ => Err(no_backend()),
         }
     }
 
@@ -211,37 +717,132 @@ impl Client {
         Transaction::new(self, id).await
     }
 
+    /// Creates an interactive transaction which defers BEGIN until its first statement.
+    /// A transaction which never executes a statement doesn't hit the server at all,
+    /// which saves round trips for conditionally empty transactions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # async fn run() {
+    /// let db = libsql_client::Client::in_memory().unwrap();
+    /// let tx = db.deferred_transaction();
+    /// tx.commit().await.unwrap(); // nothing was sent to the database
+    /// # }
+    /// ```
+    pub fn deferred_transaction(&self) -> Transaction {
+        let id = TRANSACTION_IDS.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        Transaction::deferred(self, id)
+    }
+
+    /// Creates an interactive transaction which only reads from the database.
+    /// Statements which may write, i.e. anything but queries, `EXPLAIN` and savepoints,
+    /// fail with [crate::Error::Misuse] before being sent, so DDL and DML inside it fail fast.
+    /// So does a plain `ROLLBACK`, which would end the transaction behind its back;
+    /// `ROLLBACK TO` a savepoint is allowed.
+    ///
+    /// BEGIN is deferred until the first statement. Since the transaction never writes,
+    /// a replica can serve it without forwarding it to the primary.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # async fn run() {
+    /// let db = libsql_client::Client::in_memory().unwrap();
+    /// # db.execute("create table foo(bar text)").await.unwrap();
+    /// let tx = db.read_transaction();
+    /// tx.execute("select * from foo").await.unwrap();
+    /// assert!(tx.execute("delete from foo").await.is_err());
+    /// tx.commit().await.unwrap();
+    /// # }
+    /// ```
+    pub fn read_transaction(&self) -> Transaction {
+        self.deferred_transaction().read_only()
+    }
+
+    /// Returns the Hrana protocol version used to talk to the server,
+    /// or `None` for backends which don't use a versioned HTTP endpoint.
+    pub fn protocol_version(&self) -> Option<ProtocolVersion> {
+        match self {
+            #[cfg(http_backend)]
+            Self::Http(r) => Some(r.protocol_version()),
+            _ => None,
+        }
+    }
+
+    /// Returns the newest Hrana protocol version supported by the server, which
+    /// lets applications enable features depending on server capabilities.
+    /// The server is probed on the first call and the result is cached.
+    ///
+    /// Returns `None` for the local backend, which has no server, and for the Hrana backend,
+    /// whose version is negotiated by the WebSocket handshake when connecting.
+    pub async fn server_version(&self) -> Result<Option<ProtocolVersion>> {
+        match self {
+            #[cfg(http_backend)]
+            Self::Http(r) => r.server_version().await.map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the highest replication frame number reported by the server,
+    /// or `None` if it didn't report any, or for backends which don't talk to sqld over HTTP.
+    pub fn last_frame_no(&self) -> Option<u64> {
+        match self {
+            #[cfg(http_backend)]
+            Self::Http(r) => r.last_frame_no(),
+            _ => None,
+        }
+    }
+
+    /// Waits until the server reports a replication frame number of at least `frame_no`,
+    /// e.g. to read your own writes from a replica. Only supported over HTTP,
+    /// with servers which report frame numbers.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn wait_for_frame(&self, frame_no: u64, timeout: std::time::Duration) -> Result<()> {
+        match self {
+            #[cfg(http_backend)]
+            Self::Http(r) => r.wait_for_frame(frame_no, timeout).await,
+            _ => anyhow::bail!("Waiting for replication frames is only supported over HTTP"),
+        }
+    }
+
+    /// Returns the counters of requests sent by this client,
+    /// or `None` for backends which don't collect metrics.
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> Option<crate::metrics::MetricsSnapshot> {
+        match self {
+            #[cfg(http_backend)]
+            Self::Http(r) => Some(r.metrics()),
+            _ => None,
+        }
+    }
+
     pub(crate) async fn execute_in_transaction(
         &self,
         tx_id: u64,
         stmt: Statement,
     ) -> Result<ResultSet> {
-        match self {
+        stmt.check_placeholders()?;
+        let arg_types = stmt.arg_types();
+        let result = match self {
             #[cfg(feature = "local_backend")]
             Self::Local(l) => l.execute_in_transaction(tx_id, stmt),
-            #[cfg(any(
-                feature = "reqwest_backend",
-                feature = "workers_backend",
-                feature = "spin_backend"
-            ))]
-            Self::Http(r) => r.execute_in_transaction(tx_id, stmt).await,
+            #[cfg(http_backend)]
+            Self::Http(r) => r.execute_in_tx(tx_id, stmt).await,
             #[cfg(feature = "hrana_backend")]
             Self::Hrana(h) => h.execute_in_transaction(tx_id, stmt).await,
 
             _ => panic!("Must enable at least one feature"),
-        }
+        };
+        result.map_err(|e| crate::utils::with_arg_types_on_mismatch(e, arg_types))
     }
 
     pub(crate) async fn commit_transaction(&self, tx_id: u64) -> Result<()> {
         match self {
             #[cfg(feature = "local_backend")]
             Self::Local(l) => l.commit_transaction(tx_id),
-            #[cfg(any(
-                feature = "reqwest_backend",
-                feature = "workers_backend",
-                feature = "spin_backend"
-            ))]
-            Self::Http(r) => r.commit_transaction(tx_id).await,
+            #[cfg(http_backend)]
+            Self::Http(r) => r.commit_tx(tx_id).await,
             #[cfg(feature = "hrana_backend")]
             Self::Hrana(h) => h.commit_transaction(tx_id).await,
 
@@ -253,12 +854,8 @@ impl Client {
         match self {
             #[cfg(feature = "local_backend")]
             Self::Local(l) => l.rollback_transaction(tx_id),
-            #[cfg(any(
-                feature = "reqwest_backend",
-                feature = "workers_backend",
-                feature = "spin_backend"
-            ))]
-            Self::Http(r) => r.rollback_transaction(tx_id).await,
+            #[cfg(http_backend)]
+            Self::Http(r) => r.rollback_tx(tx_id).await,
             #[cfg(feature = "hrana_backend")]
             Self::Hrana(h) => h.rollback_transaction(tx_id).await,
 
@@ -283,22 +880,41 @@ impl Client {
         Ok(Client::Local(crate::local::Client::in_memory()?))
     }
 
+    /// Creates a client backed by a [MockClient](crate::mock::MockClient),
+    /// which answers statements with programmed responses instead of contacting a server.
+    #[cfg(feature = "mock")]
+    pub fn mock(mock: crate::mock::MockClient) -> Client {
+        let inner = crate::http::InnerClient::Mock(mock);
+        Client::Http(crate::http::Client::new(inner, "http://mock/", ""))
+    }
+
+    /// Starts building a client for the database at `url`, see [ClientBuilder](crate::ClientBuilder).
+    pub fn builder(url: impl Into<String>) -> crate::ClientBuilder {
+        crate::ClientBuilder::new(url)
+    }
+
     /// Establishes a database client based on [Config] struct
     ///
+    /// With the `unix_backend` feature, `unix:///path/to/sqld.sock` URLs connect
+    /// to a co-located sqld over its Unix domain socket, see `unix::HttpClient`.
+    ///
     /// # Examples
     ///
     /// ```
     /// # async fn f() {
     /// # use libsql_client::Config;
-    /// let config = Config {
-    ///   url: url::Url::parse("file:////tmp/example.db").unwrap(),
-    ///   auth_token: None
-    /// };
+    /// let config = Config::new("file:////tmp/example.db").unwrap();
     /// let db = libsql_client::Client::from_config(config).await.unwrap();
     /// # }
     /// ```
     #[allow(unreachable_patterns)]
     pub async fn from_config<'a>(mut config: Config) -> anyhow::Result<Client> {
+        if let Some(backend) = std::env::var(BACKEND_ENV_VAR)
+            .ok()
+            .filter(|b| !b.is_empty())
+        {
+            return Self::from_config_with_backend(config, &backend).await;
+        }
         config.url = if config.url.scheme() == "libsql" {
             // We cannot use url::Url::set_scheme() because it prevents changing the scheme to http...
             // Safe to unwrap, because we know that the scheme is libsql
@@ -318,7 +934,7 @@ impl Client {
             },
             #[cfg(feature = "reqwest_backend")]
             "http" | "https" => {
-                let inner = crate::http::InnerClient::Reqwest(crate::reqwest::HttpClient::new());
+                let inner = crate::http::InnerClient::Reqwest(crate::reqwest::HttpClient::from_config(&config)?);
                 Client::Http(crate::http::Client::from_config(inner, config)?)
             },
             #[cfg(feature = "workers_backend")]
@@ -331,10 +947,89 @@ impl Client {
                 let inner = crate::http::InnerClient::Spin(crate::spin::HttpClient::new());
                 Client::Http(crate::http::Client::from_config(inner, config)?)
             },
+            #[cfg(all(unix, feature = "unix_backend"))]
+            "unix" => Client::Http(unix_client(config)?),
+            "unix" => return Err(unix_backend_disabled()),
             _ => anyhow::bail!("Unknown scheme: {scheme}. Make sure your backend exists and is enabled with its feature flag"),
         })
     }
 
+    /// Establishes a database client with the given backend: `local`, `reqwest`,
+    /// `workers`, `spin`, `hrana` or `unix`. Fails if the backend's feature is not enabled.
+    /// `unix://` URLs are only accepted by the `unix` backend, and vice versa.
+    ///
+    /// [`Client::from_config()`] calls it with the backend named by
+    /// the `LIBSQL_CLIENT_BACKEND` env variable, if set, which lets
+    /// applications switch transports without code changes.
+    /// HTTP URLs are turned into WebSocket URLs for the `hrana` backend.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # async fn f() {
+    /// # use libsql_client::Config;
+    /// let config = Config::new("file:////tmp/example.db").unwrap();
+    /// let db = libsql_client::Client::from_config_with_backend(config, "local").await.unwrap();
+    /// # }
+    /// ```
+    #[allow(unreachable_patterns)]
+    pub async fn from_config_with_backend(mut config: Config, backend: &str) -> Result<Client> {
+        if config.url.scheme() == "unix" && backend != "unix" {
+            return Err(crate::Error::Misuse(format!(
+                "Backend `{backend}` can't connect to a Unix domain socket, use the `unix` backend"
+            ))
+            .into());
+        }
+        let scheme = match (backend, config.url.scheme()) {
+            ("hrana", "libsql" | "https") => Some("wss"),
+            ("hrana", "http") => Some("ws"),
+            (_, "libsql") => Some("https"),
+            _ => None,
+        };
+        if let Some(scheme) = scheme {
+            // Url::set_scheme() refuses some of these changes, so the URL is re-parsed
+            let rest = config
+                .url
+                .as_str()
+                .split_once("://")
+                .map_or("", |(_, rest)| rest);
+            config.url = url::Url::parse(&format!("{scheme}://{rest}"))?;
+        }
+        Ok(match backend {
+            #[cfg(feature = "local_backend")]
+            "local" => Client::Local(crate::local::Client::new(config.url.to_string())?),
+            #[cfg(feature = "reqwest_backend")]
+            "reqwest" => {
+                let inner = crate::http::InnerClient::Reqwest(crate::reqwest::HttpClient::from_config(&config)?);
+                Client::Http(crate::http::Client::from_config(inner, config)?)
+            }
+            #[cfg(feature = "workers_backend")]
+            "workers" => {
+                let inner = crate::http::InnerClient::Workers(crate::workers::HttpClient::new());
+                Client::Http(crate::http::Client::from_config(inner, config)?)
+            }
+            #[cfg(feature = "spin_backend")]
+            "spin" => {
+                let inner = crate::http::InnerClient::Spin(crate::spin::HttpClient::new());
+                Client::Http(crate::http::Client::from_config(inner, config)?)
+            }
+            #[cfg(feature = "hrana_backend")]
+            "hrana" => Client::Hrana(crate::hrana::Client::from_config(config).await?),
+            #[cfg(all(unix, feature = "unix_backend"))]
+            "unix" if config.url.scheme() == "unix" => Client::Http(unix_client(config)?),
+            "unix" if config.url.scheme() != "unix" => anyhow::bail!(
+                "Backend `unix` needs a unix:// URL, e.g. unix:///var/run/sqld.sock"
+            ),
+            "unix" => return Err(unix_backend_disabled()),
+            "local" | "reqwest" | "workers" | "spin" | "hrana" => anyhow::bail!(
+                "Backend `{backend}` is not enabled, enable the `{backend}_backend` feature to use it"
+            ),
+            _ => anyhow::bail!(
+                "Unknown backend `{backend}`, expected one of: local, reqwest, workers, spin, hrana, unix"
+            ),
+        })
+    }
+
     /// Establishes a database client based on environment variables
     ///
     /// # Env
@@ -353,15 +1048,7 @@ impl Client {
     /// # }
     /// ```
     pub async fn from_env() -> anyhow::Result<Client> {
-        let url = std::env::var("LIBSQL_CLIENT_URL").map_err(|_| {
-            anyhow::anyhow!("LIBSQL_CLIENT_URL variable should point to your libSQL/sqld database")
-        })?;
-        let auth_token = std::env::var("LIBSQL_CLIENT_TOKEN").ok();
-        Self::from_config(Config {
-            url: url::Url::parse(&url)?,
-            auth_token,
-        })
-        .await
+        Self::from_config(Config::from_env()?).await
     }
 
     #[cfg(feature = "workers_backend")]
@@ -374,10 +1061,7 @@ impl Client {
             .secret("LIBSQL_CLIENT_TOKEN")
             .map_err(|e| anyhow::anyhow!("{e}"))?
             .to_string();
-        let config = Config {
-            url: url::Url::parse(&url)?,
-            auth_token: Some(token),
-        };
+        let config = Config::new(url.as_str())?.with_auth_token(token);
         let inner = crate::http::InnerClient::Workers(crate::workers::HttpClient::new());
         Ok(Client::Http(crate::http::Client::from_config(
             inner, config,
@@ -411,7 +1095,7 @@ impl SyncClient {
     /// ```
     /// # fn f() {
     /// # use libsql_client::Config;
-    /// let config = Config { url: url::Url::parse("file:////tmp/example.db").unwrap(), auth_token: None };
+    /// let config = Config::new("file:////tmp/example.db").unwrap();
     /// let db = libsql_client::SyncClient::from_config(config).unwrap();
     /// # }
     /// ```
@@ -477,6 +1161,19 @@ impl SyncClient {
         futures::executor::block_on(self.inner.raw_batch(stmts))
     }
 
+    /// Executes a batch of SQL statements independently and returns
+    /// one result per statement, in order.
+    ///
+    /// For the async version of this method, see [`Client::batch_results()`]
+    /// # Arguments
+    /// * `stmts` - SQL statements
+    pub fn batch_results(
+        &self,
+        stmts: impl IntoIterator<Item = impl Into<Statement> + Send> + Send,
+    ) -> Result<Vec<Result<ResultSet>>> {
+        futures::executor::block_on(self.inner.batch_results(stmts))
+    }
+
     /// Transactionally executes a batch of SQL statements.
     ///
     /// For a version in which statements can fail or succeed independently, see [`SyncClient::raw_batch()`]
@@ -526,6 +1223,23 @@ impl SyncClient {
         futures::executor::block_on(self.inner.execute(stmt))
     }
 
+    /// Executes a statement without consuming it, see [`Client::execute_ref()`].
+    pub fn execute_ref(&self, stmt: &Statement) -> Result<ResultSet> {
+        futures::executor::block_on(self.inner.execute_ref(stmt))
+    }
+
+    /// Checks that the database is reachable and accepts our credentials.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let db = libsql_client::SyncClient::in_memory().unwrap();
+    /// db.ping().unwrap();
+    /// ```
+    pub fn ping(&self) -> Result<()> {
+        futures::executor::block_on(self.inner.ping())
+    }
+
     /// Creates an interactive transaction
     ///
     /// # Examples
@@ -545,6 +1259,27 @@ impl SyncClient {
         SyncTransaction::new(self, id)
     }
 
+    /// Creates an interactive transaction which defers BEGIN until its first statement.
+    /// See [`Client::deferred_transaction()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let db = libsql_client::SyncClient::in_memory().unwrap();
+    /// let tx = db.deferred_transaction();
+    /// tx.commit().unwrap(); // nothing was sent to the database
+    /// ```
+    pub fn deferred_transaction(&self) -> SyncTransaction {
+        let id = TRANSACTION_IDS.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        SyncTransaction::deferred(self, id)
+    }
+
+    /// Creates an interactive transaction which only reads from the database.
+    /// See [`Client::read_transaction()`].
+    pub fn read_transaction(&self) -> SyncTransaction {
+        self.deferred_transaction().read_only()
+    }
+
     pub(crate) fn execute_in_transaction(&self, tx_id: u64, stmt: Statement) -> Result<ResultSet> {
         futures::executor::block_on(self.inner.execute_in_transaction(tx_id, stmt))
     }
@@ -559,12 +1294,73 @@ impl SyncClient {
 }
 
 /// Configuration for the database client
-#[derive(Debug)]
+///
+/// Create it with [`Config::new()`] and adjust it with the `with_*` methods,
+/// which keeps your code compatible when new options are added.
+///
+/// Since 0.35, options other than the URL and the auth token live in [Config::options],
+/// so struct literals need `options: Default::default()`.
+#[derive(Clone, Debug)]
 pub struct Config {
     pub url: url::Url,
     pub auth_token: Option<String>,
+    /// Options set with the `with_*` methods.
+    pub options: ConfigOptions,
+}
+
+/// Options of a [Config] besides its URL and auth token.
+///
+/// Set them with the methods of [Config]. New options may be added in minor releases,
+/// so the struct can only be built with [`ConfigOptions::default()`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ConfigOptions {
+    /// Whether to refuse connecting to a remote database without an auth token.
+    /// Off by default, so that local testing setups need no token.
+    pub require_auth_token: bool,
+    /// Timeout for establishing connections to the server. Only honored by the reqwest backend.
+    pub connect_timeout: Option<std::time::Duration>,
+    /// Timeout for whole requests, from connecting until the response body is read.
+    /// Only honored by the reqwest backend.
+    pub request_timeout: Option<std::time::Duration>,
+    /// Whether to accept TLS certificates which fail verification.
+    /// Set it with [`Config::danger_accept_invalid_certs()`]. Only honored by the reqwest backend.
+    pub danger_accept_invalid_certs: bool,
+    /// Whether to speak HTTP/2 right away instead of negotiating it, for servers known
+    /// to support it without TLS. Only honored by the reqwest backend.
+    pub http2_prior_knowledge: bool,
+    /// Maximum number of idle connections kept open per host, unlimited if `None`.
+    /// Only honored by the reqwest backend.
+    pub pool_max_idle_per_host: Option<usize>,
+    /// Interval of the pings which keep idle HTTP/2 connections open, so that bursts of
+    /// requests reuse one multiplexed connection instead of opening new ones.
+    /// Defaults to [DEFAULT_HTTP2_KEEP_ALIVE_INTERVAL]. Only honored by the reqwest backend.
+    pub http2_keep_alive_interval: Option<std::time::Duration>,
+    /// PEM-encoded certificates trusted in addition to the system's root certificates.
+    /// Add them with [`Config::add_root_certificate()`]. Only honored by the reqwest backend.
+    pub root_certificates: Vec<Vec<u8>>,
+}
+
+impl Default for ConfigOptions {
+    fn default() -> Self {
+        Self {
+            require_auth_token: false,
+            connect_timeout: None,
+            request_timeout: None,
+            danger_accept_invalid_certs: false,
+            http2_prior_knowledge: false,
+            pool_max_idle_per_host: None,
+            http2_keep_alive_interval: Some(DEFAULT_HTTP2_KEEP_ALIVE_INTERVAL),
+            root_certificates: Vec::new(),
+        }
+    }
 }
 
+/// Default of [ConfigOptions::http2_keep_alive_interval], short enough to keep connections
+/// from being closed by proxies between bursts of pipeline requests.
+pub const DEFAULT_HTTP2_KEEP_ALIVE_INTERVAL: std::time::Duration =
+    std::time::Duration::from_secs(30);
+
 impl Config {
     /// Create a new [Config]
     /// # Examples
@@ -586,9 +1382,54 @@ impl Config {
                 .try_into()
                 .map_err(|e| anyhow::anyhow!("Failed to parse url: {}", e))?,
             auth_token: None,
+            options: ConfigOptions::default(),
         })
     }
 
+    /// Creates a [Config] from environment variables, which can then be passed
+    /// to any backend's `from_config()` constructor.
+    ///
+    /// # Env
+    /// * `LIBSQL_CLIENT_URL` - URL of the database endpoint
+    /// * (optional) `LIBSQL_CLIENT_TOKEN` - authentication token for the database
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use libsql_client::Config;
+    /// std::env::set_var("LIBSQL_CLIENT_URL", "https://example.com/db");
+    /// std::env::set_var("LIBSQL_CLIENT_TOKEN", "secret");
+    /// let config = Config::from_env().unwrap();
+    /// assert_eq!(config.url.as_str(), "https://example.com/db");
+    /// assert_eq!(config.auth_token.as_deref(), Some("secret"));
+    /// ```
+    pub fn from_env() -> Result<Self> {
+        let mut config = Self::new(url_from_env()?.as_str())?;
+        config.auth_token = std::env::var(TOKEN_ENV_VAR).ok();
+        Ok(config)
+    }
+
+    /// Parses a database URL into a [Config], moving its `authToken` query parameter
+    /// to [Config::auth_token], so that the token doesn't get logged along with the URL.
+    /// Other query parameters are kept, see [Config::from_connection_string] for parsing them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use libsql_client::Config;
+    /// let config = Config::from_url_str("wss://example.com?authToken=secret").unwrap();
+    /// assert_eq!(config.url.as_str(), "wss://example.com/");
+    /// assert_eq!(config.auth_token.as_deref(), Some("secret"));
+    /// ```
+    pub fn from_url_str(url: &str) -> Result<Self> {
+        let mut config = Self::new(url)?;
+        config.auth_token = crate::utils::pop_query_param(&mut config.url, "authToken".to_string());
+        if config.url.query() == Some("") {
+            config.url.set_query(None);
+        }
+        Ok(config)
+    }
+
     /// Adds an authentication token to config
     /// # Examples
     ///
@@ -604,4 +1445,102 @@ impl Config {
         self.auth_token = Some(token.into());
         self
     }
+
+    /// Makes creating a remote client fail if the auth token is missing or empty,
+    /// instead of silently connecting without authentication. Use it in production
+    /// code which must never talk to a misconfigured, anonymous endpoint.
+    /// # Examples
+    ///
+    /// ```
+    /// # async fn f() -> anyhow::Result<()> {
+    /// # use libsql_client::Config;
+    /// let config = Config::new("https://example.com/db")?.with_required_auth_token();
+    /// assert!(libsql_client::Client::from_config(config).await.is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_required_auth_token(mut self) -> Self {
+        self.options.require_auth_token = true;
+        self
+    }
+
+    /// Sets a timeout for establishing connections to the server.
+    pub fn with_connect_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.options.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets a timeout for whole requests, after which they fail.
+    pub fn with_request_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.options.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Makes the client speak HTTP/2 without negotiating it first, so that all requests
+    /// to the server are multiplexed over a single connection. The server must support
+    /// HTTP/2 over plaintext connections, otherwise requests fail.
+    pub fn with_http2_prior_knowledge(mut self) -> Self {
+        self.options.http2_prior_knowledge = true;
+        self
+    }
+
+    /// Sets the maximum number of idle connections kept open per host.
+    pub fn with_pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.options.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// Sets the interval of HTTP/2 keep-alive pings.
+    pub fn with_http2_keep_alive_interval(mut self, interval: std::time::Duration) -> Self {
+        self.options.http2_keep_alive_interval = Some(interval);
+        self
+    }
+
+    /// Trusts a PEM-encoded root certificate, e.g. of a private CA which signed
+    /// the certificate of an on-premises sqld. Certificates are still verified,
+    /// unlike with [`Config::danger_accept_invalid_certs()`].
+    /// An invalid certificate makes creating the client fail.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn f() -> anyhow::Result<()> {
+    /// # use libsql_client::Config;
+    /// let pem = std::fs::read("/etc/ssl/private-ca.pem")?;
+    /// let config = Config::new("https://db.internal:8080")?.add_root_certificate(pem);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn add_root_certificate(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.options.root_certificates.push(pem.into());
+        self
+    }
+
+    /// Disables TLS certificate verification, e.g. to connect to a local sqld
+    /// with a self-signed certificate.
+    ///
+    /// **This makes the connection vulnerable to man-in-the-middle attacks.**
+    /// Never use it for production databases.
+    /// # Examples
+    ///
+    /// ```
+    /// # async fn f() -> anyhow::Result<()> {
+    /// # use libsql_client::Config;
+    /// let config = Config::new("https://localhost:8080")?.danger_accept_invalid_certs();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn danger_accept_invalid_certs(mut self) -> Self {
+        self.options.danger_accept_invalid_certs = true;
+        self
+    }
+
+    // Fails if an auth token is required, but missing
+    pub(crate) fn check_auth_token(&self) -> Result<()> {
+        let has_token = self.auth_token.as_deref().is_some_and(|t| !t.is_empty());
+        if self.options.require_auth_token && !has_token {
+            anyhow::bail!("An auth token is required to connect to {}", self.url);
+        }
+        Ok(())
+    }
 }
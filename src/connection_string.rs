@@ -0,0 +1,151 @@
+//! Parsing of connection strings, which carry the database URL along with
+//! the client options, e.g. `libsql://host?authToken=secret&timeout=5s`.
+
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::{Client, Config};
+
+impl Config {
+    /// Parses a connection string into a [Config].
+    ///
+    /// The connection string is a database URL with the following optional query parameters:
+    /// * `authToken` - the auth token
+    /// * `timeout` - timeout of whole requests, e.g. `5s`, `500ms` or `1m`
+    /// * `connectTimeout` - timeout of establishing connections, in the same format
+    /// * `tls` - `true` (default) or `false`, whether `libsql://` URLs use TLS
+    ///
+    /// Unknown parameters are rejected, so that typos don't go unnoticed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use libsql_client::Config;
+    /// let config = Config::from_connection_string("libsql://example.com?authToken=secret&timeout=5s").unwrap();
+    /// assert_eq!(config.url.as_str(), "https://example.com/");
+    /// assert_eq!(config.auth_token.as_deref(), Some("secret"));
+    /// assert_eq!(config.options.request_timeout, Some(std::time::Duration::from_secs(5)));
+    ///
+    /// let err = Config::from_connection_string("libsql://example.com?timeout=5x").unwrap_err();
+    /// assert!(err.to_string().contains("timeout=5x"));
+    /// ```
+    pub fn from_connection_string(connection_string: &str) -> Result<Config> {
+        let mut url = url::Url::parse(connection_string)
+            .map_err(|e| anyhow::anyhow!("Invalid URL in connection string: {e}"))?;
+        let params: Vec<(String, String)> = url
+            .query_pairs()
+            .map(|(k, v)| (k.into_owned(), v.into_owned()))
+            .collect();
+        // The parameters must not stay in the URL, so that the token doesn't get logged
+        url.set_query(None);
+
+        let mut auth_token = None;
+        let mut request_timeout = None;
+        let mut connect_timeout = None;
+        let mut tls = None;
+        for (key, value) in params {
+            let invalid = |expected: &str| {
+                anyhow::anyhow!(
+                    "Invalid connection string parameter `{key}={value}`: expected {expected}"
+                )
+            };
+            match key.as_str() {
+                "authToken" => auth_token = Some(value.clone()),
+                "timeout" => {
+                    request_timeout =
+                        Some(parse_duration(&value).ok_or_else(|| invalid("a duration like 5s"))?)
+                }
+                "connectTimeout" => {
+                    connect_timeout =
+                        Some(parse_duration(&value).ok_or_else(|| invalid("a duration like 5s"))?)
+                }
+                "tls" => {
+                    tls = Some(match value.as_str() {
+                        "true" | "1" => true,
+                        "false" | "0" => false,
+                        _ => return Err(invalid("true or false")),
+                    })
+                }
+                _ => anyhow::bail!("Unknown connection string parameter `{key}`"),
+            }
+        }
+
+        if url.scheme() == "libsql" {
+            let scheme = if tls.unwrap_or(true) { "https" } else { "http" };
+            // Url::set_scheme() refuses changing the scheme to http, so the URL is re-parsed
+            url = url::Url::parse(&url.as_str().replacen("libsql", scheme, 1))?;
+        } else if tls.is_some() {
+            anyhow::bail!(
+                "Connection string parameter `tls` only applies to libsql:// URLs, got {}://",
+                url.scheme()
+            );
+        }
+
+        let mut config = Config::new(url)?;
+        config.auth_token = auth_token;
+        config.options.request_timeout = request_timeout;
+        config.options.connect_timeout = connect_timeout;
+        Ok(config)
+    }
+}
+
+impl Client {
+    /// Creates a client from a connection string.
+    /// See [`Config::from_connection_string()`] for the supported options.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # async fn f() {
+    /// let db = libsql_client::Client::from_connection_string("file:////tmp/example.db").await.unwrap();
+    /// # }
+    /// ```
+    pub async fn from_connection_string(connection_string: &str) -> Result<Client> {
+        Client::from_config(Config::from_connection_string(connection_string)?).await
+    }
+}
+
+// Parses durations like `5s`, `500ms` or `2m`
+fn parse_duration(s: &str) -> Option<Duration> {
+    let unit_start = s.find(|c: char| !c.is_ascii_digit())?;
+    let (number, unit) = s.split_at(unit_start);
+    let number: u64 = number.parse().ok()?;
+    match unit {
+        "ms" => Some(Duration::from_millis(number)),
+        "s" => Some(Duration::from_secs(number)),
+        "m" => Some(Duration::from_secs(number.checked_mul(60)?)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("500ms"), Some(Duration::from_millis(500)));
+        assert_eq!(parse_duration("5s"), Some(Duration::from_secs(5)));
+        assert_eq!(parse_duration("2m"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_duration("5"), None);
+        assert_eq!(parse_duration("s"), None);
+        assert_eq!(parse_duration("5h"), None);
+    }
+
+    #[test]
+    fn test_connection_string_without_tls() {
+        let config = Config::from_connection_string("libsql://localhost:8080?tls=false").unwrap();
+        assert_eq!(config.url.as_str(), "http://localhost:8080/");
+        assert_eq!(config.auth_token, None);
+    }
+
+    #[test]
+    fn test_connection_string_unknown_parameter() {
+        let err = Config::from_connection_string("https://example.com?authtoken=x").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown connection string parameter `authtoken`"
+        );
+    }
+}
@@ -0,0 +1,166 @@
+//! `crud` contains helpers which build and run common statements,
+//! quoting identifiers and binding values so that callers don't have to.
+
+use anyhow::Result;
+
+use crate::{utils, Client, Statement, Value};
+
+/// Maximum number of values bound to a single statement by the bulk helpers,
+/// safely below SQLite's historical limit of 999 host parameters.
+const MAX_PARAMS_PER_STATEMENT: usize = 500;
+
+// Builds `UPDATE table SET col = ?, ... WHERE where_clause`
+fn update_statement(
+    table: &str,
+    set: &[(&str, Value)],
+    where_clause: &str,
+    where_params: &[Value],
+) -> Result<Statement> {
+    if set.is_empty() {
+        anyhow::bail!("UPDATE needs at least one column to set");
+    }
+    if where_clause.trim().is_empty() {
+        anyhow::bail!("UPDATE needs a WHERE clause; pass \"TRUE\" to update all rows");
+    }
+    let assignments = set
+        .iter()
+        .map(|(column, _)| Ok(format!("{} = ?", utils::quote_identifier(column)?)))
+        .collect::<Result<Vec<String>>>()?;
+    Ok(Statement {
+        sql: format!(
+            "UPDATE {} SET {} WHERE {where_clause}",
+            utils::quote_identifier(table)?,
+            assignments.join(", ")
+        ),
+        args: set
+            .iter()
+            .map(|(_, value)| value.clone())
+            .chain(where_params.iter().cloned())
+            .collect(),
+    })
+}
+
+// Builds `DELETE FROM table WHERE id_column IN (?, ...)` statements,
+// binding at most MAX_PARAMS_PER_STATEMENT ids to each of them
+fn delete_by_ids_statements(table: &str, id_column: &str, ids: &[Value]) -> Result<Vec<Statement>> {
+    let table = utils::quote_identifier(table)?;
+    let id_column = utils::quote_identifier(id_column)?;
+    Ok(ids
+        .chunks(MAX_PARAMS_PER_STATEMENT)
+        .map(|chunk| Statement {
+            sql: format!(
+                "DELETE FROM {table} WHERE {id_column} IN ({})",
+                vec!["?"; chunk.len()].join(", ")
+            ),
+            args: chunk.to_vec(),
+        })
+        .collect())
+}
+
+impl Client {
+    /// Updates rows of a table, setting the given columns to the given values,
+    /// and returns the number of updated rows. Table and column names are quoted,
+    /// and all values are bound as parameters.
+    ///
+    /// # Arguments
+    /// * `table` - name of the table
+    /// * `set` - pairs of column names and their new values
+    /// * `where_clause` - condition selecting the rows to update, e.g. `id = ?`
+    /// * `where_params` - parameters bound to the placeholders in `where_clause`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # async fn run() {
+    /// let db = libsql_client::Client::in_memory().unwrap();
+    /// # db.execute("create table users(id integer, name text)").await.unwrap();
+    /// # db.execute("insert into users values (1, 'John')").await.unwrap();
+    /// let updated = db
+    ///     .update("users", &[("name", "Jane".into())], "id = ?", &[1.into()])
+    ///     .await
+    ///     .unwrap();
+    /// assert_eq!(updated, 1);
+    /// # }
+    /// ```
+    pub async fn update(
+        &self,
+        table: &str,
+        set: &[(&str, Value)],
+        where_clause: &str,
+        where_params: &[Value],
+    ) -> Result<u64> {
+        let stmt = update_statement(table, set, where_clause, where_params)?;
+        Ok(self.execute(stmt).await?.rows_affected)
+    }
+
+    /// Deletes the rows whose `id_column` matches any of the given ids, and returns
+    /// the number of deleted rows. Large id lists are split into several statements
+    /// so as not to exceed SQLite's limit on bound parameters; all of them run in
+    /// a single transaction, so either all rows are deleted or none.
+    ///
+    /// # Arguments
+    /// * `table` - name of the table
+    /// * `id_column` - name of the column holding the ids
+    /// * `ids` - ids of the rows to delete
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # async fn run() {
+    /// let db = libsql_client::Client::in_memory().unwrap();
+    /// # db.execute("create table users(id integer)").await.unwrap();
+    /// # db.execute("insert into users values (1), (2), (3)").await.unwrap();
+    /// let deleted = db
+    ///     .delete_by_ids("users", "id", &[1.into(), 3.into()])
+    ///     .await
+    ///     .unwrap();
+    /// assert_eq!(deleted, 2);
+    /// # }
+    /// ```
+    pub async fn delete_by_ids(&self, table: &str, id_column: &str, ids: &[Value]) -> Result<u64> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        let stmts = delete_by_ids_statements(table, id_column, ids)?;
+        let results = self.batch(stmts).await?;
+        Ok(results.iter().map(|rs| rs.rows_affected).sum())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_statement() {
+        let stmt = update_statement(
+            "user data",
+            &[("name", "Jane".into()), ("a\"b", 3.into())],
+            "id = ?",
+            &[7.into()],
+        )
+        .unwrap();
+        assert_eq!(
+            stmt.sql,
+            "UPDATE \"user data\" SET \"name\" = ?, \"a\"\"b\" = ? WHERE id = ?"
+        );
+        assert_eq!(stmt.args.len(), 3);
+    }
+
+    #[test]
+    fn test_delete_by_ids_statements_are_chunked() {
+        let ids: Vec<Value> = (0..MAX_PARAMS_PER_STATEMENT as i64 + 1)
+            .map(Value::from)
+            .collect();
+        let stmts = delete_by_ids_statements("t", "id", &ids).unwrap();
+        assert_eq!(stmts.len(), 2);
+        assert_eq!(stmts[0].args.len(), MAX_PARAMS_PER_STATEMENT);
+        assert_eq!(stmts[1].sql, "DELETE FROM \"t\" WHERE \"id\" IN (?)");
+    }
+
+    #[test]
+    fn test_update_statement_requires_columns_and_where() {
+        assert!(update_statement("t", &[], "id = 1", &[]).is_err());
+        assert!(update_statement("t", &[("a", Value::Null)], " ", &[]).is_err());
+    }
+}
@@ -0,0 +1,53 @@
+//! `DatabaseClient` is an object-safe interface to a database, for code which
+//! stores clients behind `Box<dyn DatabaseClient>` or substitutes them in tests.
+
+use anyhow::Result;
+use futures::future::BoxFuture;
+
+use crate::{BatchResult, Client, ResultSet, Statement};
+
+/// Object-safe subset of the [Client] API. The methods return boxed futures,
+/// so the trait can be used as `dyn DatabaseClient`.
+///
+/// [Client] already dispatches to the backend chosen at runtime, so this trait is only
+/// needed to plug in other implementations, e.g. fakes in unit tests.
+///
+/// # Examples
+///
+/// ```
+/// # async fn run() {
+/// use libsql_client::DatabaseClient;
+///
+/// fn get_db() -> Box<dyn DatabaseClient> {
+///     Box::new(libsql_client::Client::in_memory().unwrap())
+/// }
+///
+/// let db = get_db();
+/// let rs = db.execute("SELECT 1".into()).await.unwrap();
+/// assert_eq!(rs.rows.len(), 1);
+/// # }
+/// ```
+pub trait DatabaseClient: Send + Sync {
+    /// See [`Client::execute()`].
+    fn execute(&self, stmt: Statement) -> BoxFuture<'_, Result<ResultSet>>;
+
+    /// See [`Client::raw_batch()`].
+    fn raw_batch(&self, stmts: Vec<Statement>) -> BoxFuture<'_, Result<BatchResult>>;
+
+    /// See [`Client::batch()`].
+    fn batch(&self, stmts: Vec<Statement>) -> BoxFuture<'_, Result<Vec<ResultSet>>>;
+}
+
+impl DatabaseClient for Client {
+    fn execute(&self, stmt: Statement) -> BoxFuture<'_, Result<ResultSet>> {
+        Box::pin(Client::execute(self, stmt))
+    }
+
+    fn raw_batch(&self, stmts: Vec<Statement>) -> BoxFuture<'_, Result<BatchResult>> {
+        Box::pin(Client::raw_batch(self, stmts))
+    }
+
+    fn batch(&self, stmts: Vec<Statement>) -> BoxFuture<'_, Result<Vec<ResultSet>>> {
+        Box::pin(Client::batch(self, stmts))
+    }
+}
@@ -0,0 +1,108 @@
+//! `Deadline` bounds the total time of a multi-step operation.
+
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+
+use crate::{BatchResult, Client, Error, ResultSet, Statement};
+
+/// A client handle whose requests fail fast with [Error::Timeout] once the deadline
+/// has passed, created with [`Client::with_deadline()`].
+///
+/// Unlike per-request timeouts, a single deadline covers all the requests of a logical
+/// operation, e.g. a migration made of many statements. The deadline is checked before
+/// each request is sent: a request which is already in flight is not interrupted, so
+/// combine it with a request timeout to bound the last request too.
+///
+/// Each call to [`Client::with_deadline()`] starts a new scope with its own deadline.
+///
+/// # Examples
+///
+/// ```
+/// # async fn run() -> anyhow::Result<()> {
+/// use std::time::{Duration, Instant};
+///
+/// let db = libsql_client::Client::in_memory()?;
+/// let migration = db.with_deadline(Instant::now() + Duration::from_secs(30));
+/// migration.execute("CREATE TABLE t(x)").await?;
+/// migration.execute("INSERT INTO t VALUES (1)").await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Deadline<'a> {
+    client: &'a Client,
+    deadline: Instant,
+}
+
+impl<'a> Deadline<'a> {
+    pub(crate) fn new(client: &'a Client, deadline: Instant) -> Self {
+        Self { client, deadline }
+    }
+
+    /// Returns the time left until the deadline, or `None` if it has passed.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .checked_duration_since(Instant::now())
+            .filter(|remaining| !remaining.is_zero())
+    }
+
+    fn check(&self) -> Result<()> {
+        match self.remaining() {
+            Some(_) => Ok(()),
+            None => {
+                tracing::debug!("Deadline exceeded");
+                Err(Error::Timeout.into())
+            }
+        }
+    }
+
+    /// Executes a statement, see [`Client::execute()`].
+    pub async fn execute(&self, stmt: impl Into<Statement> + Send) -> Result<ResultSet> {
+        self.check()?;
+        self.client.execute(stmt).await
+    }
+
+    /// Executes statements in a transaction, see [`Client::batch()`].
+    pub async fn batch<I: IntoIterator<Item = impl Into<Statement> + Send> + Send>(
+        &self,
+        stmts: I,
+    ) -> Result<Vec<ResultSet>>
+    where
+        <I as IntoIterator>::IntoIter: Send,
+    {
+        self.check()?;
+        self.client.batch(stmts).await
+    }
+
+    /// Executes independent statements, see [`Client::raw_batch()`].
+    pub async fn raw_batch(
+        &self,
+        stmts: impl IntoIterator<Item = impl Into<Statement> + Send> + Send,
+    ) -> Result<BatchResult> {
+        self.check()?;
+        self.client.raw_batch(stmts).await
+    }
+}
+
+#[cfg(all(test, feature = "local_backend"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deadline() {
+        let db = Client::in_memory().unwrap();
+        let scope = db.with_deadline(Instant::now() + Duration::from_secs(60));
+        assert!(scope.remaining().is_some());
+        futures::executor::block_on(scope.execute("SELECT 1")).unwrap();
+
+        let scope = db.with_deadline(Instant::now());
+        assert_eq!(scope.remaining(), None);
+        let e = futures::executor::block_on(scope.execute("SELECT 1")).unwrap_err();
+        assert!(matches!(e.downcast_ref::<Error>(), Some(Error::Timeout)));
+
+        // A new scope gets a fresh deadline
+        let scope = db.with_deadline(Instant::now() + Duration::from_secs(60));
+        futures::executor::block_on(scope.raw_batch(["SELECT 1", "SELECT 2"])).unwrap();
+    }
+}
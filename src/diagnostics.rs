@@ -0,0 +1,99 @@
+//! Diagnostics of the server: its clock compared with the client's, and its usage statistics.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+
+use crate::{Client, Value};
+
+// Milliseconds since the Unix epoch, as seen by the server
+const SERVER_TIME_SQL: &str = "SELECT CAST((julianday('now') - 2440587.5) * 86400000.0 AS INTEGER)";
+
+/// Estimated difference between the server's clock and the local clock.
+/// See [`Client::clock_skew()`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClockSkew {
+    /// Absolute difference between the clocks.
+    pub offset: Duration,
+    /// Whether the server's clock is ahead of the local one.
+    pub server_ahead: bool,
+    /// Round trip time of the measurement, which bounds its precision.
+    pub round_trip: Duration,
+}
+
+/// Usage statistics of a database, as reported by sqld. See [`Client::stats()`].
+/// Counters missing from the server's response are zero.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(default)]
+#[non_exhaustive]
+pub struct DbStats {
+    /// Number of rows read by queries.
+    pub rows_read_count: u64,
+    /// Number of rows written by queries.
+    pub rows_written_count: u64,
+    /// Size of the database on disk.
+    pub storage_bytes_used: u64,
+    /// Number of queries executed.
+    pub query_count: u64,
+}
+
+impl Client {
+    /// Returns the usage statistics of the database, for dashboards and quota checks.
+    ///
+    /// Only available for HTTP connections to servers exposing the stats endpoint,
+    /// see [crate::http::STATS_PATH]. Fails with a descriptive error otherwise.
+    pub async fn stats(&self) -> Result<DbStats> {
+        match self {
+            #[cfg(http_backend)]
+            Self::Http(r) => r.stats().await,
+            _ => anyhow::bail!("Usage stats are only available over HTTP connections"),
+        }
+    }
+
+    /// Returns the current time according to the server's clock, with millisecond precision.
+    pub async fn server_time(&self) -> Result<SystemTime> {
+        let rs = self.execute(SERVER_TIME_SQL).await?;
+        match rs.rows.first().and_then(|row| row.values.first()) {
+            Some(Value::Integer { value }) if *value >= 0 => {
+                Ok(UNIX_EPOCH + Duration::from_millis(*value as u64))
+            }
+            other => anyhow::bail!("Unexpected server time: {other:?}"),
+        }
+    }
+
+    /// Estimates how far the server's clock is from the local one.
+    ///
+    /// The server time is assumed to be read halfway through the round trip,
+    /// so the estimate is off by at most half of [ClockSkew::round_trip].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # async fn run() {
+    /// let db = libsql_client::Client::in_memory().unwrap();
+    /// let skew = db.clock_skew().await.unwrap();
+    /// assert!(skew.offset < std::time::Duration::from_secs(1));
+    /// # }
+    /// ```
+    // SystemTime::now() panics on wasm32-unknown-unknown
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn clock_skew(&self) -> Result<ClockSkew> {
+        let sent = SystemTime::now();
+        let server_time = self.server_time().await?;
+        let received = SystemTime::now();
+        let round_trip = received.duration_since(sent).unwrap_or_default();
+        let local_time = sent + round_trip / 2;
+        Ok(match server_time.duration_since(local_time) {
+            Ok(offset) => ClockSkew {
+                offset,
+                server_ahead: true,
+                round_trip,
+            },
+            Err(e) => ClockSkew {
+                offset: e.duration(),
+                server_ahead: false,
+                round_trip,
+            },
+        })
+    }
+}
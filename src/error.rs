@@ -0,0 +1,358 @@
+//! `Error` lists the failures which callers may want to handle programmatically.
+
+use crate::proto::ProtocolVersion;
+
+/// Errors reported by the client with enough structure to act upon.
+///
+/// Client methods return [anyhow::Result], so errors of this type are wrapped
+/// in [anyhow::Error] and can be inspected with `err.downcast_ref::<libsql_client::Error>()`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # async fn f() -> anyhow::Result<()> {
+/// let db = libsql_client::Client::from_env().await?;
+/// if let Err(e) = db.execute("SELECT 1").await {
+///     match e.downcast_ref::<libsql_client::Error>() {
+///         Some(libsql_client::Error::Http { status, .. }) => println!("HTTP error {status}"),
+///         _ => println!("other error: {e}"),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The server responded with a non-success HTTP status.
+    Http { status: u16, message: String },
+    /// The server rejected the credentials, e.g. because the auth token expired.
+    /// Carries the server's message.
+    Unauthorized { message: String },
+    /// The server supports none of the protocol versions which the client tried
+    /// to negotiate, listed in `tried`.
+    NoSupportedProtocolVersion { tried: Vec<ProtocolVersion> },
+    /// A bound parameter's type conflicts with the type of its column.
+    /// Carries the server's message and the types of the bound parameters, in order.
+    TypeMismatch {
+        message: String,
+        arg_types: Vec<&'static str>,
+    },
+    /// A statement of a multi-statement script failed.
+    /// `index` is the 0-based position of the statement in the script.
+    Script { index: usize, message: String },
+    /// A statement of a script run with [crate::Client::execute_file] failed.
+    /// `line` is the 1-based line of the file on which the statement starts,
+    /// and `index` its 0-based position in the script.
+    ScriptFile {
+        path: String,
+        line: usize,
+        index: usize,
+        message: String,
+    },
+    /// The request was aborted through a [crate::CancellationToken].
+    Cancelled,
+    /// The API was used incorrectly, e.g. the number of bound parameters doesn't
+    /// match the placeholders of the statement. Detected before contacting the server.
+    Misuse(String),
+    /// A query expected to return at most one row returned `count` rows.
+    MultipleRows { count: usize },
+    /// The connection was lost after a batch was sent, so it's unknown which of its
+    /// steps were applied. The server reports the results of a batch in a single
+    /// message, so no partial results are available: steps outside of a transaction
+    /// may have been committed, and should be checked before sending them again.
+    BatchInterrupted { message: String },
+    /// The server's response could not be decoded, e.g. because a blob
+    /// isn't valid base64. Carries the decoding error. The WebSocket backend reports it
+    /// when the hrana client names the decoding error as the cause of a failed request.
+    MalformedResponse { message: String },
+    /// The circuit breaker is open after repeated failures of the server,
+    /// so the request was not sent. See [crate::circuit::CircuitBreakerPolicy].
+    CircuitOpen,
+    /// The deadline set with [crate::Client::with_deadline] passed before the request was sent.
+    Timeout,
+    /// The server's response exceeded the limit set with
+    /// [crate::http::Client::with_max_response_bytes], and was not read in full.
+    ResponseTooLarge { limit: usize },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Http { status, message } => write!(f, "HTTP status {status}: {message}"),
+            Error::Unauthorized { message } => write!(f, "Unauthorized: {message}"),
+            Error::NoSupportedProtocolVersion { tried } => {
+                let tried: Vec<String> = tried.iter().map(ToString::to_string).collect();
+                write!(
+                    f,
+                    "The server supports none of the protocol versions {}",
+                    tried.join(", ")
+                )
+            }
+            Error::TypeMismatch { message, arg_types } => {
+                let arg_types: Vec<String> = arg_types
+                    .iter()
+                    .enumerate()
+                    .map(|(i, t)| format!("{}: {t}", i + 1))
+                    .collect();
+                write!(
+                    f,
+                    "{message} (bound parameter types: {})",
+                    arg_types.join(", ")
+                )
+            }
+            Error::Script { index, message } => {
+                write!(f, "Statement {index} of the script failed: {message}")
+            }
+            Error::ScriptFile {
+                path,
+                line,
+                index,
+                message,
+            } => {
+                write!(
+                    f,
+                    "{path}:{line}: statement {index} of the script failed: {message}"
+                )
+            }
+            Error::Cancelled => write!(f, "Request cancelled"),
+            Error::Misuse(message) => write!(f, "Misuse: {message}"),
+            Error::MultipleRows { count } => {
+                write!(f, "Expected at most one row, got {count}")
+            }
+            Error::BatchInterrupted { message } => {
+                write!(
+                    f,
+                    "Connection lost during a batch, its outcome is unknown: {message}"
+                )
+            }
+            Error::MalformedResponse { message } => {
+                write!(f, "Malformed response from the server: {message}")
+            }
+            Error::CircuitOpen => write!(f, "Circuit breaker open, the server keeps failing"),
+            Error::Timeout => write!(f, "Deadline exceeded"),
+            Error::ResponseTooLarge { limit } => {
+                write!(f, "Response larger than the limit of {limit} bytes")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Error {
+    // Error for a non-success HTTP status
+    #[cfg_attr(
+        not(any(
+            feature = "reqwest_backend",
+            feature = "workers_backend",
+            feature = "spin_backend",
+            feature = "unix_backend"
+        )),
+        allow(dead_code)
+    )]
+    pub(crate) fn from_status(status: u16, message: String) -> Error {
+        match status {
+            401 => Error::Unauthorized { message },
+            _ => Error::Http { status, message },
+        }
+    }
+}
+
+// Finds the WebSocket error which caused an error of the hrana client, if any
+#[cfg(feature = "hrana_backend")]
+fn websocket_error(e: &anyhow::Error) -> Option<&tungstenite::Error> {
+    e.chain()
+        .find_map(|cause| cause.downcast_ref::<tungstenite::Error>())
+}
+
+// Turns errors of the hrana client caused by the server rejecting the WebSocket
+// handshake with HTTP 401 into Error::Unauthorized. Other errors are returned unchanged:
+// the protocol carries no error codes, so errors are never classified by their message.
+#[cfg(feature = "hrana_backend")]
+pub(crate) fn with_unauthorized(e: anyhow::Error) -> anyhow::Error {
+    match websocket_error(&e) {
+        Some(tungstenite::Error::Http(response)) if response.status().as_u16() == 401 => {
+            let message = response
+                .body()
+                .as_deref()
+                .map(|body| String::from_utf8_lossy(body).into_owned())
+                .unwrap_or_else(|| e.to_string());
+            Error::Unauthorized { message }.into()
+        }
+        _ => e,
+    }
+}
+
+// Turns errors of the hrana client caused by a server message which didn't decode
+// into Error::MalformedResponse. Other errors are returned unchanged.
+#[cfg(feature = "hrana_backend")]
+pub(crate) fn with_malformed_response(e: anyhow::Error) -> anyhow::Error {
+    let decoding_error = e
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<serde_json::Error>());
+    match decoding_error {
+        Some(decoding_error) if e.downcast_ref::<Error>().is_none() => Error::MalformedResponse {
+            message: decoding_error.to_string(),
+        }
+        .into(),
+        _ => e,
+    }
+}
+
+// Checks whether an error of the hrana client was caused by the WebSocket connection failing
+#[cfg(feature = "hrana_backend")]
+pub(crate) fn is_websocket_failure(e: &anyhow::Error) -> bool {
+    matches!(
+        websocket_error(e),
+        Some(
+            tungstenite::Error::ConnectionClosed
+                | tungstenite::Error::AlreadyClosed
+                | tungstenite::Error::Io(_)
+                | tungstenite::Error::Protocol(_)
+        )
+    )
+}
+
+// Turns the error of a batch whose results were lost along with the connection
+// into Error::BatchInterrupted, unless it's already typed, e.g. Error::Unauthorized
+#[cfg(feature = "hrana_backend")]
+pub(crate) fn into_batch_interrupted(e: anyhow::Error) -> anyhow::Error {
+    if e.downcast_ref::<Error>().is_some() {
+        return e;
+    }
+    Error::BatchInterrupted {
+        message: e.to_string(),
+    }
+    .into()
+}
+
+// Context attached to errors of remote requests. Its message repeats the
+// underlying error's, so that the id shows up wherever the error is displayed.
+#[derive(Debug)]
+struct CorrelationId {
+    id: String,
+    message: String,
+}
+
+impl std::fmt::Display for CorrelationId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (correlation id: {})", self.message, self.id)
+    }
+}
+
+pub(crate) fn with_correlation_id(e: anyhow::Error, id: String) -> anyhow::Error {
+    let message = e.to_string();
+    e.context(CorrelationId { id, message })
+}
+
+/// Returns the id which the client sent along with the failed request, in the
+/// `x-correlation-id` header, to match the error with server-side logs.
+/// Errors of requests which never reached an HTTP server have no id.
+///
+/// Typed errors wrapped this way can still be retrieved with `downcast_ref`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # async fn f() -> anyhow::Result<()> {
+/// let db = libsql_client::Client::from_env().await?;
+/// if let Err(e) = db.execute("SELECT 1").await {
+///     if let Some(id) = libsql_client::error::correlation_id(&e) {
+///         println!("request {id} failed: {e}");
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn correlation_id(e: &anyhow::Error) -> Option<&str> {
+    e.downcast_ref::<CorrelationId>().map(|c| c.id.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unauthorized() {
+        assert!(matches!(
+            Error::from_status(401, "token expired".to_string()),
+            Error::Unauthorized { .. }
+        ));
+        assert!(matches!(
+            Error::from_status(403, String::new()),
+            Error::Http { status: 403, .. }
+        ));
+    }
+
+    #[cfg(feature = "hrana_backend")]
+    #[test]
+    fn test_hrana_unauthorized() {
+        let rejected = |status: u16| {
+            let response = tungstenite::http::Response::builder()
+                .status(status)
+                .body(Some(b"token expired".to_vec()))
+                .unwrap();
+            anyhow::Error::from(tungstenite::Error::Http(response)).context("connecting failed")
+        };
+        let e = with_unauthorized(rejected(401));
+        assert!(matches!(
+            e.downcast_ref::<Error>(),
+            Some(Error::Unauthorized { message }) if message == "token expired"
+        ));
+        let e = with_unauthorized(rejected(503));
+        assert!(e.downcast_ref::<Error>().is_none());
+        // SQL errors are never mistaken for auth failures, whatever they say
+        for message in ["no such table: orders_401", "unauthorized_users is locked"] {
+            let e = with_unauthorized(anyhow::anyhow!("SQLITE_ERROR: {message}"));
+            assert!(e.downcast_ref::<Error>().is_none(), "{message}");
+        }
+    }
+
+    #[cfg(feature = "hrana_backend")]
+    #[test]
+    fn test_hrana_malformed_response() {
+        let decoding_error =
+            serde_json::from_str::<crate::Value>(r#"{"type":"blob","base64":"not base64!"}"#)
+                .unwrap_err();
+        let e = with_malformed_response(
+            anyhow::Error::from(decoding_error).context("invalid server message"),
+        );
+        assert!(matches!(
+            e.downcast_ref::<Error>(),
+            Some(Error::MalformedResponse { .. })
+        ));
+        let e = with_malformed_response(anyhow::anyhow!("SQLITE_ERROR: malformed JSON"));
+        assert!(e.downcast_ref::<Error>().is_none());
+    }
+
+    #[cfg(feature = "hrana_backend")]
+    #[test]
+    fn test_websocket_failure() {
+        let e = anyhow::Error::from(tungstenite::Error::ConnectionClosed).context("request failed");
+        assert!(is_websocket_failure(&e));
+        let e = anyhow::Error::from(tungstenite::Error::Protocol(
+            tungstenite::error::ProtocolError::ResetWithoutClosingHandshake,
+        ));
+        assert!(is_websocket_failure(&e));
+        for message in ["no such table: connections", "websocket_sessions is closed"] {
+            let e = anyhow::anyhow!("SQLITE_ERROR: {message}");
+            assert!(!is_websocket_failure(&e), "{message}");
+        }
+        let e = into_batch_interrupted(anyhow::Error::from(tungstenite::Error::ConnectionClosed));
+        assert!(matches!(
+            e.downcast_ref::<Error>(),
+            Some(Error::BatchInterrupted { .. })
+        ));
+        let e = into_batch_interrupted(
+            Error::Unauthorized {
+                message: String::new(),
+            }
+            .into(),
+        );
+        assert!(matches!(
+            e.downcast_ref::<Error>(),
+            Some(Error::Unauthorized { .. })
+        ));
+    }
+}
@@ -0,0 +1,222 @@
+//! `explain` inspects how SQLite runs a query, through `EXPLAIN QUERY PLAN`.
+
+use anyhow::Result;
+
+use crate::{Client, Error, ResultSet, Statement};
+
+/// Step of a query plan, e.g. `SCAN users` or `SEARCH users USING INDEX idx_email (email=?)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlanNode {
+    /// Identifier of the step, unique within the plan.
+    pub id: i64,
+    /// Identifier of the enclosing step, 0 for top-level steps.
+    pub parent: i64,
+    /// Description of the step, as reported by SQLite.
+    pub detail: String,
+    /// Steps nested in this one, e.g. the scans of a subquery.
+    pub children: Vec<PlanNode>,
+}
+
+/// Query plan of a statement, as a tree of steps. See [`Client::explain()`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QueryPlan {
+    /// Top-level steps, in the order reported by SQLite.
+    pub nodes: Vec<PlanNode>,
+}
+
+impl PlanNode {
+    fn visit<'a>(&'a self, nodes: &mut Vec<&'a PlanNode>) {
+        nodes.push(self);
+        for child in &self.children {
+            child.visit(nodes);
+        }
+    }
+}
+
+impl QueryPlan {
+    /// Returns all the steps of the plan, parents before their children.
+    pub fn iter(&self) -> impl Iterator<Item = &PlanNode> {
+        let mut nodes = Vec::new();
+        for node in &self.nodes {
+            node.visit(&mut nodes);
+        }
+        nodes.into_iter()
+    }
+
+    /// Returns the steps which scan a whole table, without the help of an index.
+    /// Scans of covering indexes and of subquery results are not included.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # async fn run() -> anyhow::Result<()> {
+    /// let db = libsql_client::Client::in_memory()?;
+    /// db.execute("CREATE TABLE users(id INTEGER PRIMARY KEY, email TEXT)").await?;
+    /// let plan = db.explain("SELECT * FROM users WHERE email = 'a@b.c'").await?;
+    /// assert_eq!(plan.full_scans()[0].detail, "SCAN users");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn full_scans(&self) -> Vec<&PlanNode> {
+        self.iter()
+            .filter(|node| {
+                let mut words = node.detail.split_whitespace();
+                // Older SQLite versions report `SCAN TABLE t`
+                words.next() == Some("SCAN")
+                    && !node.detail.contains(" USING ")
+                    && !matches!(words.next(), Some("SUBQUERY" | "CONSTANT"))
+            })
+            .collect()
+    }
+
+    // Builds the tree out of `EXPLAIN QUERY PLAN` rows: id, parent, notused, detail
+    fn from_rows(rs: ResultSet) -> Result<QueryPlan> {
+        let mut flat = Vec::with_capacity(rs.rows.len());
+        for row in &rs.rows {
+            flat.push(PlanNode {
+                id: row.try_get(0)?,
+                parent: row.try_get(1)?,
+                detail: row.try_get::<&str>(3)?.to_string(),
+                children: vec![],
+            });
+        }
+        // SQLite lists parents before their children, so attaching the nodes
+        // from the last one keeps every parent in place until its children are moved
+        let mut nodes: Vec<PlanNode> = Vec::with_capacity(flat.len());
+        while let Some(node) = flat.pop() {
+            match flat
+                .iter_mut()
+                .rev()
+                .find(|parent| parent.id == node.parent)
+            {
+                Some(parent) => parent.children.insert(0, node),
+                None => nodes.insert(0, node),
+            }
+        }
+        Ok(QueryPlan { nodes })
+    }
+}
+
+impl Client {
+    /// Returns the query plan of a statement, by executing `EXPLAIN QUERY PLAN` on it.
+    /// The statement itself is not executed, but its parameters are bound.
+    ///
+    /// Fails with [Error::Misuse] if the statement can't be explained: if it's empty,
+    /// holds multiple statements or is already an `EXPLAIN`.
+    ///
+    /// # Arguments
+    /// * `stmt` - the SQL statement
+    pub async fn explain(&self, stmt: impl Into<Statement> + Send) -> Result<QueryPlan> {
+        let Statement { sql, args } = stmt.into();
+        match crate::script::split_statements(&sql).as_slice() {
+            [single] => {
+                let first_word = single.split_whitespace().next().unwrap_or_default();
+                if first_word.eq_ignore_ascii_case("EXPLAIN") {
+                    return Err(
+                        Error::Misuse(format!("Statement is already explained: {sql}")).into(),
+                    );
+                }
+            }
+            [] => return Err(Error::Misuse("empty statement".to_string()).into()),
+            _ => {
+                return Err(Error::Misuse(format!(
+                    "Only a single statement can be explained: {sql}"
+                ))
+                .into())
+            }
+        }
+        let rs = self
+            .execute(Statement {
+                sql: format!("EXPLAIN QUERY PLAN {sql}"),
+                args,
+            })
+            .await?;
+        QueryPlan::from_rows(rs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: i64, parent: i64, detail: &str, children: Vec<PlanNode>) -> PlanNode {
+        PlanNode {
+            id,
+            parent,
+            detail: detail.to_string(),
+            children,
+        }
+    }
+
+    #[test]
+    fn test_plan_tree() {
+        let rows: Vec<Vec<crate::Value>> = [
+            (2, 0, "SCAN users"),
+            (4, 0, "CORRELATED SCALAR SUBQUERY 1"),
+            (8, 4, "SEARCH orders USING INDEX idx_user (user_id=?)"),
+            (10, 4, "SCAN tags"),
+            (12, 0, "USE TEMP B-TREE FOR ORDER BY"),
+        ]
+        .into_iter()
+        .map(|(id, parent, detail)| vec![id.into(), parent.into(), 0.into(), detail.into()])
+        .collect();
+        let rs = ResultSet {
+            columns: ["id", "parent", "notused", "detail"]
+                .map(String::from)
+                .to_vec(),
+            rows: rows
+                .into_iter()
+                .map(|values| crate::Row {
+                    values,
+                    #[cfg(feature = "mapping_names_to_values_in_rows")]
+                    value_map: Default::default(),
+                })
+                .collect(),
+            rows_affected: 0,
+            last_insert_rowid: None,
+        };
+        let plan = QueryPlan::from_rows(rs).unwrap();
+        assert_eq!(
+            plan.nodes,
+            [
+                node(2, 0, "SCAN users", vec![]),
+                node(
+                    4,
+                    0,
+                    "CORRELATED SCALAR SUBQUERY 1",
+                    vec![
+                        node(
+                            8,
+                            4,
+                            "SEARCH orders USING INDEX idx_user (user_id=?)",
+                            vec![]
+                        ),
+                        node(10, 4, "SCAN tags", vec![]),
+                    ]
+                ),
+                node(12, 0, "USE TEMP B-TREE FOR ORDER BY", vec![]),
+            ]
+        );
+        let scans: Vec<i64> = plan.full_scans().iter().map(|node| node.id).collect();
+        assert_eq!(scans, [2, 10]);
+    }
+
+    #[cfg(feature = "local_backend")]
+    #[test]
+    fn test_explain_rejects_unexplainable_statements() {
+        let db = Client::in_memory().unwrap();
+        for sql in ["", "SELECT 1; SELECT 2", "EXPLAIN SELECT 1"] {
+            let e = futures::executor::block_on(db.explain(sql)).unwrap_err();
+            assert!(
+                matches!(e.downcast_ref::<Error>(), Some(Error::Misuse(_))),
+                "{sql:?} should be rejected"
+            );
+        }
+        let plan = futures::executor::block_on(db.explain(Statement::with_args(
+            "SELECT * FROM sqlite_master WHERE name = ?",
+            &["t"],
+        )))
+        .unwrap();
+        assert!(!plan.nodes.is_empty());
+    }
+}
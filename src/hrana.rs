@@ -1,20 +1,183 @@
 use crate::client::Config;
 use anyhow::Result;
+use fallible_iterator::FallibleIterator;
+use futures::FutureExt;
+use sqlite3_parser::ast::{Cmd, Name, Stmt};
+use sqlite3_parser::lexer::sql::Parser;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use std::sync::RwLock;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
 
-use crate::{utils, BatchResult, ResultSet, Statement};
+use crate::batch::BatchBuilder;
+use crate::error::{
+    into_batch_interrupted, is_websocket_failure, with_malformed_response, with_unauthorized,
+};
+use crate::{BatchResult, ResultSet, Statement};
 
 /// Database client. This is the main structure used to
 /// communicate with the database.
+///
+/// The Hrana protocol version is negotiated by the WebSocket handshake
+/// while connecting, so unlike the HTTP client it cannot be pinned.
 pub struct Client {
     url: String,
     token: Option<String>,
+    // Unix domain socket of the server, reached through a loopback bridge
+    socket: Option<std::path::PathBuf>,
 
     client: hrana_client::Client,
-    client_future: hrana_client::ConnFut,
-    streams_for_transactions: RwLock<HashMap<u64, Arc<hrana_client::Stream>>>,
+    connection: Mutex<ConnState>,
+    streams_for_transactions: RwLock<HashMap<u64, TransactionStream>>,
+    // Next id handed out by begin()
+    next_tx_id: AtomicU64,
+    // Stream shared by non-transactional statements, if reusing streams is enabled
+    reuse_stream: bool,
+    default_stream: RwLock<Option<Arc<hrana_client::Stream>>>,
+    on_connection_event: Option<Arc<ConnectionEventFn>>,
+    // Set once a lost connection was reported, so that it's reported once per connection
+    disconnected: AtomicBool,
+}
+
+/// Change of the state of the WebSocket connection, see [Client::on_connection_event].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConnectionEvent {
+    /// The connection was established by [Client::reconnect].
+    Connected,
+    /// A request failed because the connection was lost, or [Client::reconnect] failed.
+    Disconnected { reason: String },
+    /// [Client::reconnect] started to establish a new connection.
+    Reconnecting,
+}
+
+type ConnectionEventFn = dyn Fn(ConnectionEvent) + Send + Sync;
+
+// State of the task which runs the WebSocket connection
+enum ConnState {
+    Running(Pin<Box<hrana_client::ConnFut>>),
+    // The task finished, e.g. because the connection was lost, so it can't be polled again
+    Ended,
+}
+
+// URL which the WebSocket client dials to reach the server
+async fn connect_url(url: &str, socket: Option<&std::path::Path>) -> Result<String> {
+    match socket {
+        #[cfg(all(unix, feature = "unix_backend"))]
+        Some(path) => unix_bridge(path.to_owned()).await,
+        _ => Ok(url.to_string()),
+    }
+}
+
+// How long a bridge waits for the WebSocket client, which connects right after it's opened
+#[cfg(all(unix, feature = "unix_backend"))]
+const BRIDGE_ACCEPT_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Connects to the Unix domain socket at `path` and forwards the first connection
+// accepted on an ephemeral loopback port to it. Returns the URL of that port.
+#[cfg(all(unix, feature = "unix_backend"))]
+async fn unix_bridge(path: std::path::PathBuf) -> Result<String> {
+    let mut unix = tokio::net::UnixStream::connect(&path)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to connect to {}: {e}", path.display()))?;
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let url = format!("ws://{}", listener.local_addr()?);
+    tokio::spawn(async move {
+        match tokio::time::timeout(BRIDGE_ACCEPT_TIMEOUT, listener.accept()).await {
+            Ok(Ok((mut tcp, _))) => {
+                drop(listener);
+                if let Err(e) = tokio::io::copy_bidirectional(&mut tcp, &mut unix).await {
+                    tracing::debug!("Bridge to {} closed: {e}", path.display());
+                }
+            }
+            Ok(Err(e)) => tracing::warn!("Bridge to {} failed: {e}", path.display()),
+            Err(_) => tracing::debug!("Bridge to {} was never connected", path.display()),
+        }
+    });
+    Ok(url)
+}
+
+/// Ids handed out by [Client::begin] start here, so that they don't collide with
+/// the ids which callers of [Client::execute_in_transaction] choose themselves,
+/// as long as those stay below 2^63.
+const FIRST_GENERATED_TX_ID: u64 = 1 << 63;
+
+// Stream of an open transaction. Dropping the last reference closes the stream,
+// and the server rolls back the transaction if it's still open.
+struct TransactionStream {
+    stream: Arc<hrana_client::Stream>,
+    opened_at: Instant,
+    state: TransactionState,
+}
+
+// Transaction started on a stream with BEGIN or SAVEPOINT, and not yet ended.
+// Without one, there's nothing to commit or roll back.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct TransactionState {
+    // Whether BEGIN started the transaction, which then outlives its savepoints
+    begun: bool,
+    // Names of the open savepoints, outermost first
+    savepoints: Vec<String>,
+}
+
+// Savepoint names are case-insensitive identifiers, which may be quoted
+fn savepoint_name(name: &Name) -> String {
+    name.0
+        .trim_matches(|c| matches!(c, '"' | '\'' | '`' | '[' | ']'))
+        .to_ascii_lowercase()
+}
+
+// Returns the statements of `sql` which start or end transactions or savepoints
+fn transaction_statements(sql: &str) -> Vec<Stmt> {
+    let mut parser = Parser::new(sql.as_bytes());
+    let mut stmts = Vec::new();
+    while let Ok(Some(cmd)) = parser.next() {
+        if let Cmd::Stmt(
+            stmt @ (Stmt::Begin(..)
+            | Stmt::Savepoint(_)
+            | Stmt::Release(_)
+            | Stmt::Commit(_)
+            | Stmt::Rollback { .. }),
+        ) = cmd
+        {
+            stmts.push(stmt);
+        }
+    }
+    stmts
+}
+
+impl TransactionState {
+    fn is_open(&self) -> bool {
+        self.begun || !self.savepoints.is_empty()
+    }
+
+    // Updates the state after the statements were executed successfully
+    fn apply(&mut self, stmts: &[Stmt]) {
+        for stmt in stmts {
+            match stmt {
+                Stmt::Begin(..) => self.begun = true,
+                Stmt::Savepoint(name) => self.savepoints.push(savepoint_name(name)),
+                // Releasing a savepoint also releases the ones opened after it, and
+                // releasing the outermost one commits a transaction it started
+                Stmt::Release(name) => {
+                    let name = savepoint_name(name);
+                    if let Some(position) = self.savepoints.iter().rposition(|sp| *sp == name) {
+                        self.savepoints.truncate(position);
+                    }
+                }
+                // ROLLBACK TO keeps the savepoint open
+                Stmt::Rollback {
+                    savepoint_name: Some(_),
+                    ..
+                } => {}
+                Stmt::Commit(_) | Stmt::Rollback { .. } => *self = Self::default(),
+                _ => {}
+            }
+        }
+    }
 }
 
 impl std::fmt::Debug for Client {
@@ -29,33 +192,213 @@ impl std::fmt::Debug for Client {
 impl Client {
     /// Creates a database client with JWT authentication.
     ///
+    /// Fails with [crate::Error::Unauthorized] if the server rejects the WebSocket
+    /// handshake with HTTP 401. The protocol's errors carry no codes, so a token
+    /// rejected after the handshake is reported with the server's message only.
+    ///
+    /// The connection is made over TCP. To reach a co-located sqld over its Unix domain
+    /// socket, use [Client::new_unix].
+    ///
     /// # Arguments
     /// * `url` - URL of the database endpoint
     /// * `token` - auth token
     pub async fn new(url: impl Into<String>, token: impl Into<String>) -> Result<Self> {
-        let token = token.into();
-        let token = if token.is_empty() { None } else { Some(token) };
-        let url = url.into();
+        Self::connect(url.into(), token.into(), None).await
+    }
 
-        let (client, client_future) = hrana_client::Client::connect(&url, token.clone()).await?;
+    /// Creates a database client connected to a co-located sqld over its Unix domain socket,
+    /// e.g. a sidecar.
+    ///
+    /// The WebSocket client only dials URLs, so the socket is reached through a bridge which
+    /// listens on an ephemeral loopback port and forwards the first connection it accepts.
+    /// [Client::reconnect] opens a new bridge. Another local process which connects to the
+    /// bridge first still has to pass the token check of the server.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # async fn f() -> anyhow::Result<()> {
+    /// let db = libsql_client::hrana::Client::new_unix("/var/run/sqld.sock", "").await?;
+    /// db.execute("SELECT 1").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(all(unix, feature = "unix_backend"))]
+    pub async fn new_unix(
+        path: impl Into<std::path::PathBuf>,
+        token: impl Into<String>,
+    ) -> Result<Self> {
+        let path = path.into();
+        let url = format!("unix://{}", path.display());
+        Self::connect(url, token.into(), Some(path)).await
+    }
+
+    async fn connect(
+        url: String,
+        token: String,
+        socket: Option<std::path::PathBuf>,
+    ) -> Result<Self> {
+        let token = if token.is_empty() { None } else { Some(token) };
+        let (client, client_future) = hrana_client::Client::connect(
+            &connect_url(&url, socket.as_deref()).await?,
+            token.clone(),
+        )
+        .await
+        .map_err(|e| with_unauthorized(e.into()))?;
 
         Ok(Self {
             url,
             token,
+            socket,
             client,
-            client_future,
+            connection: Mutex::new(ConnState::Running(Box::pin(client_future))),
             streams_for_transactions: RwLock::new(HashMap::new()),
+            next_tx_id: AtomicU64::new(FIRST_GENERATED_TX_ID),
+            reuse_stream: false,
+            default_stream: RwLock::new(None),
+            on_connection_event: None,
+            disconnected: AtomicBool::new(false),
         })
     }
 
     pub async fn reconnect(&mut self) -> Result<()> {
+        self.emit(ConnectionEvent::Reconnecting);
+        let url = match connect_url(&self.url, self.socket.as_deref()).await {
+            Ok(url) => url,
+            Err(e) => {
+                self.emit(ConnectionEvent::Disconnected {
+                    reason: e.to_string(),
+                });
+                return Err(e);
+            }
+        };
         let (client, client_future) =
-            hrana_client::Client::connect(&self.url, self.token.clone()).await?;
+            match hrana_client::Client::connect(&url, self.token.clone()).await {
+                Ok(connection) => connection,
+                Err(e) => {
+                    let e = with_unauthorized(e.into());
+                    self.emit(ConnectionEvent::Disconnected {
+                        reason: e.to_string(),
+                    });
+                    return Err(e);
+                }
+            };
         self.client = client;
-        self.client_future = client_future;
+        *self.connection.lock().unwrap() = ConnState::Running(Box::pin(client_future));
+        *self.default_stream.write().unwrap() = None;
+        self.disconnected.store(false, Ordering::Relaxed);
+        self.emit(ConnectionEvent::Connected);
         Ok(())
     }
 
+    /// Registers a callback notified of changes of the connection's state, e.g. to
+    /// show them on a dashboard. It's called synchronously, so it should return quickly.
+    ///
+    /// A lost connection is noticed, and reported once, when a request fails because of it.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # async fn f() -> anyhow::Result<()> {
+    /// use libsql_client::hrana::{Client, ConnectionEvent};
+    ///
+    /// let db = Client::new("ws://localhost:8080", "")
+    ///     .await?
+    ///     .on_connection_event(|event| {
+    ///         if let ConnectionEvent::Disconnected { reason } = event {
+    ///             eprintln!("Lost the database connection: {reason}");
+    ///         }
+    ///     });
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn on_connection_event(
+        mut self,
+        callback: impl Fn(ConnectionEvent) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_connection_event = Some(Arc::new(callback));
+        self
+    }
+
+    fn emit(&self, event: ConnectionEvent) {
+        tracing::debug!("Connection event: {event:?}");
+        if let Some(callback) = &self.on_connection_event {
+            callback(event);
+        }
+    }
+
+    // Checks whether the task running the connection has finished, without waiting for it
+    fn connection_ended(&self) -> bool {
+        let mut connection = self.connection.lock().unwrap();
+        if let ConnState::Running(future) = &mut *connection {
+            if future.now_or_never().is_none() {
+                return false;
+            }
+            *connection = ConnState::Ended;
+        }
+        true
+    }
+
+    // Checks whether a request failed because the connection was lost, judging by the
+    // WebSocket error which caused it, or by the connection's task having finished.
+    // The messages of errors are never inspected, since SQL errors may say anything.
+    fn is_connection_lost(&self, e: &anyhow::Error) -> bool {
+        e.downcast_ref::<crate::Error>().is_none()
+            && (is_websocket_failure(e) || self.connection_ended())
+    }
+
+    // Converts an error of a request, reporting the loss of the connection if it caused it
+    fn request_error(&self, e: impl Into<anyhow::Error>) -> anyhow::Error {
+        let e = with_malformed_response(with_unauthorized(e.into()));
+        if self.is_connection_lost(&e) && !self.disconnected.swap(true, Ordering::Relaxed) {
+            self.emit(ConnectionEvent::Disconnected {
+                reason: e.to_string(),
+            });
+        }
+        e
+    }
+
+    /// Makes non-transactional [`Client::execute()`] calls share a single stream,
+    /// opened lazily and reopened after an error, instead of opening a stream per call.
+    /// This saves a protocol round trip for each statement, e.g. in loops of point reads.
+    /// Transactions keep using their own streams.
+    pub fn with_stream_reuse(mut self, enabled: bool) -> Self {
+        self.reuse_stream = enabled;
+        self
+    }
+
+    // Returns the shared stream for non-transactional statements, opening it if needed.
+    async fn default_stream(&self) -> Result<Arc<hrana_client::Stream>> {
+        if let Some(stream) = self.default_stream.read().unwrap().as_ref() {
+            return Ok(stream.clone());
+        }
+        // Like in stream_for_transaction(), concurrent callers may both open a stream,
+        // in which case the first one to finish gets cached
+        let stream = Arc::new(self.client.open_stream().await?);
+        let mut default_stream = self.default_stream.write().unwrap();
+        Ok(default_stream.get_or_insert(stream).clone())
+    }
+
+    // Forgets the shared stream, which gets closed once requests still using it are dropped
+    pub(crate) fn drop_default_stream(&self) {
+        *self.default_stream.write().unwrap() = None;
+    }
+
+    /// Opens a stream of its own and hands it over, for protocol requests which this
+    /// client doesn't wrap, e.g. describing or running sequences of statements.
+    ///
+    /// The stream bypasses this client's bookkeeping: it's not tracked by
+    /// [`Client::open_transactions()`] nor closed by [`Client::cleanup_stale_transactions()`],
+    /// and transactions opened on it are not visible to [`Client::execute_in_transaction()`].
+    /// The stream is closed once the last reference to it is dropped.
+    ///
+    /// Only available with the `unstable` feature, since the stream's API belongs
+    /// to the `hrana-client` crate and may change with it.
+    #[cfg(feature = "unstable")]
+    pub async fn open_raw_stream(&self) -> Result<Arc<hrana_client::Stream>> {
+        Ok(Arc::new(self.client.open_stream().await?))
+    }
+
     /// Creates a database client, given a `Url`
     ///
     /// # Arguments
@@ -77,11 +420,15 @@ impl Client {
     where
         <T as TryInto<url::Url>>::Error: std::fmt::Display,
     {
-        let mut url: url::Url = url
+        let url: url::Url = url
             .try_into()
             .map_err(|e| anyhow::anyhow!(format!("{e}")))?;
         // remove the auth token from the URL so that it doesn't get logged anywhere
-        let token = utils::pop_query_param(&mut url, "authToken".to_string());
+        let Config {
+            url,
+            auth_token: token,
+            ..
+        } = Config::from_url_str(url.as_str())?;
         let url_str = if url.scheme() == "libsql" {
             let new_url = format!("wss://{}", url.as_str().strip_prefix("libsql://").unwrap());
             url::Url::parse(&new_url).unwrap().to_string()
@@ -97,12 +444,15 @@ impl Client {
 
     /// Creates a database client from a `Config` object.
     pub async fn from_config(config: Config) -> Result<Self> {
+        config.check_auth_token()?;
         Self::new(config.url, config.auth_token.unwrap_or_default()).await
     }
 
     pub async fn shutdown(self) -> Result<()> {
         self.client.shutdown().await?;
-        self.client_future.await?;
+        if let ConnState::Running(future) = self.connection.into_inner().unwrap() {
+            future.await?;
+        }
         Ok(())
     }
 
@@ -113,7 +463,7 @@ impl Client {
             let streams = self.streams_for_transactions.read().unwrap();
             if streams.contains_key(&tx_id) {
                 tracing::trace!("Found stream for transaction {tx_id}");
-                return Ok(streams.get(&tx_id).unwrap().clone()); //NOTICE: safe to unwrap, it was either found or just inserted
+                return Ok(streams.get(&tx_id).unwrap().stream.clone()); //NOTICE: safe to unwrap, it was either found or just inserted
             }
         }
         // Pessimistic path - let's drop the mutex, create the stream and try to reinsert it.
@@ -123,24 +473,72 @@ impl Client {
         tracing::trace!("Created new stream");
         let mut streams = self.streams_for_transactions.write().unwrap();
         if let std::collections::hash_map::Entry::Vacant(e) = streams.entry(tx_id) {
-            e.insert(stream.clone());
+            e.insert(TransactionStream {
+                stream: stream.clone(),
+                opened_at: Instant::now(),
+                state: TransactionState::default(),
+            });
         }
         Ok(stream)
     }
 
-    // Drop the stream for given transaction id.
-    fn drop_stream_for_transaction(&self, tx_id: u64) {
+    // Drop the stream for given transaction id. Returns the stream if a transaction
+    // was open on it, which then needs to be committed or rolled back.
+    fn drop_stream_for_transaction(&self, tx_id: u64) -> Option<Arc<hrana_client::Stream>> {
         let mut streams = self.streams_for_transactions.write().unwrap();
         tracing::trace!("Dropping stream for transaction {tx_id}");
-        streams.remove(&tx_id);
+        streams
+            .remove(&tx_id)
+            .filter(|stream| stream.state.is_open())
+            .map(|stream| stream.stream)
     }
 
-    fn into_hrana(stmt: Statement) -> hrana_client::proto::Stmt {
-        let mut hrana_stmt = hrana_client::proto::Stmt::new(stmt.sql, true);
-        for param in stmt.args {
+    // Ends the transaction, by sending `sql` if one was opened with BEGIN or SAVEPOINT,
+    // or just by closing the stream otherwise, which has nothing left to finalize.
+    // A transaction without a stream is not opened just to be finalized.
+    async fn finish_transaction(&self, tx_id: u64, sql: &str) -> Result<()> {
+        let Some(stream) = self.drop_stream_for_transaction(tx_id) else {
+            tracing::trace!("Transaction {tx_id} was not explicitly started, skipping {sql}");
+            return Ok(());
+        };
+        stream
+            .execute(Self::into_hrana(Cow::Owned(Statement::from(sql)))?)
+            .await
+            .map(|_| ())
+            .map_err(|e| self.request_error(e))
+    }
+
+    /// Returns the number of transactions whose streams are open,
+    /// i.e. which were neither committed nor rolled back yet.
+    pub fn open_transactions(&self) -> usize {
+        self.streams_for_transactions.read().unwrap().len()
+    }
+
+    /// Closes the streams of transactions opened more than `older_than` ago, e.g. ones
+    /// abandoned by [`Client::execute_in_transaction()`] callers which neither committed
+    /// nor rolled back. The server rolls the transactions back. Returns how many were closed.
+    pub fn cleanup_stale_transactions(&self, older_than: Duration) -> usize {
+        let mut streams = self.streams_for_transactions.write().unwrap();
+        let before = streams.len();
+        streams.retain(|tx_id, tx| {
+            let stale = tx.opened_at.elapsed() > older_than;
+            if stale {
+                tracing::debug!("Closing the stream of stale transaction {tx_id}");
+            }
+            !stale
+        });
+        before - streams.len()
+    }
+
+    // Borrowed statements are copied, since the request owns its SQL and parameters
+    fn into_hrana(stmt: Cow<'_, Statement>) -> Result<hrana_client::proto::Stmt> {
+        stmt.check_not_empty()?;
+        let Statement { sql, args } = stmt.into_owned();
+        let mut hrana_stmt = hrana_client::proto::Stmt::new(sql, true);
+        for param in args {
             hrana_stmt.bind(param);
         }
-        hrana_stmt
+        Ok(hrana_stmt)
     }
 }
 
@@ -149,64 +547,370 @@ impl Client {
         &self,
         stmts: impl IntoIterator<Item = impl Into<Statement>>,
     ) -> anyhow::Result<BatchResult> {
-        let mut batch = hrana_client::proto::Batch::new();
-        for stmt in stmts.into_iter() {
-            let stmt: Statement = stmt.into();
-            let mut hrana_stmt = hrana_client::proto::Stmt::new(stmt.sql, true);
-            for param in stmt.args {
-                hrana_stmt.bind(param);
-            }
-            batch.step(None, hrana_stmt);
+        let mut batch = BatchBuilder::new();
+        for stmt in stmts {
+            batch = batch.step(stmt);
+        }
+        self.execute_batch(batch).await
+    }
+
+    /// Executes a batch whose steps may be conditioned on the outcomes of earlier steps.
+    ///
+    /// Steps run one after another on a fresh stream, each in its own transaction
+    /// unless the batch itself contains `BEGIN` and `COMMIT`. The server reports all
+    /// the results at once, so if the connection drops before they arrive, the batch
+    /// fails with [crate::Error::BatchInterrupted]: some steps may have been applied.
+    pub async fn execute_batch(&self, batch: BatchBuilder) -> anyhow::Result<BatchResult> {
+        let mut hrana_batch = hrana_client::proto::Batch::new();
+        for (stmt, cond) in batch.steps {
+            hrana_batch.step(
+                cond.map(|c| c.to_proto()),
+                Self::into_hrana(Cow::Owned(stmt))?,
+            );
         }
 
         let stream = self.client.open_stream().await?;
-        stream
-            .execute_batch(batch)
-            .await
-            .map_err(|e| anyhow::anyhow!("{}", e))
+        stream.execute_batch(hrana_batch).await.map_err(|e| {
+            let e = self.request_error(e);
+            if self.is_connection_lost(&e) {
+                into_batch_interrupted(e)
+            } else {
+                e
+            }
+        })
     }
 
+    /// Executes a statement on a fresh stream, outside of any transaction,
+    /// even if transactions are currently open on this client. Use
+    /// [`Client::execute_in_transaction()`] to run a statement inside one.
+    /// Debug builds log a warning when this happens, since it's usually a mistake.
     pub async fn execute(&self, stmt: impl Into<Statement>) -> Result<ResultSet> {
-        let stmt = Self::into_hrana(stmt.into());
+        self.execute_stmt(Self::into_hrana(Cow::Owned(stmt.into()))?)
+            .await
+    }
+
+    /// Executes a statement like [`Client::execute()`], without consuming it,
+    /// so that a statement built once can be executed many times.
+    pub async fn execute_ref(&self, stmt: &Statement) -> Result<ResultSet> {
+        self.execute_stmt(Self::into_hrana(Cow::Borrowed(stmt))?)
+            .await
+    }
+
+    async fn execute_stmt(&self, stmt: hrana_client::proto::Stmt) -> Result<ResultSet> {
+        #[cfg(debug_assertions)]
+        {
+            let open_transactions = self.open_transactions();
+            if open_transactions > 0 {
+                tracing::warn!(
+                    "Executing a statement outside of a transaction while {open_transactions} transaction(s) are open"
+                );
+            }
+        }
+        if self.reuse_stream {
+            let stream = self.default_stream().await?;
+            let result = stream.execute(stmt).await;
+            if result.is_err() {
+                tracing::trace!("Dropping the shared stream after an error");
+                *self.default_stream.write().unwrap() = None;
+            }
+            return result
+                .map(ResultSet::from)
+                .map_err(|e| self.request_error(e));
+        }
 
         let stream = self.client.open_stream().await?;
         stream
             .execute(stmt)
             .await
             .map(ResultSet::from)
-            .map_err(|e| anyhow::anyhow!("{}", e))
+            .map_err(|e| self.request_error(e))
+    }
+
+    /// Opens a stream ahead of the first request. With stream reuse enabled,
+    /// the stream is kept for later [`Client::execute()`] calls, otherwise this checks
+    /// that the WebSocket connection is alive. The connection itself is established
+    /// by the constructors.
+    ///
+    /// With stream reuse enabled, calls after the first one return immediately
+    /// while the shared stream is open.
+    pub async fn warmup(&self) -> Result<()> {
+        if self.reuse_stream {
+            self.default_stream().await.map(|_| ())
+        } else {
+            self.ping().await
+        }
+    }
+
+    /// Checks that the connection is alive, by executing a trivial statement
+    /// on a fresh stream.
+    pub async fn ping(&self) -> Result<()> {
+        let stream = self.client.open_stream().await?;
+        stream
+            .execute(hrana_client::proto::Stmt::new("SELECT 1", false))
+            .await
+            .map(|_| ())
+            .map_err(|e| self.request_error(e))
+    }
+
+    /// Opens a transaction on a stream of its own. If the returned handle is dropped
+    /// without [`TransactionHandle::commit()`] or [`TransactionHandle::rollback()`],
+    /// its stream is closed and the server rolls the transaction back.
+    pub async fn begin(&self) -> Result<TransactionHandle<'_>> {
+        let tx_id = self.next_tx_id.fetch_add(1, Ordering::Relaxed);
+        let handle = TransactionHandle {
+            client: self,
+            tx_id,
+            finished: false,
+        };
+        self.execute_in_transaction(tx_id, Statement::from("BEGIN"))
+            .await?;
+        Ok(handle)
     }
 
     pub async fn execute_in_transaction(&self, tx_id: u64, stmt: Statement) -> Result<ResultSet> {
-        let stmt = Self::into_hrana(stmt);
+        let stmt = Self::into_hrana(Cow::Owned(stmt))?;
         tracing::trace!("Transaction {tx_id} executing {}", stmt.sql);
         let stream = self.stream_for_transaction(tx_id).await?;
-        stream
+        let transaction_stmts = transaction_statements(&stmt.sql);
+        let rs = stream
             .execute(stmt)
             .await
             .map(ResultSet::from)
-            .map_err(|e| anyhow::anyhow!("{}", e))
+            .map_err(|e| self.request_error(e))?;
+        if !transaction_stmts.is_empty() {
+            if let Some(stream) = self
+                .streams_for_transactions
+                .write()
+                .unwrap()
+                .get_mut(&tx_id)
+            {
+                stream.state.apply(&transaction_stmts);
+            }
+        }
+        Ok(rs)
     }
 
+    /// Commits the transaction. If no transaction was started on its stream
+    /// with `BEGIN` or `SAVEPOINT`, its statements were already committed
+    /// one by one, so the stream is just closed.
     pub async fn commit_transaction(&self, tx_id: u64) -> Result<()> {
         tracing::trace!("Transaction {tx_id} commit");
-        let stream = self.stream_for_transaction(tx_id).await?;
-        self.drop_stream_for_transaction(tx_id);
-        stream
-            .execute(Self::into_hrana(Statement::from("COMMIT")))
-            .await
-            .map(|_| ())
-            .map_err(|e| anyhow::anyhow!("{}", e))
+        self.finish_transaction(tx_id, "COMMIT").await
     }
 
+    /// Rolls back the transaction. If no transaction was started on its stream
+    /// with `BEGIN` or `SAVEPOINT`, there's nothing to roll back, so the stream is just closed.
     pub async fn rollback_transaction(&self, tx_id: u64) -> Result<()> {
         tracing::trace!("Transaction {tx_id} rollback");
-        let stream = self.stream_for_transaction(tx_id).await?;
-        self.drop_stream_for_transaction(tx_id);
-        stream
-            .execute(Self::into_hrana(Statement::from("ROLLBACK")))
+        self.finish_transaction(tx_id, "ROLLBACK").await
+    }
+}
+
+/// Transaction opened with [Client::begin]. Dropping it without committing
+/// or rolling back closes its stream, so that the server rolls it back
+/// instead of keeping it open forever.
+#[derive(Debug)]
+pub struct TransactionHandle<'a> {
+    client: &'a Client,
+    tx_id: u64,
+    finished: bool,
+}
+
+impl<'a> TransactionHandle<'a> {
+    /// Returns the id under which the transaction is tracked by the client.
+    pub fn id(&self) -> u64 {
+        self.tx_id
+    }
+
+    /// Executes a statement within the transaction.
+    pub async fn execute(&self, stmt: impl Into<Statement>) -> Result<ResultSet> {
+        self.client
+            .execute_in_transaction(self.tx_id, stmt.into())
             .await
-            .map(|_| ())
-            .map_err(|e| anyhow::anyhow!("{}", e))
+    }
+
+    /// Commits the transaction.
+    pub async fn commit(mut self) -> Result<()> {
+        self.finished = true;
+        self.client.commit_transaction(self.tx_id).await
+    }
+
+    /// Rolls back the transaction.
+    pub async fn rollback(mut self) -> Result<()> {
+        self.finished = true;
+        self.client.rollback_transaction(self.tx_id).await
+    }
+}
+
+impl Drop for TransactionHandle<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            tracing::debug!(
+                "Transaction {} dropped without commit or rollback, closing its stream",
+                self.tx_id
+            );
+            self.client.drop_stream_for_transaction(self.tx_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_after(sqls: &[&str]) -> TransactionState {
+        let mut state = TransactionState::default();
+        for sql in sqls {
+            state.apply(&transaction_statements(sql));
+        }
+        state
+    }
+
+    #[test]
+    fn test_transaction_state() {
+        // Autocommit: statements don't leave a transaction to finalize
+        assert!(!state_after(&["INSERT INTO t VALUES (1)"]).is_open());
+        assert!(!state_after(&["ROLLBACK TO sp"]).is_open());
+        // Explicit transactions, also behind comments
+        assert!(state_after(&["/* tag: tx */ BEGIN IMMEDIATE"]).is_open());
+        assert!(state_after(&["SAVEPOINT sp"]).is_open());
+        assert!(!state_after(&["BEGIN", "END"]).is_open());
+        assert!(!state_after(&["BEGIN", "commit"]).is_open());
+        assert!(!state_after(&["SAVEPOINT sp", "ROLLBACK"]).is_open());
+        assert!(!state_after(&["BEGIN; COMMIT"]).is_open());
+    }
+
+    #[test]
+    fn test_transaction_state_savepoints() {
+        // Releasing the outermost savepoint commits the transaction it started
+        assert!(
+            !state_after(&["SAVEPOINT sp", "INSERT INTO t VALUES (1)", "RELEASE sp"]).is_open()
+        );
+        assert!(!state_after(&[
+            "SAVEPOINT sp; INSERT INTO t VALUES (1); RELEASE SAVEPOINT \"SP\""
+        ])
+        .is_open());
+        // Releasing a nested one doesn't
+        let state = state_after(&["SAVEPOINT a", "SAVEPOINT b", "RELEASE b"]);
+        assert_eq!(state.savepoints, ["a"]);
+        // Releasing an outer one releases the ones nested in it
+        assert!(!state_after(&["SAVEPOINT a", "SAVEPOINT b", "RELEASE a"]).is_open());
+        assert!(state_after(&["SAVEPOINT a", "ROLLBACK TO a"]).is_open());
+        // A transaction started with BEGIN outlives its savepoints
+        assert!(state_after(&["BEGIN", "SAVEPOINT a", "RELEASE a"]).is_open());
+    }
+
+    // Accepts one WebSocket connection and answers Hrana requests with empty responses,
+    // except for batches, which fail with `batch_error`, or drop the connection without an answer
+    fn fake_server(batch_error: Option<&'static str>) -> (String, std::thread::JoinHandle<()>) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (socket, _) = listener.accept().unwrap();
+            serve(socket, batch_error);
+        });
+        (url, server)
+    }
+
+    fn serve(socket: impl std::io::Read + std::io::Write, batch_error: Option<&'static str>) {
+        use tungstenite::handshake::server::{Request, Response};
+        use tungstenite::Message;
+
+        let accept_protocol = |request: &Request, mut response: Response| {
+            // Agree to the first protocol version offered by the client
+            if let Some(protocols) = request.headers().get("Sec-WebSocket-Protocol") {
+                let protocol = protocols.to_str().unwrap().split(',').next().unwrap();
+                response
+                    .headers_mut()
+                    .insert("Sec-WebSocket-Protocol", protocol.trim().parse().unwrap());
+            }
+            Ok(response)
+        };
+        let mut ws = tungstenite::accept_hdr(socket, accept_protocol).unwrap();
+        while let Ok(message) = ws.read_message() {
+            let Message::Text(text) = message else {
+                continue;
+            };
+            let msg: serde_json::Value = serde_json::from_str(&text).unwrap();
+            let response = match msg["type"].as_str().unwrap() {
+                "hello" => serde_json::json!({"type": "hello_ok"}),
+                "request" => match (msg["request"]["type"].as_str().unwrap(), batch_error) {
+                    ("batch", None) => return,
+                    ("batch", Some(message)) => serde_json::json!({
+                        "type": "response_error",
+                        "request_id": msg["request_id"],
+                        "error": {"message": message},
+                    }),
+                    (request_type, _) => serde_json::json!({
+                        "type": "response_ok",
+                        "request_id": msg["request_id"],
+                        "response": {"type": request_type},
+                    }),
+                },
+                other => panic!("Unexpected message type {other}"),
+            };
+            ws.write_message(Message::Text(response.to_string()))
+                .unwrap();
+        }
+    }
+
+    fn recorded_events(db: Client) -> (Client, Arc<Mutex<Vec<ConnectionEvent>>>) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = events.clone();
+        let db = db.on_connection_event(move |event| recorded.lock().unwrap().push(event));
+        (db, events)
+    }
+
+    #[tokio::test]
+    async fn test_batch_interrupted_by_disconnect() {
+        let (url, server) = fake_server(None);
+        let (db, events) = recorded_events(Client::new(url, "").await.unwrap());
+        let e = db
+            .raw_batch(["INSERT INTO t VALUES (1)", "INSERT INTO t VALUES (2)"])
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(
+                e.downcast_ref::<crate::Error>(),
+                Some(crate::Error::BatchInterrupted { .. })
+            ),
+            "{e}"
+        );
+        assert!(matches!(
+            events.lock().unwrap().as_slice(),
+            [ConnectionEvent::Disconnected { .. }]
+        ));
+        server.join().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_batch_error_is_not_a_disconnect() {
+        // The message mentions connections, but the connection is fine
+        let (url, _server) = fake_server(Some("SQLITE_ERROR: no such table: connections"));
+        let (db, events) = recorded_events(Client::new(url, "").await.unwrap());
+        let e = db
+            .raw_batch(["INSERT INTO connections VALUES (1)"])
+            .await
+            .unwrap_err();
+        assert!(e.downcast_ref::<crate::Error>().is_none(), "{e}");
+        assert!(e.to_string().contains("no such table: connections"), "{e}");
+        assert!(events.lock().unwrap().is_empty());
+    }
+
+    #[cfg(all(unix, feature = "unix_backend"))]
+    #[tokio::test]
+    async fn test_unix_socket() {
+        let path = std::env::temp_dir().join(format!("libsql-hrana-{}.sock", std::process::id()));
+        std::fs::remove_file(&path).ok();
+        let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+        let _server = std::thread::spawn(move || {
+            let (socket, _) = listener.accept().unwrap();
+            serve(socket, Some("answered over the socket"));
+        });
+        let db = Client::new_unix(&path, "").await.unwrap();
+        let e = db.raw_batch(["SELECT 1"]).await.unwrap_err();
+        assert!(e.to_string().contains("answered over the socket"), "{e}");
+        std::fs::remove_file(&path).ok();
+        assert!(Client::new_unix(&path, "").await.is_err());
     }
 }
@@ -1,9 +1,16 @@
 use crate::client::Config;
 use anyhow::Result;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
+use tracing::Instrument;
 
-use crate::{proto::pipeline, BatchResult, ResultSet, Statement};
+use crate::batch::{BatchBuilder, Condition};
+use crate::retry::{RetryPolicy, Sleeper, TokioSleeper};
+use crate::{
+    proto::pipeline, BatchResult, ConsistencyMode, Error, ProtocolVersion, ResultSet, Statement,
+    Value,
+};
 
 /// Information about the current session: the server-generated cookie
 /// and the URL that should be used for further communication.
@@ -11,16 +18,235 @@ use crate::{proto::pipeline, BatchResult, ResultSet, Statement};
 struct Cookie {
     baton: Option<String>,
     base_url: Option<String>,
+    // SQL texts stored on the stream, see Client::with_sql_cache()
+    stored_sql: crate::sql_cache::StoredSql,
+}
+
+type TokenFn = dyn Fn() -> futures::future::BoxFuture<'static, Result<String>> + Send + Sync;
+
+/// Source of auth tokens, see [Client::with_token_provider].
+#[derive(Clone)]
+pub struct TokenProvider(Arc<TokenFn>);
+
+impl TokenProvider {
+    pub(crate) fn new<F, Fut>(provider: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<String>> + Send + 'static,
+    {
+        Self(Arc::new(move || Box::pin(provider())))
+    }
+}
+
+impl std::fmt::Debug for TokenProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TokenProvider").finish_non_exhaustive()
+    }
 }
 
 /// Generic HTTP client. Needs a helper function that actually sends
 /// the request.
+///
+/// Clones share the streams of open transactions, the replication index, the cached
+/// auth token and the circuit breaker. A clone with other per-request settings,
+/// e.g. from [Client::with_consistency], therefore still acts as the same client.
 #[derive(Clone, Debug)]
 pub struct Client {
     inner: InnerClient,
     cookies: Arc<RwLock<HashMap<u64, Cookie>>>,
+    base_url: String,
     url_for_queries: String,
+    // Scheme added by new() to a URL given without one
+    implicit_scheme: Option<String>,
+    protocol_version: ProtocolVersion,
+    server_version: Arc<RwLock<Option<ProtocolVersion>>>,
     auth: String,
+    token_provider: Option<TokenProvider>,
+    // Authorization header built from the provider's last token, shared by all clones
+    cached_token: Arc<RwLock<Option<String>>>,
+    headers: Vec<(String, String)>,
+    // Correlation id sent with every request instead of generated ones, see with_request_id()
+    request_id: Option<String>,
+    user_agent: String,
+    namespace: Option<String>,
+    namespace_routing: NamespaceRouting,
+    retry_policy: RetryPolicy,
+    max_response_bytes: usize,
+    // Shared by all clones, so that they all stop sending requests to a failing server
+    #[cfg(not(target_arch = "wasm32"))]
+    circuit_breaker: Option<Arc<crate::circuit::CircuitBreaker>>,
+    sleeper: Arc<dyn Sleeper>,
+    consistency: ConsistencyMode,
+    log_params: bool,
+    wire_logging: bool,
+    // Highest replication index reported by the server, shared by all clones
+    last_frame_no: Arc<RwLock<Option<u64>>>,
+    // Set once warmup() succeeds, shared by all clones
+    warmed_up: Arc<std::sync::atomic::AtomicBool>,
+    // Next id handed out by begin(), shared by all clones so that their transactions never collide
+    next_tx_id: Arc<std::sync::atomic::AtomicU64>,
+    sql_cache: Option<Arc<crate::sql_cache::SqlCache>>,
+    #[cfg(feature = "metrics")]
+    metrics: Arc<crate::metrics::Metrics>,
+}
+
+/// Path of the pipeline endpoint, relative to the database URL.
+pub const DEFAULT_PIPELINE_PATH: &str = "v2/pipeline";
+
+/// Path of the usage statistics endpoint, relative to the database URL.
+pub const STATS_PATH: &str = "v1/stats";
+
+/// Header carrying the highest replication index seen by the client, sent in
+/// [ConsistencyMode::Strong] so that a replica catches up before serving the request.
+pub const REPLICATION_INDEX_HEADER: &str = "x-libsql-replication-index";
+
+/// User-Agent sent with every request, unless overridden with [Client::with_user_agent].
+pub const DEFAULT_USER_AGENT: &str = concat!("libsql-client-rs/", env!("CARGO_PKG_VERSION"));
+
+/// Header carrying the client-generated id of each request, see [crate::error::correlation_id].
+pub const CORRELATION_ID_HEADER: &str = "x-correlation-id";
+
+/// Header selecting the database of a multi-tenant sqld, see [NamespaceRouting::Header].
+pub const NAMESPACE_HEADER: &str = "x-namespace";
+
+/// Default limit of the size of responses, see [Client::with_max_response_bytes].
+pub const DEFAULT_MAX_RESPONSE_BYTES: usize = 64 * 1024 * 1024;
+
+/// How [Client::with_namespace] tells a multi-tenant sqld which database to use.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NamespaceRouting {
+    /// Prefixes the host with the namespace, e.g. `db1.example.com` for `example.com`.
+    /// This is what sqld expects by default, and requires DNS resolving the subdomain.
+    #[default]
+    Subdomain,
+    /// Keeps the URL and sends the namespace in the [NAMESPACE_HEADER] header,
+    /// e.g. to reach a server by its IP address.
+    Header,
+}
+
+/// Ids generated by [Client::begin] start here, far above the ids callers
+/// of the deprecated [Client::execute_in_transaction] typically pick by hand.
+const FIRST_GENERATED_TX_ID: u64 = 1 << 63;
+
+/// Opaque id of a transaction opened with [Client::begin].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TxId(u64);
+
+/// Protocol versions tried by [Client::negotiate_protocol_version], newest first.
+const NEGOTIABLE_VERSIONS: [ProtocolVersion; 2] = [ProtocolVersion::V3, ProtocolVersion::V2];
+
+// Joins the base URL and the pipeline path with exactly one slash in between,
+// so that both `http://host` and `http://host/prefix/` work as base URLs.
+fn join_pipeline_url(base_url: &str, pipeline_path: &str) -> String {
+    let pipeline_path = pipeline_path.trim_start_matches('/');
+    let fallback = || format!("{}/{pipeline_path}", base_url.trim_end_matches('/'));
+    match url::Url::parse(base_url) {
+        Ok(mut url) => {
+            // Url::join() replaces the last path segment unless the path ends with a slash
+            if !url.path().ends_with('/') {
+                let path = format!("{}/", url.path());
+                url.set_path(&path);
+            }
+            url.join(pipeline_path)
+                .map(String::from)
+                .unwrap_or_else(|_| fallback())
+        }
+        Err(_) => fallback(),
+    }
+}
+
+// Picks the scheme of a URL given without one: plaintext for the local machine,
+// where sqld usually runs without TLS, and TLS everywhere else
+fn default_scheme_for(url: &str) -> &'static str {
+    let is_local = match url::Url::parse(&format!("http://{url}"))
+        .ok()
+        .as_ref()
+        .and_then(url::Url::host)
+    {
+        Some(url::Host::Domain(domain)) => domain.eq_ignore_ascii_case("localhost"),
+        Some(url::Host::Ipv4(ip)) => ip.is_loopback(),
+        Some(url::Host::Ipv6(ip)) => ip.is_loopback(),
+        None => false,
+    };
+    if is_local {
+        "http"
+    } else {
+        "https"
+    }
+}
+
+// Prefixes the host of the URL with the namespace, unless it's already there,
+// e.g. in stream URLs returned by the server
+fn with_namespace_host(url: String, namespace: &str) -> String {
+    let Ok(mut parsed) = url::Url::parse(&url) else {
+        return url;
+    };
+    let Some(host) = parsed.host_str() else {
+        return url;
+    };
+    if host.starts_with(&format!("{namespace}.")) {
+        return url;
+    }
+    let host = format!("{namespace}.{host}");
+    match parsed.set_host(Some(&host)) {
+        Ok(()) => parsed.into(),
+        Err(_) => url,
+    }
+}
+
+/// Sends the HTTP requests of [Client], for transports not built into the crate,
+/// e.g. a custom HTTP stack or a proxy recording the traffic. Plug it in with [InnerClient::Custom].
+///
+/// Both methods return the response body, which the client deserializes itself.
+/// Non-success statuses should be reported as [Error::Http], so that retries
+/// and the circuit breaker recognize them.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use futures::future::BoxFuture;
+/// use libsql_client::http::{Client, InnerClient, Transport};
+///
+/// #[derive(Debug)]
+/// struct Offline;
+///
+/// impl Transport for Offline {
+///     fn send(
+///         &self,
+///         url: String,
+///         _auth: String,
+///         _headers: Vec<(String, String)>,
+///         _body: String,
+///     ) -> BoxFuture<'_, anyhow::Result<String>> {
+///         Box::pin(async move { anyhow::bail!("offline, not sending to {url}") })
+///     }
+/// }
+///
+/// let db = Client::new(InnerClient::Custom(Arc::new(Offline)), "https://db.example.com", "");
+/// ```
+pub trait Transport: std::fmt::Debug + Send + Sync {
+    /// Sends a POST request with a JSON `body`. `auth` is the value of the `Authorization` header.
+    fn send(
+        &self,
+        url: String,
+        auth: String,
+        headers: Vec<(String, String)>,
+        body: String,
+    ) -> futures::future::BoxFuture<'_, Result<String>>;
+
+    /// Sends a GET request, only used for the stats endpoint.
+    /// Fails by default, which only makes [Client::stats] unavailable.
+    fn get(
+        &self,
+        url: String,
+        _auth: String,
+        _headers: Vec<(String, String)>,
+    ) -> futures::future::BoxFuture<'_, Result<String>> {
+        Box::pin(async move {
+            Err(Error::Misuse(format!("GET {url} is not supported by the transport")).into())
+        })
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -31,24 +257,109 @@ pub enum InnerClient {
     Workers(crate::workers::HttpClient),
     #[cfg(feature = "spin_backend")]
     Spin(crate::spin::HttpClient),
+    #[cfg(all(unix, feature = "unix_backend"))]
+    Unix(crate::unix::HttpClient),
+    #[cfg(feature = "mock")]
+    Mock(crate::mock::MockClient),
+    /// A transport implemented outside of the crate.
+    Custom(Arc<dyn Transport>),
     Default,
 }
 
+// Error of requests sent through InnerClient::Default, e.g. when no HTTP backend
+// feature is enabled, which is recoverable unlike a panic
+fn no_backend() -> anyhow::Error {
+    Error::Misuse("no backend feature enabled".to_string()).into()
+}
+
 impl InnerClient {
     pub async fn send(
         &self,
         url: String,
         auth: String,
+        headers: Vec<(String, String)>,
         body: String,
     ) -> Result<pipeline::ServerMsg> {
+        let response = self.send_raw(url, auth, headers, body).await?;
+        Ok(serde_json::from_str(&response)?)
+    }
+
+    /// Sends the request and returns the response body, without deserializing it.
+    pub async fn send_raw(
+        &self,
+        url: String,
+        auth: String,
+        headers: Vec<(String, String)>,
+        body: String,
+    ) -> Result<String> {
         match self {
             #[cfg(feature = "reqwest_backend")]
-            InnerClient::Reqwest(client) => client.send(url, auth, body).await,
+            InnerClient::Reqwest(client) => client.send(url, auth, headers, body).await,
             #[cfg(feature = "workers_backend")]
-            InnerClient::Workers(client) => client.send(url, auth, body).await,
+            InnerClient::Workers(client) => client.send(url, auth, headers, body).await,
             #[cfg(feature = "spin_backend")]
-            InnerClient::Spin(client) => client.send(url, auth, body).await,
-            _ => panic!("Must enable at least one feature"),
+            InnerClient::Spin(client) => client.send(url, auth, headers, body).await,
+            #[cfg(all(unix, feature = "unix_backend"))]
+            InnerClient::Unix(client) => client.send(url, auth, headers, body).await,
+            #[cfg(feature = "mock")]
+            InnerClient::Mock(client) => client.send(url, auth, headers, body).await,
+            InnerClient::Custom(transport) => transport.send(url, auth, headers, body).await,
+            _ => Err(no_backend()),
+        }
+    }
+
+    // Like send_raw(), but fails with Error::ResponseTooLarge if the response body
+    // exceeds `max_bytes`, without reading all of it where the backend allows
+    pub(crate) async fn send_limited(
+        &self,
+        url: String,
+        auth: String,
+        headers: Vec<(String, String)>,
+        body: String,
+        max_bytes: usize,
+    ) -> Result<String> {
+        let response = match self {
+            #[cfg(feature = "reqwest_backend")]
+            InnerClient::Reqwest(client) => {
+                return client
+                    .send_limited(url, auth, headers, body, max_bytes)
+                    .await
+            }
+            #[cfg(feature = "workers_backend")]
+            InnerClient::Workers(client) => {
+                return client
+                    .send_limited(url, auth, headers, body, max_bytes)
+                    .await
+            }
+            inner => inner.send_raw(url, auth, headers, body).await?,
+        };
+        if response.len() > max_bytes {
+            return Err(Error::ResponseTooLarge { limit: max_bytes }.into());
+        }
+        Ok(response)
+    }
+}
+
+impl InnerClient {
+    pub async fn get(
+        &self,
+        url: String,
+        auth: String,
+        headers: Vec<(String, String)>,
+    ) -> Result<String> {
+        match self {
+            #[cfg(feature = "reqwest_backend")]
+            InnerClient::Reqwest(client) => client.get(url, auth, headers).await,
+            #[cfg(feature = "workers_backend")]
+            InnerClient::Workers(client) => client.get(url, auth, headers).await,
+            #[cfg(feature = "spin_backend")]
+            InnerClient::Spin(client) => client.get(url, auth, headers).await,
+            #[cfg(all(unix, feature = "unix_backend"))]
+            InnerClient::Unix(client) => client.get(url, auth, headers).await,
+            #[cfg(feature = "mock")]
+            InnerClient::Mock(client) => client.get(url, auth, headers).await,
+            InnerClient::Custom(transport) => transport.get(url, auth, headers).await,
+            _ => Err(no_backend()),
         }
     }
 }
@@ -56,29 +367,277 @@ impl InnerClient {
 impl Client {
     /// Creates a database client with JWT authentication.
     ///
+    /// A URL without a scheme gets `http://` if it points to the local machine
+    /// (`localhost` or a loopback address), where sqld usually runs without TLS,
+    /// and `https://` otherwise. See [`Client::with_default_scheme()`] to override it.
+    ///
     /// # Arguments
     /// * `url` - URL of the database endpoint
     /// * `token` - auth token
     pub fn new(inner: InnerClient, url: impl Into<String>, token: impl Into<String>) -> Self {
         let token = token.into();
         let url = url.into();
-        // Auto-update the URL to start with https:// if no protocol was specified
-        let base_url = if !url.contains("://") {
-            format!("https://{}", &url)
+        let (base_url, implicit_scheme) = if !url.contains("://") {
+            let scheme = default_scheme_for(&url);
+            (format!("{scheme}://{url}"), Some(scheme.to_string()))
         } else {
-            url
+            (url, None)
         };
-        let url_for_queries = format!("{base_url}v2/pipeline");
+        let url_for_queries = join_pipeline_url(&base_url, DEFAULT_PIPELINE_PATH);
         Self {
             inner,
             cookies: Arc::new(RwLock::new(HashMap::new())),
+            base_url,
             url_for_queries,
+            implicit_scheme,
+            protocol_version: ProtocolVersion::default(),
+            server_version: Arc::default(),
             auth: format!("Bearer {token}"),
+            token_provider: None,
+            cached_token: Arc::default(),
+            headers: Vec::new(),
+            request_id: None,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            namespace: None,
+            namespace_routing: NamespaceRouting::default(),
+            retry_policy: RetryPolicy::default(),
+            max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+            #[cfg(not(target_arch = "wasm32"))]
+            circuit_breaker: None,
+            sleeper: Arc::new(TokioSleeper),
+            consistency: ConsistencyMode::default(),
+            log_params: false,
+            wire_logging: false,
+            last_frame_no: Arc::default(),
+            warmed_up: Arc::default(),
+            next_tx_id: Arc::new(std::sync::atomic::AtomicU64::new(FIRST_GENERATED_TX_ID)),
+            sql_cache: None,
+            #[cfg(feature = "metrics")]
+            metrics: Arc::default(),
+        }
+    }
+
+    /// Forwards every finished request to the given sink, in addition to
+    /// accumulating it in the counters returned by [`Client::metrics()`].
+    /// Resets the counters.
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics_sink(mut self, sink: Arc<dyn crate::metrics::MetricsSink>) -> Self {
+        self.metrics = Arc::new(crate::metrics::Metrics::with_sink(sink));
+        self
+    }
+
+    /// Returns the counters of requests sent by this client and all its clones.
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> crate::metrics::MetricsSnapshot {
+        self.metrics.snapshot()
+    }
+
+    /// Adds a header sent with every request, e.g. for a proxy in front of the server.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Replaces the [TokioSleeper] which waits between retries and between polls
+    /// of [`Client::wait_for_frame()`], e.g. with a fake clock in tests.
+    pub fn with_sleeper(mut self, sleeper: Arc<dyn Sleeper>) -> Self {
+        self.sleeper = sleeper;
+        self
+    }
+
+    /// Sets the User-Agent sent with every request, which identifies the application
+    /// in the server's logs. Defaults to [DEFAULT_USER_AGENT].
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Targets the given namespace of a multi-tenant sqld, so that one base URL
+    /// can reach many databases. See [`Client::with_namespace_routing()`] for how
+    /// the namespace is passed to the server.
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    /// Sets how the namespace set with [`Client::with_namespace()`] is passed to
+    /// the server. Defaults to [NamespaceRouting::Subdomain].
+    pub fn with_namespace_routing(mut self, routing: NamespaceRouting) -> Self {
+        self.namespace_routing = routing;
+        self
+    }
+
+    /// Replaces the scheme which [`Client::new()`] added to a URL given without one,
+    /// e.g. `"http"` for a plaintext server on another machine of a private network.
+    /// URLs with an explicit scheme are left unchanged.
+    pub fn with_default_scheme(mut self, scheme: impl Into<String>) -> Self {
+        let Some(implicit_scheme) = self.implicit_scheme.take() else {
+            return self;
+        };
+        let scheme = scheme.into();
+        let replace_scheme = |url: &str| match url.strip_prefix(&format!("{implicit_scheme}://")) {
+            Some(rest) => format!("{scheme}://{rest}"),
+            None => url.to_string(),
+        };
+        self.base_url = replace_scheme(&self.base_url);
+        self.url_for_queries = replace_scheme(&self.url_for_queries);
+        self.implicit_scheme = Some(scheme);
+        self
+    }
+
+    /// Sets the policy for retrying requests which the server rejected without processing them.
+    /// By default, requests are not retried.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Sends `request_id` in the [CORRELATION_ID_HEADER] of every request, instead of
+    /// a generated id, e.g. the id of a distributed trace, so that the server's logs
+    /// can be matched with the client's. Servers which don't log the header ignore it.
+    ///
+    /// Errors carry the id, see [crate::error::correlation_id]. To tag a single
+    /// request, set it on a clone, which shares the connection state of this client.
+    pub fn with_request_id(mut self, request_id: impl Into<String>) -> Self {
+        self.request_id = Some(request_id.into());
+        self
+    }
+
+    /// Limits the size of the server's responses, so that an unexpectedly large
+    /// result can't exhaust the memory, e.g. of a serverless function.
+    /// Larger responses fail with [Error::ResponseTooLarge]. Defaults to [DEFAULT_MAX_RESPONSE_BYTES].
+    ///
+    /// The reqwest backend stops reading a response once it exceeds the limit, and the
+    /// workers backend rejects responses whose `Content-Length` exceeds it up front.
+    /// Other backends receive responses in full before they are checked.
+    pub fn with_max_response_bytes(mut self, max_response_bytes: usize) -> Self {
+        self.max_response_bytes = max_response_bytes;
+        self
+    }
+
+    /// Stores SQL texts executed at least `threshold` times on the server, so that
+    /// further statements refer to them by id instead of sending the whole text.
+    /// At most `capacity` texts are stored per stream, and the least recently used
+    /// one is closed to make room for a new one. Off by default.
+    ///
+    /// The server keeps stored texts only as long as the stream, so they are used by
+    /// statements executed in transactions opened with [`Client::begin()`]. Statements
+    /// executed outside of transactions run on a fresh stream each and always send the text.
+    /// Execution counts are shared by all clones of this client, see [`Client::sql_cache_stats()`].
+    pub fn with_sql_cache(mut self, threshold: u32, capacity: usize) -> Self {
+        self.sql_cache = Some(Arc::new(crate::sql_cache::SqlCache::new(
+            threshold, capacity,
+        )));
+        self
+    }
+
+    /// Returns the counters of the cache enabled with [`Client::with_sql_cache()`],
+    /// or `None` if it's disabled.
+    pub fn sql_cache_stats(&self) -> Option<crate::sql_cache::SqlCacheStats> {
+        self.sql_cache.as_ref().map(|sql_cache| sql_cache.stats())
+    }
+
+    /// Enables a circuit breaker, which fails requests with [Error::CircuitOpen]
+    /// without sending them while the server keeps failing. Off by default.
+    /// The breaker's state is shared by all clones of this client.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_circuit_breaker(mut self, policy: crate::circuit::CircuitBreakerPolicy) -> Self {
+        self.circuit_breaker = Some(Arc::new(crate::circuit::CircuitBreaker::new(policy)));
+        self
+    }
+
+    /// Includes values of bound parameters in the tracing spans recorded for
+    /// each request, under the `libsql_client::http` target.
+    /// Off by default, because parameters often carry secrets or personal data.
+    pub fn with_param_logging(mut self, enabled: bool) -> Self {
+        self.log_params = enabled;
+        self
+    }
+
+    /// Pins the Hrana protocol version used to talk to the server.
+    /// The pipeline endpoint is derived from the version, e.g. `v3/pipeline` for [ProtocolVersion::V3].
+    ///
+    /// # Arguments
+    /// * `version` - protocol version
+    pub fn with_protocol_version(mut self, version: ProtocolVersion) -> Self {
+        self.protocol_version = version;
+        self.url_for_queries = join_pipeline_url(&self.base_url, version.pipeline_path());
+        self
+    }
+
+    /// Returns the Hrana protocol version used to talk to the server.
+    pub fn protocol_version(&self) -> ProtocolVersion {
+        self.protocol_version
+    }
+
+    /// Finds the newest protocol version supported by the server and pins it for
+    /// further requests. Fails with [Error::NoSupportedProtocolVersion] if the server
+    /// supports none of the versions known to this client.
+    pub async fn negotiate_protocol_version(&mut self) -> Result<ProtocolVersion> {
+        let version = self.server_version().await?;
+        self.protocol_version = version;
+        self.url_for_queries = join_pipeline_url(&self.base_url, version.pipeline_path());
+        Ok(version)
+    }
+
+    /// Returns the newest protocol version supported by the server, without pinning it.
+    /// The server is probed on the first call only, and the result is shared by all clones
+    /// of this client. Fails with [Error::NoSupportedProtocolVersion] if the server
+    /// supports none of the versions known to this client.
+    pub async fn server_version(&self) -> Result<ProtocolVersion> {
+        if let Some(version) = *self.server_version.read().unwrap() {
+            return Ok(version);
+        }
+        for version in NEGOTIABLE_VERSIONS {
+            let url = self.route(join_pipeline_url(&self.base_url, version.pipeline_path()));
+            // An empty stream is the cheapest request every pipeline endpoint understands
+            let msg = pipeline::ClientMsg {
+                baton: None,
+                requests: vec![pipeline::StreamRequest::Close],
+            };
+            let body = serde_json::to_string(&msg)?;
+            let headers = self.request_headers(&self.next_correlation_id());
+            match self
+                .inner
+                .send(url, self.auth_header().await?, headers, body)
+                .await
+            {
+                Ok(_) => {
+                    *self.server_version.write().unwrap() = Some(version);
+                    return Ok(version);
+                }
+                Err(e) if is_not_found(&e) => {
+                    tracing::debug!("Server does not support {version}");
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(Error::NoSupportedProtocolVersion {
+            tried: NEGOTIABLE_VERSIONS.to_vec(),
         }
+        .into())
+    }
+
+    /// Forgets the cached result of [`Client::server_version()`], so that the
+    /// next call probes the server again, e.g. after the server was upgraded.
+    pub fn reset_server_version(&self) {
+        *self.server_version.write().unwrap() = None;
+    }
+
+    /// Overrides the path of the pipeline endpoint, which defaults to [DEFAULT_PIPELINE_PATH].
+    /// The path is resolved relative to the database URL, so for a database URL
+    /// `https://host/db1/` and path `v2/pipeline` the requests go to `https://host/db1/v2/pipeline`.
+    ///
+    /// # Arguments
+    /// * `pipeline_path` - path of the pipeline endpoint, e.g. `v2/pipeline`
+    pub fn with_pipeline_path(mut self, pipeline_path: impl AsRef<str>) -> Self {
+        self.url_for_queries = join_pipeline_url(&self.base_url, pipeline_path.as_ref());
+        self
     }
 
     /// Establishes  a database client from a `Config` object
     pub fn from_config(inner: InnerClient, config: Config) -> anyhow::Result<Self> {
+        config.check_auth_token()?;
         Ok(Self::new(
             inner,
             config.url,
@@ -87,31 +646,464 @@ impl Client {
     }
 
     pub fn from_env(inner: InnerClient) -> anyhow::Result<Client> {
-        let url = std::env::var("LIBSQL_CLIENT_URL").map_err(|_| {
-            anyhow::anyhow!("LIBSQL_CLIENT_URL variable should point to your sqld database")
-        })?;
-
-        let token = std::env::var("LIBSQL_CLIENT_TOKEN").unwrap_or_default();
+        // Not parsed with Config::from_env(), which rejects URLs without a scheme
+        let url = crate::client::url_from_env()?;
+        let token = std::env::var(crate::client::TOKEN_ENV_VAR).unwrap_or_default();
         Ok(Client::new(inner, url, token))
     }
 }
 
+// Runs a request, logging its outcome and returning its duration where a clock is available.
+async fn timed<T>(
+    request: impl std::future::Future<Output = Result<T>>,
+) -> (Result<T>, Option<std::time::Duration>) {
+    // std::time::Instant panics on wasm32-unknown-unknown
+    #[cfg(not(target_arch = "wasm32"))]
+    let start = std::time::Instant::now();
+    let result = request.await;
+    #[cfg(not(target_arch = "wasm32"))]
+    let elapsed = Some(start.elapsed());
+    #[cfg(target_arch = "wasm32")]
+    let elapsed: Option<std::time::Duration> = None;
+    tracing::debug!(
+        target: "libsql_client::http",
+        elapsed_ms = elapsed.map(|e| e.as_millis() as u64),
+        ok = result.is_ok(),
+        "request finished"
+    );
+    (result, elapsed)
+}
+
+// Generates an id which is unique within the process and unlikely to collide
+// with ids generated by other processes.
+fn new_correlation_id() -> String {
+    use std::hash::{BuildHasher, Hasher};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::OnceLock;
+
+    static PROCESS_ID: OnceLock<u64> = OnceLock::new();
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let process_id = PROCESS_ID.get_or_init(|| {
+        std::collections::hash_map::RandomState::new()
+            .build_hasher()
+            .finish()
+    });
+    format!(
+        "{process_id:016x}-{:x}",
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+// Picks the first response accepted by `extract` out of the pipeline results.
+// Responses are matched by their type rather than by their position, and each
+// pipeline sends at most one request of a given type besides `close`, so the
+// pairing holds even if the server reorders the responses.
+// Other successful responses are ignored, so that informational responses
+// appended by newer servers don't break the client, but any error response fails.
+fn find_response<T>(
+    results: Vec<pipeline::Response>,
+    extract: impl Fn(pipeline::StreamResponse) -> Option<T>,
+) -> Result<T> {
+    let mut found = None;
+    for result in results {
+        match result {
+            pipeline::Response::Ok(pipeline::StreamResponseOk { response }) => {
+                if found.is_none() {
+                    found = extract(response);
+                }
+            }
+            pipeline::Response::Error(e) => {
+                anyhow::bail!("Error from server: {:?}", e)
+            }
+        }
+    }
+    found.ok_or_else(|| anyhow::anyhow!("Expected response missing from server"))
+}
+
+// Interval between polls of Client::wait_for_frame()
+#[cfg(not(target_arch = "wasm32"))]
+const FRAME_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
+
+// Finds the highest `replication_index` of statement results in a pipeline response.
+// The index is a number encoded as a string, but plain numbers are accepted too.
+fn max_replication_index(response: &str) -> Option<u64> {
+    // Cheap check, to skip parsing responses of servers which never report it
+    if !response.contains("replication_index") {
+        return None;
+    }
+    fn parse(value: &serde_json::Value) -> Option<u64> {
+        match value {
+            serde_json::Value::String(s) => s.parse().ok(),
+            value => value.as_u64(),
+        }
+    }
+    fn visit(value: &serde_json::Value, max: &mut Option<u64>) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, value) in map {
+                    if key == "replication_index" {
+                        if let Some(index) = parse(value) {
+                            *max = Some(max.map_or(index, |m| m.max(index)));
+                        }
+                    } else {
+                        visit(value, max);
+                    }
+                }
+            }
+            serde_json::Value::Array(values) => values.iter().for_each(|v| visit(v, max)),
+            _ => {}
+        }
+    }
+    let response: serde_json::Value = serde_json::from_str(response).ok()?;
+    let mut max = None;
+    visit(&response, &mut max);
+    max
+}
+
+// Parses a response of the pipeline endpoint. Responses which don't decode, e.g. because
+// of invalid base64 in a blob, are reported as Error::MalformedResponse.
+fn parse_response<T: serde::de::DeserializeOwned>(response: &str) -> Result<T> {
+    serde_json::from_str(response).map_err(|e| {
+        Error::MalformedResponse {
+            message: e.to_string(),
+        }
+        .into()
+    })
+}
+
+fn is_unauthorized(e: &anyhow::Error) -> bool {
+    matches!(e.downcast_ref::<Error>(), Some(Error::Unauthorized { .. }))
+}
+
+fn is_not_found(e: &anyhow::Error) -> bool {
+    matches!(
+        e.downcast_ref::<Error>(),
+        Some(Error::Http { status: 404, .. })
+    )
+}
+
 impl Client {
-    fn into_hrana(stmt: Statement) -> crate::proto::Stmt {
-        let mut hrana_stmt = crate::proto::Stmt::new(stmt.sql, true);
-        for param in stmt.args {
+    /// Logs the JSON of every pipeline request and response, along with the URL and
+    /// headers, under the `libsql_client::wire` target at debug level.
+    /// The Authorization header is never logged. Off by default, since the JSON
+    /// includes bound parameters, which often carry secrets or personal data.
+    pub fn with_wire_logging(mut self, enabled: bool) -> Self {
+        self.wire_logging = enabled;
+        self
+    }
+
+    /// Sets the consistency of requests. In [ConsistencyMode::Strong], each request
+    /// carries the highest replication index seen by this client and its clones in the
+    /// [REPLICATION_INDEX_HEADER], so that a replica waits until it applied the
+    /// corresponding writes before executing it. Defaults to [ConsistencyMode::Eventual].
+    pub fn with_consistency(mut self, consistency: ConsistencyMode) -> Self {
+        self.consistency = consistency;
+        self
+    }
+
+    /// Executes a statement like [`Client::execute()`], overriding the consistency
+    /// set with [`Client::with_consistency()`] for this statement only.
+    pub async fn execute_with_consistency(
+        &self,
+        stmt: impl Into<Statement> + Send,
+        consistency: ConsistencyMode,
+    ) -> Result<ResultSet> {
+        if consistency == self.consistency {
+            return self.execute(stmt).await;
+        }
+        self.clone()
+            .with_consistency(consistency)
+            .execute(stmt)
+            .await
+    }
+
+    /// Returns the highest replication frame number reported by the server
+    /// in responses to this client and its clones, or `None` if the server
+    /// didn't report any. Useful for read-your-writes with embedded replicas.
+    pub fn last_frame_no(&self) -> Option<u64> {
+        *self.last_frame_no.read().unwrap()
+    }
+
+    /// Polls the server until it reports a replication frame number of at least `frame_no`,
+    /// i.e. until it caught up with a write. Fails if the server doesn't report
+    /// frame numbers at all, or if `timeout` passes first.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn wait_for_frame(&self, frame_no: u64, timeout: std::time::Duration) -> Result<()> {
+        let deadline = std::time::Instant::now() + timeout;
+        if self.last_frame_no().is_some_and(|last| last >= frame_no) {
+            return Ok(());
+        }
+        loop {
+            self.execute_inner("SELECT 1", 0).await?;
+            match self.last_frame_no() {
+                None => anyhow::bail!("The server does not report replication frame numbers"),
+                Some(last) if last >= frame_no => return Ok(()),
+                Some(_) => {}
+            }
+            // The time spent on requests counts too, so the deadline is checked after each one
+            let now = std::time::Instant::now();
+            if now >= deadline {
+                anyhow::bail!("Timed out waiting for the server to reach frame {frame_no}")
+            }
+            self.sleeper
+                .sleep(FRAME_POLL_INTERVAL.min(deadline - now))
+                .await;
+        }
+    }
+
+    // Remembers the highest replication index found in a raw pipeline response.
+    // Servers speaking Hrana 3 report it in each statement result, which the
+    // protocol types of this crate don't have a field for.
+    fn record_frame_no(&self, response: &str) {
+        let Some(frame_no) = max_replication_index(response) else {
+            return;
+        };
+        let mut last_frame_no = self.last_frame_no.write().unwrap();
+        if last_frame_no.map_or(true, |last| frame_no > last) {
+            *last_frame_no = Some(frame_no);
+        }
+    }
+
+    /// Fetches auth tokens from `provider` instead of using a fixed token, e.g. to use
+    /// JWTs which expire. The provider is called before the first request and after
+    /// the server rejects the current token with [Error::Unauthorized], in which
+    /// case the request is sent once more with the new token.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # async fn fetch_jwt() -> anyhow::Result<String> { Ok(String::new()) }
+    /// use libsql_client::http::{Client, InnerClient};
+    /// use libsql_client::reqwest::HttpClient;
+    ///
+    /// let inner = InnerClient::Reqwest(HttpClient::new());
+    /// let db = Client::new(inner, "https://example.com", "").with_token_provider(fetch_jwt);
+    /// ```
+    pub fn with_token_provider<F, Fut>(self, provider: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<String>> + Send + 'static,
+    {
+        self.with_token_provider_boxed(TokenProvider::new(provider))
+    }
+
+    pub(crate) fn with_token_provider_boxed(mut self, provider: TokenProvider) -> Self {
+        self.token_provider = Some(provider);
+        self.cached_token = Arc::default();
+        self
+    }
+
+    // Returns the Authorization header, calling the token provider if there's no cached token
+    async fn auth_header(&self) -> Result<String> {
+        let Some(provider) = &self.token_provider else {
+            return Ok(self.auth.clone());
+        };
+        if let Some(auth) = self.cached_token.read().unwrap().as_ref() {
+            return Ok(auth.clone());
+        }
+        let auth = format!("Bearer {}", (provider.0)().await?);
+        *self.cached_token.write().unwrap() = Some(auth.clone());
+        Ok(auth)
+    }
+
+    // Id of the next request, sent in the CORRELATION_ID_HEADER
+    fn next_correlation_id(&self) -> String {
+        self.request_id.clone().unwrap_or_else(new_correlation_id)
+    }
+
+    fn request_headers(&self, correlation_id: &str) -> Vec<(String, String)> {
+        let mut headers = self.headers.clone();
+        headers.push(("User-Agent".to_string(), self.user_agent.clone()));
+        if let (Some(namespace), NamespaceRouting::Header) =
+            (&self.namespace, self.namespace_routing)
+        {
+            headers.push((NAMESPACE_HEADER.to_string(), namespace.clone()));
+        }
+        headers.push((
+            CORRELATION_ID_HEADER.to_string(),
+            correlation_id.to_string(),
+        ));
+        if self.consistency == ConsistencyMode::Strong {
+            if let Some(frame_no) = self.last_frame_no() {
+                headers.push((REPLICATION_INDEX_HEADER.to_string(), frame_no.to_string()));
+            }
+        }
+        headers
+    }
+
+    // Points the URL at the namespace's database, if it's selected by subdomain
+    fn route(&self, url: String) -> String {
+        match (&self.namespace, self.namespace_routing) {
+            (Some(namespace), NamespaceRouting::Subdomain) => with_namespace_host(url, namespace),
+            _ => url,
+        }
+    }
+
+    // Sends the request to the backend, retrying according to the retry policy.
+    // The pipeline endpoint of an unsupported protocol version does not exist,
+    // so a 404 is reported as such.
+    async fn send<T: serde::de::DeserializeOwned>(
+        &self,
+        url: String,
+        body: String,
+        correlation_id: &str,
+    ) -> Result<T> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(circuit_breaker) = &self.circuit_breaker {
+            circuit_breaker.check()?;
+        }
+        let url = self.route(url);
+        let mut retry = 0;
+        let mut refreshed_token = false;
+        let result = loop {
+            let auth = self.auth_header().await?;
+            let headers = self.request_headers(correlation_id);
+            if self.wire_logging {
+                tracing::debug!(
+                    target: "libsql_client::wire",
+                    "Sending to {url} with headers {headers:?} (Authorization redacted): {body}"
+                );
+            }
+            let result = self
+                .inner
+                .send_limited(
+                    url.clone(),
+                    auth,
+                    headers,
+                    body.clone(),
+                    self.max_response_bytes,
+                )
+                .await;
+            if self.wire_logging {
+                match &result {
+                    Ok(response) => tracing::debug!(
+                        target: "libsql_client::wire",
+                        "Received from {url}: {response}"
+                    ),
+                    Err(e) => tracing::debug!(
+                        target: "libsql_client::wire",
+                        "Request to {url} failed: {e}"
+                    ),
+                }
+            }
+            if let Ok(response) = &result {
+                self.record_frame_no(response);
+            }
+            let result = result.and_then(|response| parse_response(&response));
+            match result {
+                Err(e)
+                    if self.token_provider.is_some() && !refreshed_token && is_unauthorized(&e) =>
+                {
+                    tracing::debug!(
+                        target: "libsql_client::http",
+                        "Refreshing the auth token after: {e}"
+                    );
+                    refreshed_token = true;
+                    *self.cached_token.write().unwrap() = None;
+                }
+                Err(e)
+                    if retry < self.retry_policy.max_retries
+                        && self.retry_policy.is_retryable(&e) =>
+                {
+                    retry += 1;
+                    let backoff = self.retry_policy.backoff(retry);
+                    tracing::debug!(
+                        target: "libsql_client::http",
+                        "Retrying request in {backoff:?} after error: {e}"
+                    );
+                    self.sleeper.sleep(backoff).await;
+                }
+                result => break result,
+            }
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(circuit_breaker) = &self.circuit_breaker {
+            circuit_breaker.record(result.as_ref().err());
+        }
+        result
+    }
+
+    // Borrowed statements are copied, since the request owns its SQL and parameters
+    fn into_hrana(stmt: Cow<'_, Statement>) -> Result<crate::proto::Stmt> {
+        stmt.check_not_empty()?;
+        let Statement { sql, args } = stmt.into_owned();
+        let mut hrana_stmt = crate::proto::Stmt::new(sql, true);
+        for param in args {
             hrana_stmt.bind(param);
         }
-        hrana_stmt
+        Ok(hrana_stmt)
+    }
+
+    // Fills in the span with the SQL and parameters of the statements,
+    // skipping the formatting if nobody listens.
+    fn record_statements<'a>(
+        &self,
+        span: &tracing::Span,
+        stmts: impl Iterator<Item = &'a Statement> + Clone,
+    ) {
+        if span.is_disabled() {
+            return;
+        }
+        let sql: Vec<&str> = stmts.clone().map(|s| s.sql.as_str()).collect();
+        span.record("sql", sql.join("; ").as_str());
+        span.record(
+            "param_count",
+            stmts.clone().map(|s| s.args.len()).sum::<usize>(),
+        );
+        if self.log_params {
+            let params: Vec<&Value> = stmts.flat_map(|s| s.args.iter()).collect();
+            span.record("params", tracing::field::debug(&params));
+        }
     }
 
     pub async fn raw_batch(
         &self,
         stmts: impl IntoIterator<Item = impl Into<Statement>>,
     ) -> anyhow::Result<BatchResult> {
+        let mut batch = BatchBuilder::new();
+        for stmt in stmts {
+            batch = batch.step(stmt);
+        }
+        self.execute_batch(batch).await
+    }
+
+    /// Executes a batch whose steps may be conditioned on the outcomes of earlier steps.
+    pub async fn execute_batch(&self, batch: BatchBuilder) -> anyhow::Result<BatchResult> {
+        let stmts = batch.steps;
+        let correlation_id = self.next_correlation_id();
+        let span = tracing::debug_span!(
+            target: "libsql_client::http",
+            "raw_batch",
+            steps = stmts.len(),
+            correlation_id = correlation_id.as_str(),
+            sql = tracing::field::Empty,
+            param_count = tracing::field::Empty,
+            params = tracing::field::Empty,
+        );
+        self.record_statements(&span, stmts.iter().map(|(stmt, _)| stmt));
+        let (result, _elapsed) = timed(self.raw_batch_inner(stmts, &correlation_id))
+            .instrument(span)
+            .await;
+        #[cfg(feature = "metrics")]
+        self.metrics.record(crate::metrics::RequestEvent {
+            batch: true,
+            ok: result.is_ok(),
+            elapsed: _elapsed,
+        });
+        result.map_err(|e| crate::error::with_correlation_id(e, correlation_id))
+    }
+
+    async fn raw_batch_inner(
+        &self,
+        stmts: Vec<(Statement, Option<Condition>)>,
+        correlation_id: &str,
+    ) -> Result<BatchResult> {
         let mut batch = crate::proto::Batch::new();
-        for stmt in stmts.into_iter() {
-            batch.step(None, Self::into_hrana(stmt.into()));
+        for (stmt, cond) in stmts {
+            batch.step(
+                cond.map(|c| c.to_proto()),
+                Self::into_hrana(Cow::Owned(stmt))?,
+            );
         }
 
         let msg = pipeline::ClientMsg {
@@ -122,35 +1114,14 @@ impl Client {
             ],
         };
         let body = serde_json::to_string(&msg)?;
-        let mut response: pipeline::ServerMsg = self
-            .inner
-            .send(self.url_for_queries.clone(), self.auth.clone(), body)
+        let response: pipeline::ServerMsg = self
+            .send(self.url_for_queries.clone(), body, correlation_id)
             .await?;
 
-        if response.results.is_empty() {
-            anyhow::bail!(
-                "Unexpected empty response from server: {:?}",
-                response.results
-            );
-        }
-        if response.results.len() > 2 {
-            // One with actual results, one closing the stream
-            anyhow::bail!(
-                "Unexpected multiple responses from server: {:?}",
-                response.results
-            );
-        }
-        match response.results.swap_remove(0) {
-            pipeline::Response::Ok(pipeline::StreamResponseOk {
-                response: pipeline::StreamResponse::Batch(batch_result),
-            }) => Ok(batch_result.result),
-            pipeline::Response::Ok(_) => {
-                anyhow::bail!("Unexpected response from server: {:?}", response.results)
-            }
-            pipeline::Response::Error(e) => {
-                anyhow::bail!("Error from server: {:?}", e)
-            }
-        }
+        find_response(response.results, |response| match response {
+            pipeline::StreamResponse::Batch(batch_result) => Some(batch_result.result),
+            _ => None,
+        })
     }
 
     async fn execute_inner(
@@ -158,7 +1129,40 @@ impl Client {
         stmt: impl Into<Statement> + Send,
         tx_id: u64,
     ) -> Result<ResultSet> {
-        let stmt = Self::into_hrana(stmt.into());
+        self.execute_statement(Cow::Owned(stmt.into()), tx_id).await
+    }
+
+    async fn execute_statement(&self, stmt: Cow<'_, Statement>, tx_id: u64) -> Result<ResultSet> {
+        let correlation_id = self.next_correlation_id();
+        let span = tracing::debug_span!(
+            target: "libsql_client::http",
+            "execute",
+            tx_id,
+            correlation_id = correlation_id.as_str(),
+            sql = tracing::field::Empty,
+            param_count = tracing::field::Empty,
+            params = tracing::field::Empty,
+        );
+        self.record_statements(&span, std::iter::once(stmt.as_ref()));
+        let (result, _elapsed) = timed(self.execute_stmt(stmt, tx_id, &correlation_id))
+            .instrument(span)
+            .await;
+        #[cfg(feature = "metrics")]
+        self.metrics.record(crate::metrics::RequestEvent {
+            batch: false,
+            ok: result.is_ok(),
+            elapsed: _elapsed,
+        });
+        result.map_err(|e| crate::error::with_correlation_id(e, correlation_id))
+    }
+
+    async fn execute_stmt(
+        &self,
+        stmt: Cow<'_, Statement>,
+        tx_id: u64,
+        correlation_id: &str,
+    ) -> Result<ResultSet> {
+        let stmt = Self::into_hrana(stmt)?;
 
         let cookie = if tx_id > 0 {
             self.cookies
@@ -170,6 +1174,11 @@ impl Client {
         } else {
             Cookie::default()
         };
+        if let (true, Some(sql_cache)) = (tx_id > 0, &self.sql_cache) {
+            return self
+                .execute_with_sql_cache(stmt, tx_id, cookie, sql_cache, correlation_id)
+                .await;
+        }
         let requests = if tx_id != 0 {
             vec![pipeline::StreamRequest::Execute(
                 pipeline::StreamExecuteReq { stmt },
@@ -188,8 +1197,7 @@ impl Client {
         let url = cookie
             .base_url
             .unwrap_or_else(|| self.url_for_queries.clone());
-        let mut response: pipeline::ServerMsg =
-            self.inner.send(url, self.auth.clone(), body).await?;
+        let response: pipeline::ServerMsg = self.send(url, body, correlation_id).await?;
 
         if tx_id > 0 {
             let base_url = response.base_url;
@@ -200,6 +1208,7 @@ impl Client {
                         Cookie {
                             baton: Some(baton),
                             base_url,
+                            stored_sql: cookie.stored_sql,
                         },
                     );
                 }
@@ -207,30 +1216,91 @@ impl Client {
             }
         }
 
-        if response.results.is_empty() {
-            anyhow::bail!(
-                "Unexpected empty response from server: {:?}",
-                response.results
-            );
-        }
-        if response.results.len() > 2 {
-            // One with actual results, one closing the stream
-            anyhow::bail!(
-                "Unexpected multiple responses from server: {:?}",
-                response.results
-            );
-        }
-        match response.results.swap_remove(0) {
-            pipeline::Response::Ok(pipeline::StreamResponseOk {
-                response: pipeline::StreamResponse::Execute(execute_result),
-            }) => Ok(ResultSet::from(execute_result.result)),
-            pipeline::Response::Ok(_) => {
-                anyhow::bail!("Unexpected response from server: {:?}", response.results)
+        find_response(response.results, |response| match response {
+            pipeline::StreamResponse::Execute(execute_result) => {
+                Some(ResultSet::from(execute_result.result))
             }
-            pipeline::Response::Error(e) => {
-                anyhow::bail!("Error from server: {:?}", e)
+            _ => None,
+        })
+    }
+
+    // Executes a statement of a transaction, referring to its SQL text by id
+    // once the text is stored on the stream, see with_sql_cache()
+    async fn execute_with_sql_cache(
+        &self,
+        stmt: crate::proto::Stmt,
+        tx_id: u64,
+        mut cookie: Cookie,
+        sql_cache: &crate::sql_cache::SqlCache,
+        correlation_id: &str,
+    ) -> Result<ResultSet> {
+        use crate::proto::{StoredSqlServerMsg, StoredSqlStreamResponse, StoredSqlStreamResult};
+        use crate::sql_cache::SqlRef;
+
+        let sql_ref = sql_cache.plan(&stmt.sql, &mut cookie.stored_sql);
+        // The protocol crate has neither store_sql and close_sql requests nor statements
+        // referring to stored SQL texts, so the message is built by hand
+        let mut requests = Vec::new();
+        let sql_id = match sql_ref {
+            SqlRef::Text => None,
+            SqlRef::Stored(sql_id) => Some(sql_id),
+            SqlRef::Store { sql_id, evict } => {
+                if let Some(evict) = evict {
+                    requests.push(serde_json::json!({"type": "close_sql", "sql_id": evict}));
+                }
+                requests.push(
+                    serde_json::json!({"type": "store_sql", "sql_id": sql_id, "sql": stmt.sql}),
+                );
+                Some(sql_id)
             }
+        };
+        let mut stmt = serde_json::to_value(stmt)?;
+        if let (Some(sql_id), Some(fields)) = (sql_id, stmt.as_object_mut()) {
+            fields.remove("sql");
+            fields.insert("sql_id".to_string(), sql_id.into());
+        }
+        requests.push(serde_json::json!({"type": "execute", "stmt": stmt}));
+        let body = serde_json::json!({"baton": cookie.baton, "requests": requests}).to_string();
+        let url = cookie
+            .base_url
+            .clone()
+            .unwrap_or_else(|| self.url_for_queries.clone());
+        let response: StoredSqlServerMsg = self.send(url, body, correlation_id).await?;
+
+        let mut result = None;
+        let mut error = None;
+        for stream_result in response.results {
+            match stream_result {
+                StoredSqlStreamResult::Ok {
+                    response: StoredSqlStreamResponse::Execute { result: rows },
+                } => result = Some(ResultSet::from(rows)),
+                StoredSqlStreamResult::Ok { .. } => {}
+                StoredSqlStreamResult::Error { error: e } => {
+                    error.get_or_insert(e);
+                }
+            }
+        }
+        if error.is_some() {
+            // It's unknown which of the texts were stored and closed
+            cookie.stored_sql.forget();
+        } else {
+            sql_cache.record(sql_ref);
         }
+        let Some(baton) = response.baton else {
+            anyhow::bail!("Stream closed: server returned empty baton")
+        };
+        self.cookies.write().unwrap().insert(
+            tx_id,
+            Cookie {
+                baton: Some(baton),
+                base_url: response.base_url,
+                stored_sql: cookie.stored_sql,
+            },
+        );
+        if let Some(e) = error {
+            anyhow::bail!("Error from server: {:?}", e)
+        }
+        result.ok_or_else(|| anyhow::anyhow!("Expected response missing from server"))
     }
 
     async fn close_stream_for(&self, tx_id: u64) -> Result<()> {
@@ -249,30 +1319,728 @@ impl Client {
             .base_url
             .unwrap_or_else(|| self.url_for_queries.clone());
         let body = serde_json::to_string(&msg)?;
-        self.inner.send(url, self.auth.clone(), body).await.ok();
+        self.send::<pipeline::ServerMsg>(url, body, &self.next_correlation_id())
+            .await
+            .ok();
         self.cookies.write().unwrap().remove(&tx_id);
         Ok(())
     }
 
+    /// Executes a statement on a fresh stream, outside of any transaction,
+    /// even if transactions are currently open on this client. Use
+    /// [`Client::execute_in()`] to run a statement inside one.
+    /// Debug builds log a warning when this happens, since it's usually a mistake.
+    ///
     /// # Arguments
     /// * `stmt` - the SQL statement
     pub async fn execute(&self, stmt: impl Into<Statement> + Send) -> Result<ResultSet> {
+        self.warn_about_open_transactions();
         self.execute_inner(stmt, 0).await
     }
 
+    /// Executes a statement like [`Client::execute()`], without consuming it,
+    /// so that a statement built once can be executed many times.
+    pub async fn execute_ref(&self, stmt: &Statement) -> Result<ResultSet> {
+        self.warn_about_open_transactions();
+        self.execute_statement(Cow::Borrowed(stmt), 0).await
+    }
+
+    fn warn_about_open_transactions(&self) {
+        #[cfg(debug_assertions)]
+        {
+            let open_transactions = self.cookies.read().unwrap().len();
+            if open_transactions > 0 {
+                tracing::warn!(
+                    "Executing a statement outside of a transaction while {open_transactions} transaction(s) are open"
+                );
+            }
+        }
+    }
+
+    /// Closes the streams of all open transactions, shared by all clones of this client,
+    /// so that a graceful shutdown doesn't leave them dangling on the server.
+    /// The transactions are not committed, so the server rolls them back.
+    ///
+    /// Failures to close a stream are ignored, since the server eventually
+    /// expires it anyway. Calling it again, or with no open transactions, is a no-op.
+    pub async fn shutdown(&self) -> Result<()> {
+        let tx_ids: Vec<u64> = self.cookies.read().unwrap().keys().copied().collect();
+        for tx_id in tx_ids {
+            self.close_stream_for(tx_id).await.ok();
+        }
+        Ok(())
+    }
+
+    /// Sends a `SELECT 1` to establish the connection to the server, so that
+    /// the first real request doesn't pay for the TLS handshake. Also fetches
+    /// the token from the token provider, if any.
+    ///
+    /// Only the first successful call contacts the server, later ones return immediately.
+    pub async fn warmup(&self) -> Result<()> {
+        use std::sync::atomic::Ordering;
+        if self.warmed_up.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        self.execute_inner(Statement::new("SELECT 1"), 0).await?;
+        self.warmed_up.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Checks that the server is reachable and accepts our credentials,
+    /// by opening and immediately closing a stream.
+    ///
+    /// Authentication failures are reported as [Error::Unauthorized],
+    /// while network failures are reported by the underlying HTTP backend.
+    pub async fn ping(&self) -> Result<()> {
+        let msg = pipeline::ClientMsg {
+            baton: None,
+            requests: vec![pipeline::StreamRequest::Close],
+        };
+        let body = serde_json::to_string(&msg)?;
+        let correlation_id = self.next_correlation_id();
+        self.send::<pipeline::ServerMsg>(self.url_for_queries.clone(), body, &correlation_id)
+            .await
+            .map_err(|e| crate::error::with_correlation_id(e, correlation_id))?;
+        Ok(())
+    }
+
+    /// Returns the parameters and result columns of a statement, without executing it,
+    /// e.g. to validate queries against the live schema.
+    pub async fn describe(&self, sql: impl Into<String>) -> Result<crate::proto::StmtDescription> {
+        use crate::proto::{DescribeServerMsg, DescribeStreamResponse, DescribeStreamResult};
+
+        // The protocol crate has no describe request, so the message is built by hand
+        let body = serde_json::json!({
+            "baton": null,
+            "requests": [{"type": "describe", "sql": sql.into()}, {"type": "close"}],
+        })
+        .to_string();
+        let correlation_id = self.next_correlation_id();
+        let response: DescribeServerMsg = self
+            .send(self.url_for_queries.clone(), body, &correlation_id)
+            .await
+            .map_err(|e| crate::error::with_correlation_id(e, correlation_id))?;
+        for result in response.results {
+            match result {
+                DescribeStreamResult::Ok {
+                    response: DescribeStreamResponse::Describe { result },
+                } => return Ok(result),
+                DescribeStreamResult::Ok { .. } => {}
+                DescribeStreamResult::Error { error } => anyhow::bail!(error.message),
+            }
+        }
+        anyhow::bail!("The server did not describe the statement")
+    }
+
+    /// Fetches the usage statistics of the database from the [STATS_PATH] endpoint.
+    /// Fails with a descriptive error if the server doesn't expose it.
+    pub async fn stats(&self) -> Result<crate::diagnostics::DbStats> {
+        let url = self.route(join_pipeline_url(&self.base_url, STATS_PATH));
+        let correlation_id = self.next_correlation_id();
+        let headers = self.request_headers(&correlation_id);
+        let auth = self.auth_header().await?;
+        let body = match self.inner.get(url.clone(), auth, headers).await {
+            Ok(body) => body,
+            Err(e) if is_not_found(&e) => {
+                anyhow::bail!("The server does not expose usage stats at {url}")
+            }
+            Err(e) => return Err(crate::error::with_correlation_id(e, correlation_id)),
+        };
+        serde_json::from_str(&body)
+            .map_err(|e| anyhow::anyhow!("Unexpected usage stats from {url}: {e}"))
+    }
+
+    /// Opens a transaction on a stream of its own and returns its id.
+    /// Ids are generated from a counter shared by all clones of this client,
+    /// so clones used from different tasks never share a stream by accident.
+    pub async fn begin(&self) -> Result<TxId> {
+        use std::sync::atomic::Ordering;
+        let tx_id = self.next_tx_id.fetch_add(1, Ordering::Relaxed);
+        self.execute_inner("BEGIN", tx_id).await?;
+        Ok(TxId(tx_id))
+    }
+
+    /// Executes a statement in a transaction opened with [`Client::begin()`].
+    pub async fn execute_in(
+        &self,
+        tx: TxId,
+        stmt: impl Into<Statement> + Send,
+    ) -> Result<ResultSet> {
+        self.execute_inner(stmt, tx.0).await
+    }
+
+    /// Commits a transaction opened with [`Client::begin()`] and closes its stream.
+    pub async fn commit(&self, tx: TxId) -> Result<()> {
+        self.commit_tx(tx.0).await
+    }
+
+    /// Rolls back a transaction opened with [`Client::begin()`] and closes its stream.
+    pub async fn rollback(&self, tx: TxId) -> Result<()> {
+        self.rollback_tx(tx.0).await
+    }
+
+    #[deprecated(
+        since = "0.35.0",
+        note = "ids picked by the caller may collide across clones, use `begin()` and `execute_in()`"
+    )]
     pub async fn execute_in_transaction(&self, tx_id: u64, stmt: Statement) -> Result<ResultSet> {
-        self.execute_inner(stmt, tx_id).await
+        self.execute_in_tx(tx_id, stmt).await
     }
 
+    #[deprecated(since = "0.35.0", note = "use `begin()` and `commit()`")]
     pub async fn commit_transaction(&self, tx_id: u64) -> Result<()> {
+        self.commit_tx(tx_id).await
+    }
+
+    #[deprecated(since = "0.35.0", note = "use `begin()` and `rollback()`")]
+    pub async fn rollback_transaction(&self, tx_id: u64) -> Result<()> {
+        self.rollback_tx(tx_id).await
+    }
+
+    pub(crate) async fn execute_in_tx(&self, tx_id: u64, stmt: Statement) -> Result<ResultSet> {
+        self.execute_inner(stmt, tx_id).await
+    }
+
+    pub(crate) async fn commit_tx(&self, tx_id: u64) -> Result<()> {
         self.execute_inner("COMMIT", tx_id).await.map(|_| ())?;
         self.close_stream_for(tx_id).await.ok();
         Ok(())
     }
 
-    pub async fn rollback_transaction(&self, tx_id: u64) -> Result<()> {
+    pub(crate) async fn rollback_tx(&self, tx_id: u64) -> Result<()> {
         self.execute_inner("ROLLBACK", tx_id).await.map(|_| ())?;
         self.close_stream_for(tx_id).await.ok();
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_response_ignores_extra_responses() {
+        let ok = |response| pipeline::Response::Ok(pipeline::StreamResponseOk { response });
+        let results = vec![
+            ok(pipeline::StreamResponse::Close),
+            ok(pipeline::StreamResponse::Batch(
+                pipeline::StreamBatchResult {
+                    result: BatchResult {
+                        step_results: vec![],
+                        step_errors: vec![],
+                    },
+                },
+            )),
+            ok(pipeline::StreamResponse::Close),
+            ok(pipeline::StreamResponse::Close),
+        ];
+        let extract = |response| match response {
+            pipeline::StreamResponse::Batch(batch_result) => Some(batch_result.result),
+            _ => None,
+        };
+        assert!(find_response(results, extract).is_ok());
+        assert!(find_response(vec![ok(pipeline::StreamResponse::Close)], extract).is_err());
+    }
+
+    #[test]
+    fn test_find_response_in_reordered_results() {
+        let response = r#"{"baton":null,"base_url":null,"results":[
+            {"type":"ok","response":{"type":"close"}},
+            {"type":"ok","response":{"type":"batch","result":{"step_results":[
+                {"cols":[],"rows":[],"affected_row_count":3,"last_insert_rowid":null}
+            ],"step_errors":[null]}}}
+        ]}"#;
+        let msg: pipeline::ServerMsg = parse_response(response).unwrap();
+        let batch_result = find_response(msg.results, |response| match response {
+            pipeline::StreamResponse::Batch(batch_result) => Some(batch_result.result),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!(
+            batch_result.step_results[0]
+                .as_ref()
+                .unwrap()
+                .affected_row_count,
+            3
+        );
+
+        let response = r#"{"baton":null,"base_url":null,"results":[
+            {"type":"ok","response":{"type":"close"}},
+            {"type":"ok","response":{"type":"execute","result":{
+                "cols":[{"name":"x"}],"rows":[[{"type":"integer","value":"7"}]],
+                "affected_row_count":0,"last_insert_rowid":null}}}
+        ]}"#;
+        let msg: pipeline::ServerMsg = parse_response(response).unwrap();
+        let rs = find_response(msg.results, |response| match response {
+            pipeline::StreamResponse::Execute(execute_result) => {
+                Some(ResultSet::from(execute_result.result))
+            }
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!(rs.rows[0].try_get::<i64>(0).unwrap(), 7);
+    }
+
+    #[test]
+    fn test_max_replication_index() {
+        let response = r#"{"baton":null,"base_url":null,"results":[
+            {"type":"ok","response":{"type":"execute","result":{"replication_index":"17"}}},
+            {"type":"ok","response":{"type":"batch","result":{"step_results":[
+                {"replication_index":"42"},null,{"replication_index":5}
+            ]}}}
+        ]}"#;
+        assert_eq!(max_replication_index(response), Some(42));
+        assert_eq!(max_replication_index(r#"{"results":[]}"#), None);
+    }
+
+    #[test]
+    fn test_invalid_base64_blob() {
+        let response = |blob: &str| {
+            format!(
+                r#"{{"baton":null,"base_url":null,"results":[{{"type":"ok","response":{{"type":"execute","result":{{
+                    "cols":[{{"name":"b"}}],"rows":[[{{"type":"blob","base64":"{blob}"}}]],
+                    "affected_row_count":0,"last_insert_rowid":null}}}}}}]}}"#
+            )
+        };
+        assert!(parse_response::<pipeline::ServerMsg>(&response("3q2+7w")).is_ok());
+        let e = parse_response::<pipeline::ServerMsg>(&response("not base64!")).unwrap_err();
+        assert!(matches!(
+            e.downcast_ref::<Error>(),
+            Some(Error::MalformedResponse { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_describe_response() {
+        use crate::proto::{DescribeServerMsg, DescribeStreamResponse, DescribeStreamResult};
+
+        let response = r#"{"baton":null,"base_url":null,"results":[
+            {"type":"ok","response":{"type":"describe","result":{
+                "params":[{"name":null},{"name":":id"}],
+                "cols":[{"name":"name","decltype":"TEXT"},{"name":"n","decltype":null}],
+                "is_explain":false,"is_readonly":true}}},
+            {"type":"ok","response":{"type":"close"}}
+        ]}"#;
+        let msg: DescribeServerMsg = parse_response(response).unwrap();
+        let DescribeStreamResult::Ok {
+            response: DescribeStreamResponse::Describe { result },
+        } = &msg.results[0]
+        else {
+            panic!("expected a description, got {msg:?}");
+        };
+        assert_eq!(result.param_count(), 2);
+        assert_eq!(result.params[1].name.as_deref(), Some(":id"));
+        assert_eq!(result.cols[0].decltype.as_deref(), Some("TEXT"));
+        assert!(result.is_readonly);
+        assert!(matches!(
+            msg.results[1],
+            DescribeStreamResult::Ok {
+                response: DescribeStreamResponse::Other
+            }
+        ));
+    }
+
+    #[test]
+    fn test_correlation_ids_are_unique() {
+        let first = new_correlation_id();
+        let second = new_correlation_id();
+        assert_ne!(first, second);
+        assert_eq!(first.split('-').next(), second.split('-').next());
+    }
+
+    #[test]
+    fn test_join_pipeline_url_without_trailing_slash() {
+        assert_eq!(
+            join_pipeline_url("http://localhost:8080", DEFAULT_PIPELINE_PATH),
+            "http://localhost:8080/v2/pipeline"
+        );
+    }
+
+    #[test]
+    fn test_join_pipeline_url_with_trailing_slash() {
+        assert_eq!(
+            join_pipeline_url("http://localhost:8080/", DEFAULT_PIPELINE_PATH),
+            "http://localhost:8080/v2/pipeline"
+        );
+    }
+
+    #[test]
+    fn test_join_pipeline_url_with_path() {
+        assert_eq!(
+            join_pipeline_url("https://example.com/db1", DEFAULT_PIPELINE_PATH),
+            "https://example.com/db1/v2/pipeline"
+        );
+        assert_eq!(
+            join_pipeline_url("https://example.com/db1/", "/v3/pipeline"),
+            "https://example.com/db1/v3/pipeline"
+        );
+    }
+
+    #[test]
+    fn test_default_inner_client_fails() {
+        let client = Client::new(InnerClient::Default, "localhost:8080", "");
+        let e = futures::executor::block_on(client.execute("SELECT 1")).unwrap_err();
+        assert!(matches!(
+            e.downcast_ref::<Error>(),
+            Some(Error::Misuse(message)) if message == "no backend feature enabled"
+        ));
+    }
+
+    #[test]
+    fn test_clones_share_tx_ids() {
+        use std::sync::atomic::Ordering;
+        let client = Client::new(InnerClient::Default, "localhost:8080", "");
+        let clone = client.clone();
+        let first = client.next_tx_id.fetch_add(1, Ordering::Relaxed);
+        let second = clone.next_tx_id.fetch_add(1, Ordering::Relaxed);
+        assert_eq!(first, FIRST_GENERATED_TX_ID);
+        assert_eq!(second, first + 1);
+    }
+
+    #[test]
+    fn test_user_agent_header() {
+        let user_agent = |client: &Client| {
+            client
+                .request_headers("id")
+                .into_iter()
+                .find(|(name, _)| name == "User-Agent")
+                .map(|(_, value)| value)
+        };
+        let client = Client::new(InnerClient::Default, "localhost:8080", "");
+        assert_eq!(user_agent(&client).as_deref(), Some(DEFAULT_USER_AGENT));
+        assert!(DEFAULT_USER_AGENT.starts_with("libsql-client-rs/"));
+        let client = client.with_user_agent("my-app/1.0");
+        assert_eq!(user_agent(&client).as_deref(), Some("my-app/1.0"));
+    }
+
+    #[test]
+    fn test_namespace_routing() {
+        let client = Client::new(InnerClient::Default, "https://example.com:8080/", "")
+            .with_namespace("db1");
+        assert_eq!(
+            client.route(client.url_for_queries.clone()),
+            "https://db1.example.com:8080/v2/pipeline"
+        );
+        assert_eq!(
+            client.route("https://db1.example.com/v2/pipeline".to_string()),
+            "https://db1.example.com/v2/pipeline"
+        );
+        let client = client.with_namespace_routing(NamespaceRouting::Header);
+        assert_eq!(
+            client.route(client.url_for_queries.clone()),
+            "https://example.com:8080/v2/pipeline"
+        );
+        assert!(client
+            .request_headers("id")
+            .contains(&(NAMESPACE_HEADER.to_string(), "db1".to_string())));
+    }
+
+    #[cfg(feature = "mock")]
+    #[test]
+    fn test_not_found() {
+        let mock = crate::mock::MockClient::new();
+        let client = Client::new(InnerClient::Mock(mock.clone()), "localhost:8080", "");
+        // Only probing the pipeline endpoints tells unsupported versions apart
+        mock.fail_next_requests(404, 2);
+        let e = futures::executor::block_on(client.server_version()).unwrap_err();
+        assert!(matches!(
+            e.downcast_ref::<Error>(),
+            Some(Error::NoSupportedProtocolVersion { tried }) if tried.len() == 2
+        ));
+        // Otherwise, e.g. for an unknown namespace, it's just an HTTP error
+        mock.fail_next_requests(404, 1);
+        let e = futures::executor::block_on(client.execute("SELECT 1")).unwrap_err();
+        assert!(matches!(
+            e.downcast_ref::<Error>(),
+            Some(Error::Http { status: 404, .. })
+        ));
+    }
+
+    #[cfg(feature = "mock")]
+    #[test]
+    fn test_retry_backoff_uses_sleeper() {
+        use std::time::Duration;
+
+        #[derive(Debug, Default)]
+        struct FakeClock(std::sync::Mutex<Vec<Duration>>);
+
+        impl Sleeper for FakeClock {
+            fn sleep(&self, duration: Duration) -> futures::future::BoxFuture<'static, ()> {
+                self.0.lock().unwrap().push(duration);
+                Box::pin(async {})
+            }
+        }
+
+        let mock = crate::mock::MockClient::new();
+        mock.expect("SELECT 1").returns(ResultSet {
+            columns: vec![],
+            rows: vec![],
+            rows_affected: 0,
+            last_insert_rowid: None,
+        });
+        mock.fail_next_requests(503, 2);
+        let clock = Arc::new(FakeClock::default());
+        let client = Client::new(InnerClient::Mock(mock), "localhost:8080", "")
+            .with_retry_policy(RetryPolicy::new(3))
+            .with_sleeper(clock.clone());
+        futures::executor::block_on(client.execute("SELECT 1")).unwrap();
+        assert_eq!(
+            *clock.0.lock().unwrap(),
+            [Duration::from_millis(100), Duration::from_millis(200)]
+        );
+    }
+
+    #[test]
+    fn test_custom_transport() {
+        #[derive(Debug, Default)]
+        struct Recorder(std::sync::Mutex<Vec<(String, String)>>);
+
+        impl Transport for Recorder {
+            fn send(
+                &self,
+                url: String,
+                _auth: String,
+                _headers: Vec<(String, String)>,
+                body: String,
+            ) -> futures::future::BoxFuture<'_, Result<String>> {
+                self.0.lock().unwrap().push((url, body));
+                Box::pin(async {
+                    Ok(r#"{"baton":null,"base_url":null,"results":[
+                        {"type":"ok","response":{"type":"execute","result":{
+                            "cols":[{"name":"x"}],"rows":[[{"type":"integer","value":"1"}]],
+                            "affected_row_count":0,"last_insert_rowid":null}}},
+                        {"type":"ok","response":{"type":"close"}}
+                    ]}"#
+                    .to_string())
+                })
+            }
+        }
+
+        let recorder = Arc::new(Recorder::default());
+        let client = Client::new(
+            InnerClient::Custom(recorder.clone()),
+            "https://db.example.com",
+            "",
+        );
+        let rs = futures::executor::block_on(client.execute("SELECT 1")).unwrap();
+        assert_eq!(rs.rows[0].try_get::<i64>(0).unwrap(), 1);
+        let requests = recorder.0.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].0.starts_with("https://db.example.com/"));
+        assert!(requests[0].1.contains("SELECT 1"));
+        let e = futures::executor::block_on(client.stats()).unwrap_err();
+        assert!(e.to_string().contains("not supported by the transport"));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_wait_for_frame_is_time_bound() {
+        use std::time::{Duration, Instant};
+
+        // Replica which never catches up, and answers slowly
+        #[derive(Debug, Default)]
+        struct LaggingReplica(std::sync::atomic::AtomicUsize);
+
+        impl Transport for LaggingReplica {
+            fn send(
+                &self,
+                _url: String,
+                _auth: String,
+                _headers: Vec<(String, String)>,
+                _body: String,
+            ) -> futures::future::BoxFuture<'_, Result<String>> {
+                self.0.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                std::thread::sleep(Duration::from_millis(40));
+                Box::pin(async {
+                    Ok(r#"{"baton":null,"base_url":null,"results":[
+                        {"type":"ok","response":{"type":"execute","result":{
+                            "cols":[],"rows":[],"affected_row_count":0,
+                            "last_insert_rowid":null,"replication_index":"1"}}},
+                        {"type":"ok","response":{"type":"close"}}
+                    ]}"#
+                    .to_string())
+                })
+            }
+        }
+
+        #[derive(Debug)]
+        struct ThreadSleeper;
+
+        impl Sleeper for ThreadSleeper {
+            fn sleep(&self, duration: Duration) -> futures::future::BoxFuture<'static, ()> {
+                std::thread::sleep(duration);
+                Box::pin(async {})
+            }
+        }
+
+        let replica = Arc::new(LaggingReplica::default());
+        let client = Client::new(InnerClient::Custom(replica.clone()), "https://db", "")
+            .with_sleeper(Arc::new(ThreadSleeper));
+        let started = Instant::now();
+        let e = futures::executor::block_on(client.wait_for_frame(2, Duration::from_millis(200)))
+            .unwrap_err();
+        assert!(e.to_string().contains("Timed out"), "{e}");
+        // Each poll takes 40ms plus a 50ms pause, so counting polls would take 4 x 90ms
+        assert!(started.elapsed() < Duration::from_millis(300));
+        assert!(replica.0.load(std::sync::atomic::Ordering::Relaxed) <= 3);
+    }
+
+    #[test]
+    fn test_sql_cache() {
+        #[derive(Debug, Default)]
+        struct Server(std::sync::Mutex<Vec<serde_json::Value>>);
+
+        impl Transport for Server {
+            fn send(
+                &self,
+                _url: String,
+                _auth: String,
+                _headers: Vec<(String, String)>,
+                body: String,
+            ) -> futures::future::BoxFuture<'_, Result<String>> {
+                let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+                let results: Vec<_> = body["requests"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|request| match request["type"].as_str().unwrap() {
+                        "execute" => serde_json::json!({"type": "ok", "response": {
+                            "type": "execute",
+                            "result": {"cols": [], "rows": [], "affected_row_count": 0,
+                                "last_insert_rowid": null}}}),
+                        other => serde_json::json!({"type": "ok", "response": {"type": other}}),
+                    })
+                    .collect();
+                self.0.lock().unwrap().push(body);
+                let response =
+                    serde_json::json!({"baton": "b", "base_url": null, "results": results});
+                Box::pin(async move { Ok(response.to_string()) })
+            }
+        }
+
+        let server = Arc::new(Server::default());
+        let client = Client::new(
+            InnerClient::Custom(server.clone()),
+            "https://db.example.com",
+            "",
+        )
+        .with_sql_cache(2, 1);
+        futures::executor::block_on(async {
+            let tx = client.begin().await.unwrap();
+            for sql in ["SELECT 1", "SELECT 1", "SELECT 1", "SELECT 2", "SELECT 2"] {
+                client.execute_in(tx, sql).await.unwrap();
+            }
+        });
+        let requests = server.0.lock().unwrap();
+        let types = |i: usize| -> Vec<String> {
+            requests[i]["requests"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|r| r["type"].as_str().unwrap().to_string())
+                .collect()
+        };
+        // BEGIN and the first SELECT 1 are sent as text
+        assert_eq!(requests[1]["requests"][0]["stmt"]["sql"], "SELECT 1");
+        assert_eq!(types(2), ["store_sql", "execute"]);
+        assert_eq!(requests[2]["requests"][0]["sql"], "SELECT 1");
+        assert_eq!(requests[2]["requests"][1]["stmt"]["sql_id"], 0);
+        assert_eq!(types(3), ["execute"]);
+        assert_eq!(requests[3]["requests"][0]["stmt"]["sql_id"], 0);
+        assert!(requests[3]["requests"][0]["stmt"].get("sql").is_none());
+        assert_eq!(types(5), ["close_sql", "store_sql", "execute"]);
+        assert_eq!(requests[5]["requests"][0]["sql_id"], 0);
+        assert_eq!(requests[5]["requests"][2]["stmt"]["sql_id"], 1);
+        assert_eq!(
+            client.sql_cache_stats(),
+            Some(crate::sql_cache::SqlCacheStats {
+                hits: 1,
+                stores: 2,
+                evictions: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_request_id() {
+        let client = Client::new(InnerClient::Default, "https://db.example.com", "");
+        assert_ne!(client.next_correlation_id(), client.next_correlation_id());
+        let client = client.with_request_id("trace-1");
+        let id = client.next_correlation_id();
+        assert_eq!(id, "trace-1");
+        assert!(client
+            .request_headers(&id)
+            .contains(&(CORRELATION_ID_HEADER.to_string(), "trace-1".to_string())));
+    }
+
+    #[test]
+    fn test_max_response_bytes() {
+        #[derive(Debug)]
+        struct LargeResponse;
+
+        impl Transport for LargeResponse {
+            fn send(
+                &self,
+                _url: String,
+                _auth: String,
+                _headers: Vec<(String, String)>,
+                _body: String,
+            ) -> futures::future::BoxFuture<'_, Result<String>> {
+                Box::pin(async { Ok(" ".repeat(1024)) })
+            }
+        }
+
+        let client = Client::new(
+            InnerClient::Custom(Arc::new(LargeResponse)),
+            "https://db.example.com",
+            "",
+        )
+        .with_max_response_bytes(1000);
+        let e = futures::executor::block_on(client.execute("SELECT 1")).unwrap_err();
+        assert!(matches!(
+            e.downcast_ref::<Error>(),
+            Some(Error::ResponseTooLarge { limit: 1000 })
+        ));
+    }
+
+    #[test]
+    fn test_new_client_url_for_queries() {
+        let client = Client::new(InnerClient::Default, "db.example.com:8080", "");
+        assert_eq!(
+            client.url_for_queries,
+            "https://db.example.com:8080/v2/pipeline"
+        );
+        let client = client.with_protocol_version(ProtocolVersion::V3);
+        assert_eq!(
+            client.url_for_queries,
+            "https://db.example.com:8080/v3/pipeline"
+        );
+    }
+
+    #[test]
+    fn test_default_scheme() {
+        for (url, expected) in [
+            ("localhost:8080", "http://localhost:8080/v2/pipeline"),
+            ("127.0.0.1:8080", "http://127.0.0.1:8080/v2/pipeline"),
+            ("[::1]:8080", "http://[::1]:8080/v2/pipeline"),
+            (
+                "localhost.example.com",
+                "https://localhost.example.com/v2/pipeline",
+            ),
+            (
+                "https://localhost:8080",
+                "https://localhost:8080/v2/pipeline",
+            ),
+        ] {
+            let client = Client::new(InnerClient::Default, url, "");
+            assert_eq!(client.url_for_queries, expected, "{url}");
+        }
+        let client =
+            Client::new(InnerClient::Default, "10.0.0.5:8080", "").with_default_scheme("http");
+        assert_eq!(client.base_url, "http://10.0.0.5:8080");
+        assert_eq!(client.url_for_queries, "http://10.0.0.5:8080/v2/pipeline");
+        let client = Client::new(InnerClient::Default, "https://db.example.com", "")
+            .with_default_scheme("http");
+        assert_eq!(client.base_url, "https://db.example.com");
+    }
+}
@@ -0,0 +1,441 @@
+//! JSON serialization of query results, for servers which pass them on to their clients.
+//!
+//! [ResultSet] serializes as `{"columns": [...], "rows": [[...]], ...}` and [Row] as an array
+//! of its values. Values serialize as native JSON types: integers and floats become numbers,
+//! NULL becomes `null` and blobs become base64-encoded strings. Use [ResultSet::as_objects]
+//! to serialize rows as objects keyed by column name instead.
+//!
+//! JavaScript loses precision on integers beyond 2^53, so clients running in a browser
+//! may prefer [IntegerFormat::LargeAsString], set with the `integer_format()` method
+//! of [ResultSet::as_json], [ResultSet::as_objects] or [JsonValue].
+//!
+//! Both types also deserialize from this shape. JSON does not tell blobs and quoted integers
+//! apart from text, so they come back as [Value::Text].
+
+use base64::prelude::BASE64_STANDARD_NO_PAD;
+use base64::Engine;
+use serde::de::{Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, SerializeStruct, Serializer};
+
+use crate::{utils, ResultSet, Row, Value};
+
+/// Largest integer which a JavaScript number represents exactly, `2^53 - 1`.
+const MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+
+/// How integers are written to JSON.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IntegerFormat {
+    /// Every integer is a JSON number.
+    #[default]
+    Number,
+    /// Integers outside of `±(2^53 - 1)` are quoted, e.g. `"9007199254740993"`,
+    /// so that JavaScript clients don't round them. Smaller ones are numbers.
+    LargeAsString,
+}
+
+/// Serializes a [Value] as a native JSON type. Non-finite floats become `null`.
+///
+/// # Examples
+/// ```
+/// use libsql_client::json::{IntegerFormat, JsonValue};
+/// use libsql_client::Value;
+///
+/// let value = Value::Integer { value: 1 << 60 };
+/// assert_eq!(serde_json::to_string(&JsonValue::new(&value)).unwrap(), "1152921504606846976");
+/// let quoted = JsonValue::new(&value).integer_format(IntegerFormat::LargeAsString);
+/// assert_eq!(serde_json::to_string(&quoted).unwrap(), r#""1152921504606846976""#);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct JsonValue<'a> {
+    value: &'a Value,
+    integers: IntegerFormat,
+}
+
+impl<'a> JsonValue<'a> {
+    /// Wraps a value, writing integers as [IntegerFormat::Number].
+    pub fn new(value: &'a Value) -> Self {
+        Self {
+            value,
+            integers: IntegerFormat::default(),
+        }
+    }
+
+    /// Sets how integers are written, [IntegerFormat::Number] by default.
+    pub fn integer_format(mut self, integers: IntegerFormat) -> Self {
+        self.integers = integers;
+        self
+    }
+}
+
+impl Serialize for JsonValue<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.value {
+            Value::Null => serializer.serialize_none(),
+            Value::Integer { value }
+                if self.integers == IntegerFormat::LargeAsString
+                    && !(-MAX_SAFE_INTEGER..=MAX_SAFE_INTEGER).contains(value) =>
+            {
+                serializer.collect_str(value)
+            }
+            Value::Integer { value } => serializer.serialize_i64(*value),
+            Value::Float { value } if value.is_finite() => serializer.serialize_f64(*value),
+            Value::Float { .. } => serializer.serialize_none(),
+            Value::Text { value } => serializer.serialize_str(value),
+            Value::Blob { value } => {
+                serializer.serialize_str(&BASE64_STANDARD_NO_PAD.encode(value))
+            }
+        }
+    }
+}
+
+struct JsonRow<'a> {
+    values: &'a [Value],
+    integers: IntegerFormat,
+}
+
+impl Serialize for JsonRow<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.values.len()))?;
+        for value in self.values {
+            seq.serialize_element(&JsonValue::new(value).integer_format(self.integers))?;
+        }
+        seq.end()
+    }
+}
+
+impl Serialize for Row {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        JsonRow {
+            values: &self.values,
+            integers: IntegerFormat::default(),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Serializes a [ResultSet] with its rows as arrays of values. See [ResultSet::as_json].
+#[derive(Clone, Copy, Debug)]
+pub struct JsonResultSet<'a> {
+    result_set: &'a ResultSet,
+    integers: IntegerFormat,
+}
+
+impl<'a> JsonResultSet<'a> {
+    pub(crate) fn new(result_set: &'a ResultSet) -> Self {
+        Self {
+            result_set,
+            integers: IntegerFormat::default(),
+        }
+    }
+
+    /// Sets how integers are written, [IntegerFormat::Number] by default.
+    pub fn integer_format(mut self, integers: IntegerFormat) -> Self {
+        self.integers = integers;
+        self
+    }
+}
+
+impl Serialize for JsonResultSet<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let rs = self.result_set;
+        let rows: Vec<JsonRow> = rs
+            .rows
+            .iter()
+            .map(|row| JsonRow {
+                values: &row.values,
+                integers: self.integers,
+            })
+            .collect();
+        let mut result_set = serializer.serialize_struct("ResultSet", 4)?;
+        result_set.serialize_field("columns", &rs.columns)?;
+        result_set.serialize_field("rows", &rows)?;
+        result_set.serialize_field("rows_affected", &rs.rows_affected)?;
+        result_set.serialize_field("last_insert_rowid", &rs.last_insert_rowid)?;
+        result_set.end()
+    }
+}
+
+impl Serialize for ResultSet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_json().serialize(serializer)
+    }
+}
+
+/// Reads a [Value] back from a native JSON type.
+struct PlainValue(Value);
+
+impl<'de> Deserialize<'de> for PlainValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct PlainValueVisitor;
+
+        impl<'de> Visitor<'de> for PlainValueVisitor {
+            type Value = PlainValue;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("null, a number or a string")
+            }
+
+            fn visit_unit<E: serde::de::Error>(self) -> Result<PlainValue, E> {
+                Ok(PlainValue(Value::Null))
+            }
+
+            fn visit_none<E: serde::de::Error>(self) -> Result<PlainValue, E> {
+                Ok(PlainValue(Value::Null))
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<PlainValue, E> {
+                Ok(PlainValue(Value::Integer { value }))
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<PlainValue, E> {
+                i64::try_from(value)
+                    .map(|value| PlainValue(Value::Integer { value }))
+                    .map_err(|_| E::custom(format!("integer {value} is out of range")))
+            }
+
+            fn visit_f64<E: serde::de::Error>(self, value: f64) -> Result<PlainValue, E> {
+                Ok(PlainValue(Value::Float { value }))
+            }
+
+            fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<PlainValue, E> {
+                Ok(PlainValue(Value::Text {
+                    value: value.to_string(),
+                }))
+            }
+
+            fn visit_string<E: serde::de::Error>(self, value: String) -> Result<PlainValue, E> {
+                Ok(PlainValue(Value::Text { value }))
+            }
+        }
+
+        deserializer.deserialize_any(PlainValueVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for Row {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let values: Vec<PlainValue> = Deserialize::deserialize(deserializer)?;
+        Ok(Row {
+            values: values.into_iter().map(|PlainValue(value)| value).collect(),
+            #[cfg(feature = "mapping_names_to_values_in_rows")]
+            value_map: Default::default(),
+        })
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct ResultSetFields {
+    columns: Vec<String>,
+    rows: Vec<Row>,
+    #[serde(default)]
+    rows_affected: u64,
+    #[serde(default)]
+    last_insert_rowid: Option<i64>,
+}
+
+impl<'de> Deserialize<'de> for ResultSet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let fields = ResultSetFields::deserialize(deserializer)?;
+        #[cfg(feature = "mapping_names_to_values_in_rows")]
+        let rows = {
+            let keys = utils::disambiguate_columns(&fields.columns);
+            let mut rows = fields.rows;
+            for row in &mut rows {
+                row.value_map = keys.iter().cloned().zip(row.values.clone()).collect();
+            }
+            rows
+        };
+        #[cfg(not(feature = "mapping_names_to_values_in_rows"))]
+        let rows = fields.rows;
+        Ok(ResultSet {
+            columns: fields.columns,
+            rows,
+            rows_affected: fields.rows_affected,
+            last_insert_rowid: fields.last_insert_rowid,
+        })
+    }
+}
+
+/// Serializes the rows of a [ResultSet] as an array of objects keyed by column name.
+/// See [ResultSet::as_objects].
+#[derive(Clone, Copy, Debug)]
+pub struct ColumnObjects<'a> {
+    result_set: &'a ResultSet,
+    integers: IntegerFormat,
+}
+
+impl<'a> ColumnObjects<'a> {
+    pub(crate) fn new(result_set: &'a ResultSet) -> Self {
+        Self {
+            result_set,
+            integers: IntegerFormat::default(),
+        }
+    }
+
+    /// Sets how integers are written, [IntegerFormat::Number] by default.
+    pub fn integer_format(mut self, integers: IntegerFormat) -> Self {
+        self.integers = integers;
+        self
+    }
+}
+
+impl Serialize for ColumnObjects<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let rs = self.result_set;
+        let columns = utils::disambiguate_columns(&rs.columns);
+        let mut seq = serializer.serialize_seq(Some(rs.rows.len()))?;
+        for row in &rs.rows {
+            seq.serialize_element(&RowObject {
+                columns: &columns,
+                row,
+                integers: self.integers,
+            })?;
+        }
+        seq.end()
+    }
+}
+
+struct RowObject<'a> {
+    columns: &'a [String],
+    row: &'a Row,
+    integers: IntegerFormat,
+}
+
+impl Serialize for RowObject<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.columns.len()))?;
+        for (column, value) in self.columns.iter().zip(&self.row.values) {
+            map.serialize_entry(column, &JsonValue::new(value).integer_format(self.integers))?;
+        }
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result_set() -> ResultSet {
+        ResultSet {
+            columns: vec!["id".to_string(), "name".to_string(), "data".to_string()],
+            rows: vec![Row {
+                values: vec![
+                    Value::Integer { value: 1 },
+                    Value::Null,
+                    Value::Blob {
+                        value: vec![0xca, 0xfe],
+                    },
+                ],
+                #[cfg(feature = "mapping_names_to_values_in_rows")]
+                value_map: Default::default(),
+            }],
+            rows_affected: 0,
+            last_insert_rowid: None,
+        }
+    }
+
+    #[test]
+    fn test_result_set_to_json() {
+        let json = serde_json::to_value(result_set()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "columns": ["id", "name", "data"],
+                "rows": [[1, null, "yv4"]],
+                "rows_affected": 0,
+                "last_insert_rowid": null,
+            })
+        );
+    }
+
+    #[test]
+    fn test_column_objects_to_json() {
+        let rs = result_set();
+        let json = serde_json::to_value(rs.as_objects()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([{"id": 1, "name": null, "data": "yv4"}])
+        );
+    }
+
+    #[test]
+    fn test_result_set_from_json() {
+        let rs: ResultSet = serde_json::from_value(serde_json::json!({
+            "columns": ["id", "score", "name", "id"],
+            "rows": [[1, 2.5, "Jane", null]],
+            "rows_affected": 1,
+            "last_insert_rowid": 7,
+        }))
+        .unwrap();
+        assert_eq!(rs.columns, ["id", "score", "name", "id"]);
+        assert!(matches!(
+            rs.rows[0].values.as_slice(),
+            [
+                Value::Integer { value: 1 },
+                Value::Float { value },
+                Value::Text { value: name },
+                Value::Null,
+            ] if *value == 2.5 && name == "Jane"
+        ));
+        assert_eq!(rs.rows_affected, 1);
+        assert_eq!(rs.last_insert_rowid, Some(7));
+        #[cfg(feature = "mapping_names_to_values_in_rows")]
+        {
+            assert!(matches!(
+                rs.rows[0].value_map["id"],
+                Value::Integer { value: 1 }
+            ));
+            assert!(matches!(rs.rows[0].value_map["id:1"], Value::Null));
+        }
+    }
+
+    #[test]
+    fn test_result_set_json_round_trip() {
+        let json = serde_json::to_string(&result_set()).unwrap();
+        let rs: ResultSet = serde_json::from_str(&json).unwrap();
+        assert_eq!(rs.columns, result_set().columns);
+        assert!(matches!(rs.rows[0].values[0], Value::Integer { value: 1 }));
+        assert!(matches!(rs.rows[0].values[1], Value::Null));
+        // Blobs come back as their base64 text
+        assert!(matches!(&rs.rows[0].values[2], Value::Text { value } if value == "yv4"));
+    }
+
+    #[test]
+    fn test_column_objects_with_duplicate_columns() {
+        let mut rs = result_set();
+        rs.columns = vec!["id".to_string(), "id".to_string(), "id".to_string()];
+        let json = serde_json::to_string(&rs.as_objects()).unwrap();
+        assert_eq!(json, r#"[{"id":1,"id:1":null,"id:2":"yv4"}]"#);
+    }
+
+    #[test]
+    fn test_non_finite_float_is_null() {
+        let value = Value::Float { value: f64::NAN };
+        assert_eq!(
+            serde_json::to_string(&JsonValue::new(&value)).unwrap(),
+            "null"
+        );
+    }
+
+    #[test]
+    fn test_large_integers_as_strings() {
+        let mut rs = result_set();
+        rs.rows[0].values[0] = Value::Integer {
+            value: MAX_SAFE_INTEGER + 2,
+        };
+        rs.rows[0].values[1] = Value::Integer {
+            value: -MAX_SAFE_INTEGER,
+        };
+        let json = serde_json::to_value(rs.as_json().integer_format(IntegerFormat::LargeAsString))
+            .unwrap();
+        assert_eq!(
+            json["rows"],
+            serde_json::json!([["9007199254740993", -9007199254740991i64, "yv4"]])
+        );
+        let json =
+            serde_json::to_value(rs.as_objects().integer_format(IntegerFormat::LargeAsString))
+                .unwrap();
+        assert_eq!(json[0]["id"], "9007199254740993");
+        assert_eq!(json[0]["name"], -9007199254740991i64);
+    }
+}
@@ -15,7 +15,13 @@ pub mod statement;
 pub use statement::Statement;
 
 pub mod proto;
-pub use proto::{BatchResult, Col, Value};
+pub use proto::{BatchResult, Col, ProtocolVersion, StmtDescription, Value};
+
+pub mod error;
+pub use error::Error;
+
+pub mod value;
+pub use value::{ValueExt, ValueKey};
 
 #[cfg(feature = "mapping_names_to_values_in_rows")]
 pub mod de;
@@ -23,10 +29,15 @@ pub mod de;
 #[cfg(feature = "workers_backend")]
 pub use worker;
 
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Debug)]
 /// Represents a row returned from the database.
+///
+/// Serializes as an array of its values, see [json] for details. A row deserialized
+/// on its own has no column names, so its `value_map` is empty.
 pub struct Row {
     pub values: Vec<Value>,
+    /// Values keyed by column name. If several columns share a name, the leftmost one
+    /// keeps it and the others get a suffix with their occurrence, e.g. `id`, `id:1`, `id:2`.
     #[cfg(feature = "mapping_names_to_values_in_rows")]
     pub value_map: std::collections::HashMap<String, Value>,
 }
@@ -61,6 +72,37 @@ impl<'a> Row {
         val.try_into().map_err(|x: String| anyhow::anyhow!(x))
     }
 
+    /// Returns the value at the given position, or `None` if the index is out of bounds.
+    /// Positional access works regardless of columns sharing a name.
+    ///
+    /// # Examples
+    /// ```
+    /// let db = libsql_client::SyncClient::in_memory().unwrap();
+    /// let rs = db.execute("select 1 as id, 2 as id").unwrap();
+    /// assert!(matches!(rs.rows[0].get_by_index(1), Some(libsql_client::Value::Integer { value: 2 })));
+    /// ```
+    pub fn get_by_index(&self, index: usize) -> Option<&Value> {
+        self.values.get(index)
+    }
+
+    /// Get a blob value by index from this row, encoded as a lowercase hex string
+    ///
+    /// Will return an error if the index is invalid or if the value is not a blob
+    ///
+    /// # Examples
+    /// ```
+    /// let db = libsql_client::SyncClient::in_memory().unwrap();
+    /// let rs = db.execute("select x'cafe'").unwrap();
+    /// assert_eq!(rs.rows[0].get_hex(0).unwrap(), "cafe");
+    /// ```
+    pub fn get_hex(&self, index: usize) -> anyhow::Result<String> {
+        match self.values.get(index) {
+            Some(Value::Blob { value }) => Ok(utils::encode_hex(value)),
+            Some(other) => Err(anyhow::anyhow!("value {other} is not a blob")),
+            None => Err(anyhow::anyhow!("out of bound index {}", index)),
+        }
+    }
+
     /// Try to get a value given a column name from this row and convert it to the desired type
     ///
     /// Will return an error if the column name is invalid or if the value cannot be converted to the
@@ -90,11 +132,31 @@ impl<'a> Row {
             .ok_or(anyhow::anyhow!("column `{}` not present", col))?;
         val.try_into().map_err(|x: String| anyhow::anyhow!(x))
     }
+
+    /// Returns the values of this row keyed by their column names
+    ///
+    /// # Examples
+    /// ```
+    /// let db = libsql_client::SyncClient::in_memory().unwrap();
+    /// let rs = db.execute("select 1 as one, 'two' as two").unwrap();
+    /// let map = rs.rows[0].as_map();
+    /// assert!(matches!(map["one"], libsql_client::Value::Integer { value: 1 }));
+    /// ```
+    #[cfg(feature = "mapping_names_to_values_in_rows")]
+    pub fn as_map(&self) -> std::collections::HashMap<&str, &Value> {
+        self.value_map
+            .iter()
+            .map(|(column, value)| (column.as_str(), value))
+            .collect()
+    }
 }
 
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Debug)]
 /// Represents the result of a database query
 ///
+/// Serializes as `{"columns": [...], "rows": [[...]], ...}` and deserializes from the same
+/// shape, see [json] for details.
+///
 /// # Examples
 /// ```
 /// # async fn f() {
@@ -115,6 +177,7 @@ pub struct ResultSet {
     /// name of the columns present in this `ResultSet`.
     pub columns: Vec<String>,
     /// One entry per row returned from the database. See [Row] for details.
+    /// Writes with a `RETURNING` clause return rows too, along with their [ResultSet::rows_affected].
     pub rows: Vec<Row>,
     /// How many rows were changed by this statement
     pub rows_affected: u64,
@@ -123,6 +186,208 @@ pub struct ResultSet {
     pub last_insert_rowid: Option<i64>,
 }
 
+impl ResultSet {
+    /// Converts the rows into maps from column names to values.
+    /// If several columns share a name, the leftmost one keeps it and the others get
+    /// a suffix with their occurrence, e.g. `id`, `id:1`, `id:2`.
+    ///
+    /// # Examples
+    /// ```
+    /// let db = libsql_client::SyncClient::in_memory().unwrap();
+    /// let rs = db.execute("select 1 as one, 2 as two, 3 as one").unwrap();
+    /// let maps = rs.into_maps();
+    /// assert_eq!(maps[0].len(), 3);
+    /// assert!(matches!(maps[0]["one"], libsql_client::Value::Integer { value: 1 }));
+    /// assert!(matches!(maps[0]["one:1"], libsql_client::Value::Integer { value: 3 }));
+    /// ```
+    pub fn into_maps(self) -> Vec<std::collections::HashMap<String, Value>> {
+        let columns = utils::disambiguate_columns(&self.columns);
+        self.rows
+            .into_iter()
+            .map(|row| columns.iter().cloned().zip(row.values).collect())
+            .collect()
+    }
+
+    /// Returns whether the statement changed any rows, judging by [ResultSet::rows_affected]
+    /// alone, without parsing the SQL. An `UPDATE` or `DELETE` which matched no rows
+    /// reports `false`, and so does DDL like `CREATE TABLE`, whose changes SQLite doesn't count.
+    ///
+    /// # Examples
+    /// ```
+    /// let db = libsql_client::SyncClient::in_memory().unwrap();
+    /// db.execute("create table t(x)").unwrap();
+    /// assert!(db.execute("insert into t values (1)").unwrap().is_write());
+    /// assert!(!db.execute("select * from t").unwrap().is_write());
+    /// ```
+    pub fn is_write(&self) -> bool {
+        self.rows_affected > 0
+    }
+
+    /// Returns a wrapper which serializes the rows as objects keyed by column name,
+    /// e.g. `[{"id": 1, "name": "Jane"}]`. If several columns share a name, the leftmost
+    /// one keeps it and the others get a suffix with their occurrence, e.g. `id:1`.
+    ///
+    /// # Examples
+    /// ```
+    /// let db = libsql_client::SyncClient::in_memory().unwrap();
+    /// let rs = db.execute("select 1 as id, 'Jane' as name").unwrap();
+    /// let json = serde_json::to_string(&rs.as_objects()).unwrap();
+    /// assert_eq!(json, r#"[{"id":1,"name":"Jane"}]"#);
+    /// ```
+    pub fn as_objects(&self) -> json::ColumnObjects<'_> {
+        json::ColumnObjects::new(self)
+    }
+
+    /// Returns a wrapper which serializes the result set like its [serde::Serialize]
+    /// implementation does, with options such as [json::IntegerFormat].
+    ///
+    /// # Examples
+    /// ```
+    /// use libsql_client::json::IntegerFormat;
+    ///
+    /// let db = libsql_client::SyncClient::in_memory().unwrap();
+    /// let rs = db.execute("select 9007199254740993 as id").unwrap();
+    /// let json = rs.as_json().integer_format(IntegerFormat::LargeAsString);
+    /// let json = serde_json::to_value(json).unwrap();
+    /// assert_eq!(json["rows"][0][0], "9007199254740993");
+    /// ```
+    pub fn as_json(&self) -> json::JsonResultSet<'_> {
+        json::JsonResultSet::new(self)
+    }
+
+    /// Sorts the rows in place with a comparator, without another round trip
+    /// to the database. The sort is stable, so rows which compare equal keep
+    /// their original relative order. [ValueExt::sql_cmp] compares values the way SQLite does.
+    ///
+    /// # Examples
+    /// ```
+    /// use libsql_client::ValueExt;
+    ///
+    /// let db = libsql_client::SyncClient::in_memory().unwrap();
+    /// let mut rs = db.execute("select 2 union all select 1").unwrap();
+    /// rs.sort_by(|a, b| a.values[0].sql_cmp(&b.values[0]));
+    /// assert_eq!(rs.rows[0].try_get::<usize>(0).unwrap(), 1);
+    /// ```
+    pub fn sort_by(&mut self, compare: impl FnMut(&Row, &Row) -> std::cmp::Ordering) {
+        self.rows.sort_by(compare)
+    }
+
+    /// Sorts the rows in place by the values of the named column,
+    /// compared with [ValueExt::sql_cmp]: numbers by value rather than as text.
+    /// Ties keep their original relative order.
+    ///
+    /// # Examples
+    /// ```
+    /// use libsql_client::SortOrder;
+    ///
+    /// let db = libsql_client::SyncClient::in_memory().unwrap();
+    /// let mut rs = db.execute("select 9 as n union all select 10").unwrap();
+    /// rs.sort_by_column("n", SortOrder::Descending).unwrap();
+    /// assert_eq!(rs.rows[0].try_get::<usize>(0).unwrap(), 10);
+    /// ```
+    pub fn sort_by_column(&mut self, name: &str, order: SortOrder) -> anyhow::Result<()> {
+        let index = self
+            .columns
+            .iter()
+            .position(|column| column == name)
+            .ok_or_else(|| anyhow::anyhow!("No column named {name}"))?;
+        self.rows.sort_by(|a, b| {
+            let ordering = match (a.values.get(index), b.values.get(index)) {
+                (Some(a), Some(b)) => a.sql_cmp(b),
+                (a, b) => a.is_some().cmp(&b.is_some()),
+            };
+            match order {
+                SortOrder::Ascending => ordering,
+                SortOrder::Descending => ordering.reverse(),
+            }
+        });
+        Ok(())
+    }
+
+    /// Renders the result set as an aligned ASCII table, for CLI tools and debug output.
+    /// NULLs are shown as `NULL` and blobs as hex literals, e.g. `x'cafe'`.
+    ///
+    /// # Examples
+    /// ```
+    /// let db = libsql_client::SyncClient::in_memory().unwrap();
+    /// let rs = db.execute("select 1 as id, 'Jane' as name, null as note").unwrap();
+    /// assert_eq!(
+    ///     rs.to_table_string(),
+    ///     "+----+------+------+\n\
+    ///      | id | name | note |\n\
+    ///      +----+------+------+\n\
+    ///      | 1  | Jane | NULL |\n\
+    ///      +----+------+------+\n"
+    /// );
+    /// ```
+    pub fn to_table_string(&self) -> String {
+        let cells: Vec<Vec<String>> = self
+            .rows
+            .iter()
+            .map(|row| row.values.iter().map(utils::display_cell).collect())
+            .collect();
+        let widths: Vec<usize> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                cells
+                    .iter()
+                    .filter_map(|row| row.get(i))
+                    .map(|cell| cell.chars().count())
+                    .chain(std::iter::once(column.chars().count()))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let separator: String = widths
+            .iter()
+            .map(|width| format!("+{}", "-".repeat(width + 2)))
+            .collect::<String>()
+            + "+\n";
+        let line = |values: &[String]| -> String {
+            widths
+                .iter()
+                .enumerate()
+                .map(|(i, width)| {
+                    let value = values.get(i).map(String::as_str).unwrap_or("");
+                    let padding = width - value.chars().count();
+                    format!("| {value}{} ", " ".repeat(padding))
+                })
+                .collect::<String>()
+                + "|\n"
+        };
+        let mut table = separator.clone();
+        table += &line(&self.columns);
+        table += &separator;
+        for row in &cells {
+            table += &line(row);
+        }
+        if !cells.is_empty() {
+            table += &separator;
+        }
+        table
+    }
+}
+
+/// Direction of [ResultSet::sort_by_column].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+/// Consistency of reads executed through a replica, see [`Client::with_consistency()`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConsistencyMode {
+    /// Reads may be served by a replica which didn't yet apply this client's writes.
+    #[default]
+    Eventual,
+    /// Reads observe all writes previously acknowledged to this client and its clones.
+    Strong,
+}
+
 impl std::convert::From<proto::StmtResult> for ResultSet {
     fn from(value: proto::StmtResult) -> Self {
         let columns: Vec<String> = value
@@ -130,15 +395,17 @@ impl std::convert::From<proto::StmtResult> for ResultSet {
             .into_iter()
             .map(|c| c.name.unwrap_or_default())
             .collect();
+        #[cfg(feature = "mapping_names_to_values_in_rows")]
+        let map_keys = utils::disambiguate_columns(&columns);
         let rows = value
             .rows
             .into_iter()
             .map(|values| {
                 #[cfg(feature = "mapping_names_to_values_in_rows")]
-                let value_map = columns
+                let value_map = map_keys
                     .iter()
-                    .enumerate()
-                    .map(|(i, c)| (c.to_string(), values[i].clone()))
+                    .zip(values.iter())
+                    .map(|(c, v)| (c.clone(), v.clone()))
                     .collect();
                 Row {
                     values,
@@ -157,14 +424,61 @@ impl std::convert::From<proto::StmtResult> for ResultSet {
 }
 
 pub mod client;
-pub use client::{Client, Config, SyncClient};
+pub use client::{Client, Config, ConfigOptions, SyncClient};
+
+pub mod pages;
+pub use pages::Pages;
+
+pub mod rows;
+pub use rows::{RowRef, Rows};
+
+pub mod diagnostics;
+
+pub mod json;
+
+pub mod script;
+
+pub mod explain;
+pub use explain::{PlanNode, QueryPlan};
+
+mod connection_string;
+
+pub mod builder;
+pub use builder::ClientBuilder;
 
-#[cfg(any(
-    feature = "reqwest_backend",
-    feature = "workers_backend",
-    feature = "spin_backend",
-))]
+pub mod retry;
+pub use retry::RetryPolicy;
+
+pub mod database_client;
+pub use database_client::DatabaseClient;
+
+pub mod batch;
+pub use batch::{BatchBuilder, MultiResultSet};
+
+pub mod cancel;
+pub use cancel::CancellationToken;
+
+// std::time::Instant panics on wasm32-unknown-unknown, so deadlines,
+// circuit breakers and the query cache are not available there
+#[cfg(not(target_arch = "wasm32"))]
+pub mod deadline;
+#[cfg(not(target_arch = "wasm32"))]
+pub use deadline::Deadline;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod circuit;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cache;
+
+mod crud;
+
+mod query;
+
+#[cfg(http_backend)]
 pub mod http;
+#[cfg(http_backend)]
+pub mod sql_cache;
 pub mod transaction;
 pub use transaction::{SyncTransaction, Transaction};
 
@@ -180,9 +494,25 @@ pub mod local;
 #[cfg(feature = "spin_backend")]
 pub mod spin;
 
+#[cfg(all(unix, feature = "unix_backend"))]
+pub mod unix;
+
 #[cfg(feature = "hrana_backend")]
 pub mod hrana;
+
+#[cfg(feature = "response_body")]
+pub mod body;
+
+#[cfg(feature = "metrics")]
+pub mod metrics;
+
+#[cfg(feature = "arrow")]
+pub mod arrow;
+
+#[cfg(feature = "mock")]
+pub mod mock;
 mod utils;
+pub use utils::{quote_identifier, quote_string_literal};
 
 /// A macro for passing parameters to statements without having to manually
 /// define their types.
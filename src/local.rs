@@ -1,3 +1,4 @@
+use crate::batch::BatchBuilder;
 use crate::{proto, proto::StmtResult, BatchResult, Col, ResultSet, Statement, Value};
 use anyhow::Result;
 use sqlite3_parser::ast::{Cmd, Stmt};
@@ -63,9 +64,7 @@ impl Client {
     }
 
     pub fn from_env() -> anyhow::Result<Self> {
-        let path = std::env::var("LIBSQL_CLIENT_URL").map_err(|_| {
-            anyhow::anyhow!("LIBSQL_CLIENT_URL variable should point to your sqld database")
-        })?;
+        let path = crate::client::url_from_env()?;
         let path = match path.strip_prefix("file:///") {
             Some(path) => path,
             None => anyhow::bail!("Local URL needs to start with file:///"),
@@ -100,65 +99,18 @@ impl Client {
         let mut step_results = vec![];
         let mut step_errors = vec![];
         for stmt in stmts {
-            let stmt = stmt.into();
-            let sql_string = &stmt.sql;
-            let params: libsql::Params = stmt
-                .args
-                .into_iter()
-                .map(ValueWrapper)
-                .map(libsql::Value::from)
-                .collect::<Vec<_>>()
-                .into();
-            let stmt = self.conn.prepare(sql_string)?;
-            let cols: Vec<Col> = stmt
-                .columns()
-                .into_iter()
-                .map(|c| Col {
-                    name: Some(c.name().to_string()),
-                })
-                .collect();
-            let mut rows = Vec::new();
-            let input_rows = match stmt.query(&params) {
-                Ok(rows) => rows,
+            let Statement { sql, args } = stmt.into();
+            match self.execute_step(&sql, args)? {
+                Ok(stmt_result) => {
+                    step_results.push(Some(stmt_result));
+                    step_errors.push(None);
+                }
                 Err(e) => {
                     step_results.push(None);
-                    step_errors.push(Some(proto::Error {
-                        message: e.to_string(),
-                    }));
+                    step_errors.push(Some(e));
                     break;
                 }
-            };
-            while let Some(row) = input_rows.next()? {
-                let cells = (0..cols.len())
-                    .map(|i| ValueWrapper::from(row.get_value(i as i32).unwrap()).0)
-                    .collect();
-                rows.push(cells)
             }
-            let parser = Parser::new(sql_string.as_bytes());
-            let cmd = parser.last();
-
-            let last_insert_rowid = match cmd {
-                Ok(Some(Cmd::Stmt(Stmt::Insert { .. }))) => Some(self.conn.last_insert_rowid()),
-                _ => None,
-            };
-
-            let affected_row_count = match cmd {
-                Ok(Some(
-                    Cmd::Stmt(Stmt::Insert { .. })
-                    | Cmd::Stmt(Stmt::Update { .. })
-                    | Cmd::Stmt(Stmt::Delete { .. }),
-                )) => self.conn.changes(),
-                _ => 0,
-            };
-
-            let stmt_result = StmtResult {
-                cols,
-                rows,
-                affected_row_count,
-                last_insert_rowid,
-            };
-            step_results.push(Some(stmt_result));
-            step_errors.push(None);
         }
         Ok(BatchResult {
             step_results,
@@ -166,6 +118,107 @@ impl Client {
         })
     }
 
+    /// Executes a batch whose steps may be conditioned on the outcomes of earlier steps.
+    /// Unlike [`Client::raw_batch()`], a failing step doesn't stop the batch:
+    /// later steps decide whether to run through their conditions.
+    pub fn execute_batch(&self, batch: BatchBuilder) -> anyhow::Result<BatchResult> {
+        let mut step_results = vec![];
+        let mut step_errors = vec![];
+        let mut outcomes = vec![];
+        for (stmt, cond) in batch.steps {
+            if cond.is_some_and(|cond| !cond.eval(&outcomes)) {
+                step_results.push(None);
+                step_errors.push(None);
+                outcomes.push(None);
+                continue;
+            }
+            match self.execute_step(&stmt.sql, stmt.args)? {
+                Ok(stmt_result) => {
+                    step_results.push(Some(stmt_result));
+                    step_errors.push(None);
+                    outcomes.push(Some(true));
+                }
+                Err(e) => {
+                    step_results.push(None);
+                    step_errors.push(Some(e));
+                    outcomes.push(Some(false));
+                }
+            }
+        }
+        Ok(BatchResult {
+            step_results,
+            step_errors,
+        })
+    }
+
+    // Executes a single step of a batch. The outer result fails if reading
+    // the results fails, and the inner one if the statement itself fails.
+    fn execute_step(
+        &self,
+        sql_string: &str,
+        args: Vec<Value>,
+    ) -> anyhow::Result<Result<StmtResult, proto::Error>> {
+        let params: libsql::Params = args
+            .into_iter()
+            .map(ValueWrapper)
+            .map(libsql::Value::from)
+            .collect::<Vec<_>>()
+            .into();
+        let prepared = match self.conn.prepare(sql_string) {
+            Ok(prepared) => prepared,
+            Err(e) => {
+                return Ok(Err(proto::Error {
+                    message: e.to_string(),
+                }))
+            }
+        };
+        let cols: Vec<Col> = prepared
+            .columns()
+            .into_iter()
+            .map(|c| Col {
+                name: Some(c.name().to_string()),
+            })
+            .collect();
+        let mut rows = Vec::new();
+        let input_rows = match prepared.query(&params) {
+            Ok(rows) => rows,
+            Err(e) => {
+                return Ok(Err(proto::Error {
+                    message: e.to_string(),
+                }))
+            }
+        };
+        while let Some(row) = input_rows.next()? {
+            let cells = (0..cols.len())
+                .map(|i| ValueWrapper::from(row.get_value(i as i32).unwrap()).0)
+                .collect();
+            rows.push(cells)
+        }
+        let parser = Parser::new(sql_string.as_bytes());
+        let cmd = parser.last();
+
+        let last_insert_rowid = match cmd {
+            Ok(Some(Cmd::Stmt(Stmt::Insert { .. }))) => Some(self.conn.last_insert_rowid()),
+            _ => None,
+        };
+
+        let affected_row_count = match cmd {
+            Ok(Some(
+                Cmd::Stmt(Stmt::Insert { .. })
+                | Cmd::Stmt(Stmt::Update { .. })
+                | Cmd::Stmt(Stmt::Delete { .. }),
+            )) => self.conn.changes(),
+            _ => 0,
+        };
+
+        Ok(Ok(StmtResult {
+            cols,
+            rows,
+            affected_row_count,
+            last_insert_rowid,
+        }))
+    }
+
     /// Executes a batch of SQL statements, wrapped in "BEGIN", "END", transaction-style.
     /// Each statement is going to run in its own transaction,
     /// unless they're wrapped in BEGIN and END
@@ -216,6 +269,26 @@ impl Client {
         }
     }
 
+    /// Executes a statement like [`Client::execute()`], without consuming it,
+    /// so that a statement built once can be executed many times.
+    /// Only the bound parameters are copied, to hand them over to SQLite.
+    pub fn execute_ref(&self, stmt: &Statement) -> Result<ResultSet> {
+        match self.execute_step(&stmt.sql, stmt.args.clone())? {
+            Ok(result) => Ok(ResultSet::from(result)),
+            Err(e) => Err(anyhow::anyhow!(e.message)),
+        }
+    }
+
+    /// Does nothing, local databases are opened by the constructors.
+    pub fn warmup(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Checks that the database is usable, by executing a trivial statement.
+    pub fn ping(&self) -> Result<()> {
+        self.execute("SELECT 1").map(|_| ())
+    }
+
     pub fn execute_in_transaction(&self, _tx_id: u64, stmt: Statement) -> Result<ResultSet> {
         self.execute(stmt)
     }
@@ -228,3 +301,35 @@ impl Client {
         self.execute("ROLLBACK").map(|_| ())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_returning() {
+        let db = Client::in_memory().unwrap();
+        db.execute("CREATE TABLE t(id INTEGER PRIMARY KEY, name TEXT)")
+            .unwrap();
+        let rs = db
+            .execute("INSERT INTO t(name) VALUES ('a'), ('b') RETURNING id, name")
+            .unwrap();
+        assert_eq!(rs.columns, ["id", "name"]);
+        assert_eq!(rs.rows.len(), 2);
+        assert_eq!(rs.rows[1].try_get::<i64>(0).unwrap(), 2);
+        assert_eq!(rs.rows_affected, 2);
+        assert_eq!(rs.last_insert_rowid, Some(2));
+    }
+
+    #[test]
+    fn test_execute_ref_reuses_statement() {
+        let db = Client::in_memory().unwrap();
+        db.execute("CREATE TABLE t(x)").unwrap();
+        let stmt = Statement::with_args("INSERT INTO t VALUES (?)", &[42]);
+        for _ in 0..3 {
+            db.execute_ref(&stmt).unwrap();
+        }
+        let rs = db.execute("SELECT count(*) FROM t WHERE x = 42").unwrap();
+        assert_eq!(rs.rows[0].try_get::<i64>(0).unwrap(), 3);
+    }
+}
@@ -0,0 +1,132 @@
+//! `metrics` counts requests sent by the HTTP client, see [`crate::http::Client::metrics()`].
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Upper bounds of the latency histogram buckets, in milliseconds.
+/// One more bucket counts the requests slower than the last bound.
+pub const LATENCY_BUCKETS_MS: [u64; 8] = [1, 5, 10, 50, 100, 500, 1000, 5000];
+
+/// A finished request, as reported to a [MetricsSink].
+#[derive(Clone, Copy, Debug)]
+pub struct RequestEvent {
+    /// Whether the request was a batch rather than a single statement.
+    pub batch: bool,
+    /// Whether the request succeeded.
+    pub ok: bool,
+    /// Duration of the request, if a clock is available on the target.
+    pub elapsed: Option<Duration>,
+}
+
+/// Receives every finished request, e.g. to forward it to Prometheus.
+pub trait MetricsSink: Send + Sync {
+    fn record(&self, event: &RequestEvent);
+}
+
+/// A point-in-time copy of the counters.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    /// Number of executed statements, excluding batches.
+    pub queries: u64,
+    /// Number of executed batches.
+    pub batches: u64,
+    /// Number of failed requests, both statements and batches.
+    pub errors: u64,
+    /// Request counts per latency bucket, keyed by the bucket's upper bound.
+    /// The last bucket's bound is [Duration::MAX].
+    pub latency_histogram: Vec<(Duration, u64)>,
+}
+
+/// Counters shared by all clones of a client.
+#[derive(Default)]
+pub(crate) struct Metrics {
+    queries: AtomicU64,
+    batches: AtomicU64,
+    errors: AtomicU64,
+    latency_buckets: [AtomicU64; LATENCY_BUCKETS_MS.len() + 1],
+    sink: Option<Arc<dyn MetricsSink>>,
+}
+
+impl std::fmt::Debug for Metrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Metrics")
+            .field("snapshot", &self.snapshot())
+            .field("sink", &self.sink.is_some())
+            .finish()
+    }
+}
+
+impl Metrics {
+    pub(crate) fn with_sink(sink: Arc<dyn MetricsSink>) -> Self {
+        Self {
+            sink: Some(sink),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn record(&self, event: RequestEvent) {
+        let counter = if event.batch {
+            &self.batches
+        } else {
+            &self.queries
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        if !event.ok {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+        if let Some(elapsed) = event.elapsed {
+            let elapsed_ms = elapsed.as_millis() as u64;
+            let bucket = LATENCY_BUCKETS_MS
+                .iter()
+                .position(|&bound| elapsed_ms <= bound)
+                .unwrap_or(LATENCY_BUCKETS_MS.len());
+            self.latency_buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        }
+        if let Some(sink) = &self.sink {
+            sink.record(&event);
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> MetricsSnapshot {
+        let bounds = LATENCY_BUCKETS_MS
+            .iter()
+            .map(|&ms| Duration::from_millis(ms))
+            .chain(std::iter::once(Duration::MAX));
+        MetricsSnapshot {
+            queries: self.queries.load(Ordering::Relaxed),
+            batches: self.batches.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            latency_histogram: bounds
+                .zip(self.latency_buckets.iter())
+                .map(|(bound, count)| (bound, count.load(Ordering::Relaxed)))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_snapshot() {
+        let metrics = Metrics::default();
+        metrics.record(RequestEvent {
+            batch: false,
+            ok: true,
+            elapsed: Some(Duration::from_millis(3)),
+        });
+        metrics.record(RequestEvent {
+            batch: true,
+            ok: false,
+            elapsed: Some(Duration::from_secs(60)),
+        });
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.queries, 1);
+        assert_eq!(snapshot.batches, 1);
+        assert_eq!(snapshot.errors, 1);
+        assert_eq!(snapshot.latency_histogram[1], (Duration::from_millis(5), 1));
+        assert_eq!(snapshot.latency_histogram.last(), Some(&(Duration::MAX, 1)));
+    }
+}
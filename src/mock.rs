@@ -0,0 +1,261 @@
+//! `MockClient` is an in-process backend answering with canned results,
+//! for unit-testing code which talks to the database without running sqld.
+
+use anyhow::Result;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use crate::batch::Condition;
+use crate::proto::{self, pipeline, Col, StmtResult};
+use crate::ResultSet;
+
+#[derive(Clone, Debug)]
+enum MockResponse {
+    Rows(StmtResult),
+    Error(String),
+}
+
+#[derive(Clone, Debug)]
+struct Expectation {
+    sql: String,
+    response: MockResponse,
+}
+
+/// A backend which answers statements with programmed responses.
+///
+/// Each statement is matched against the expectations by its SQL text, with
+/// surrounding whitespace ignored; the first matching expectation answers it,
+/// and stays in place for further statements. Statements which match no expectation
+/// fail, which includes the BEGIN/COMMIT/ROLLBACK of transactions unless expected.
+///
+/// # Examples
+///
+/// ```
+/// # async fn run() {
+/// use libsql_client::mock::MockClient;
+/// use libsql_client::{Client, ResultSet};
+///
+/// let mock = MockClient::new();
+/// mock.expect("SELECT 1").returns(ResultSet {
+///     columns: vec!["1".to_string()],
+///     rows: vec![],
+///     rows_affected: 0,
+///     last_insert_rowid: None,
+/// });
+/// mock.expect("DROP TABLE users").fails("table users is protected");
+///
+/// let db = Client::mock(mock);
+/// assert_eq!(db.execute("SELECT 1").await.unwrap().columns, ["1"]);
+/// assert!(db.execute("DROP TABLE users").await.is_err());
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MockClient {
+    expectations: Arc<Mutex<Vec<Expectation>>>,
+    // HTTP statuses with which the next requests fail, in order
+    failures: Arc<Mutex<VecDeque<u16>>>,
+}
+
+/// An expectation being programmed, see [`MockClient::expect()`].
+pub struct ExpectationBuilder<'a> {
+    client: &'a MockClient,
+    sql: String,
+}
+
+impl ExpectationBuilder<'_> {
+    /// Answers the statement with the given result set.
+    pub fn returns(self, result_set: ResultSet) {
+        let result = StmtResult {
+            cols: result_set
+                .columns
+                .into_iter()
+                .map(|name| Col { name: Some(name) })
+                .collect(),
+            rows: result_set.rows.into_iter().map(|row| row.values).collect(),
+            affected_row_count: result_set.rows_affected,
+            last_insert_rowid: result_set.last_insert_rowid,
+        };
+        self.client.push(self.sql, MockResponse::Rows(result));
+    }
+
+    /// Answers the statement with an error carrying the given message.
+    pub fn fails(self, message: impl Into<String>) {
+        self.client
+            .push(self.sql, MockResponse::Error(message.into()));
+    }
+}
+
+impl MockClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts programming the response to a statement.
+    pub fn expect(&self, sql: impl Into<String>) -> ExpectationBuilder<'_> {
+        ExpectationBuilder {
+            client: self,
+            sql: sql.into().trim().to_string(),
+        }
+    }
+
+    /// Makes the next `count` requests fail with the given HTTP status,
+    /// e.g. 503 to exercise retries.
+    pub fn fail_next_requests(&self, status: u16, count: usize) {
+        self.failures
+            .lock()
+            .unwrap()
+            .extend(std::iter::repeat(status).take(count));
+    }
+
+    fn push(&self, sql: String, response: MockResponse) {
+        self.expectations
+            .lock()
+            .unwrap()
+            .push(Expectation { sql, response });
+    }
+
+    fn respond(&self, sql: &str) -> MockResponse {
+        let sql = sql.trim();
+        self.expectations
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|e| e.sql == sql)
+            .map(|e| e.response.clone())
+            .unwrap_or_else(|| MockResponse::Error(format!("Unexpected statement: {sql}")))
+    }
+
+    pub async fn send(
+        &self,
+        _url: String,
+        _auth: String,
+        _headers: Vec<(String, String)>,
+        body: String,
+    ) -> Result<String> {
+        if let Some(status) = self.failures.lock().unwrap().pop_front() {
+            return Err(crate::Error::Http {
+                status,
+                message: "failure programmed in the mock backend".to_string(),
+            }
+            .into());
+        }
+        let msg: pipeline::ClientMsg = serde_json::from_str(&body)?;
+        #[allow(unreachable_patterns)]
+        let results = msg
+            .requests
+            .into_iter()
+            .map(|request| match request {
+                pipeline::StreamRequest::Execute(req) => match self.respond(&req.stmt.sql) {
+                    MockResponse::Rows(result) => ok(pipeline::StreamResponse::Execute(
+                        pipeline::StreamExecuteResult { result },
+                    )),
+                    MockResponse::Error(message) => error(message),
+                },
+                pipeline::StreamRequest::Batch(req) => {
+                    let mut result = proto::BatchResult {
+                        step_results: vec![],
+                        step_errors: vec![],
+                    };
+                    // Steps whose conditions don't hold are skipped, like sqld does
+                    let mut outcomes = vec![];
+                    for step in req.batch.steps {
+                        if step
+                            .condition
+                            .is_some_and(|cond| !Condition::from_proto(&cond).eval(&outcomes))
+                        {
+                            result.step_results.push(None);
+                            result.step_errors.push(None);
+                            outcomes.push(None);
+                            continue;
+                        }
+                        match self.respond(&step.stmt.sql) {
+                            MockResponse::Rows(rows) => {
+                                result.step_results.push(Some(rows));
+                                result.step_errors.push(None);
+                                outcomes.push(Some(true));
+                            }
+                            MockResponse::Error(message) => {
+                                result.step_results.push(None);
+                                result.step_errors.push(Some(proto::Error { message }));
+                                outcomes.push(Some(false));
+                            }
+                        }
+                    }
+                    ok(pipeline::StreamResponse::Batch(
+                        pipeline::StreamBatchResult { result },
+                    ))
+                }
+                pipeline::StreamRequest::Close => ok(pipeline::StreamResponse::Close),
+                _ => error("Request not supported by the mock backend".to_string()),
+            })
+            .collect();
+        let response = pipeline::ServerMsg {
+            baton: Some("mock".to_string()),
+            base_url: None,
+            results,
+        };
+        Ok(serde_json::to_string(&response)?)
+    }
+
+    /// The mock only emulates the pipeline endpoint, so other endpoints are not found.
+    pub async fn get(
+        &self,
+        url: String,
+        _auth: String,
+        _headers: Vec<(String, String)>,
+    ) -> Result<String> {
+        Err(crate::Error::Http {
+            status: 404,
+            message: format!("{url} is not emulated by the mock backend"),
+        }
+        .into())
+    }
+}
+
+fn ok(response: pipeline::StreamResponse) -> pipeline::Response {
+    pipeline::Response::Ok(pipeline::StreamResponseOk { response })
+}
+
+fn error(message: String) -> pipeline::Response {
+    pipeline::Response::Error(pipeline::StreamResponseError {
+        error: proto::Error { message },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::batch::BatchBuilder;
+
+    #[test]
+    fn test_batch_conditions() {
+        let mock = MockClient::new();
+        let empty = || ResultSet {
+            columns: vec![],
+            rows: vec![],
+            rows_affected: 0,
+            last_insert_rowid: None,
+        };
+        mock.expect("INSERT INTO users VALUES ('ann')")
+            .fails("UNIQUE constraint failed: users.name");
+        mock.expect("INSERT INTO log VALUES ('ann signed up')")
+            .returns(empty());
+        mock.expect("INSERT INTO log VALUES ('ann exists')")
+            .returns(empty());
+        let db = crate::Client::mock(mock);
+        let batch = BatchBuilder::new()
+            .step("INSERT INTO users VALUES ('ann')")
+            .step("INSERT INTO log VALUES ('ann signed up')")
+            .when(Condition::Ok(0))
+            .step("INSERT INTO log VALUES ('ann exists')")
+            .when(Condition::Error(0))
+            // Refers to a skipped step, which neither succeeded nor failed
+            .step("INSERT INTO log VALUES ('ann signed up')")
+            .when(Condition::Or(vec![Condition::Ok(1), Condition::Error(1)]));
+        let result = futures::executor::block_on(db.execute_batch(batch)).unwrap();
+        assert!(result.step_errors[0].is_some());
+        assert!(result.step_results[1].is_none() && result.step_errors[1].is_none());
+        assert!(result.step_results[2].is_some());
+        assert!(result.step_results[3].is_none() && result.step_errors[3].is_none());
+    }
+}
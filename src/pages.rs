@@ -0,0 +1,141 @@
+//! `Pages` fetches the results of a query in fixed-size chunks,
+//! by appending `LIMIT` and `OFFSET` clauses to it.
+
+use anyhow::Result;
+use fallible_iterator::FallibleIterator;
+use futures::stream::Stream;
+use sqlite3_parser::ast::{Cmd, Stmt};
+use sqlite3_parser::lexer::sql::Parser;
+
+use crate::{Client, ResultSet, Statement};
+
+/// Successive pages of a query's results. See [`Client::query_paged()`].
+pub struct Pages<'a> {
+    client: &'a Client,
+    stmt: Statement,
+    page_size: u64,
+    offset: u64,
+    done: bool,
+}
+
+impl<'a> Pages<'a> {
+    /// Fetches the next page. Returns `None` once a page shorter than
+    /// the page size was returned, or after an error.
+    pub async fn next_page(&mut self) -> Option<Result<ResultSet>> {
+        if self.done {
+            return None;
+        }
+        let stmt = Statement {
+            // The newline keeps the clause out of a trailing line comment
+            sql: format!(
+                "{}\nLIMIT {} OFFSET {}",
+                self.stmt.sql, self.page_size, self.offset
+            ),
+            args: self.stmt.args.clone(),
+        };
+        let page = self.client.execute(stmt).await;
+        match &page {
+            Ok(rs) => {
+                self.offset += rs.rows.len() as u64;
+                self.done = (rs.rows.len() as u64) < self.page_size;
+            }
+            Err(_) => self.done = true,
+        }
+        Some(page)
+    }
+
+    /// Turns the pages into a [Stream] of result sets.
+    pub fn into_stream(self) -> impl Stream<Item = Result<ResultSet>> + 'a {
+        futures::stream::unfold(self, |mut pages| async move {
+            pages.next_page().await.map(|page| (page, pages))
+        })
+    }
+}
+
+// Checks that the statement is a single SELECT without a LIMIT clause,
+// and strips the trailing semicolon and comments so that more clauses can be appended.
+fn pageable_sql(sql: &str) -> Result<String> {
+    let mut parser = Parser::new(sql.as_bytes());
+    match parser.next() {
+        Ok(Some(Cmd::Stmt(Stmt::Select(select)))) => {
+            if select.limit.is_some() {
+                anyhow::bail!("Statement already has a LIMIT clause and cannot be paged: {sql}");
+            }
+        }
+        Ok(Some(_)) => anyhow::bail!("Only SELECT statements can be paged: {sql}"),
+        Ok(None) => anyhow::bail!("Cannot page an empty statement"),
+        Err(e) => anyhow::bail!("Failed to parse statement {sql}: {e}"),
+    }
+    if !matches!(parser.next(), Ok(None)) {
+        anyhow::bail!("Only a single statement can be paged: {sql}");
+    }
+    match crate::script::split_statements(sql).as_slice() {
+        [query] => Ok(query.to_string()),
+        _ => anyhow::bail!("Only a single statement can be paged: {sql}"),
+    }
+}
+
+impl Client {
+    /// Runs a SELECT statement page by page, appending `LIMIT` and `OFFSET` clauses to it.
+    /// Pages are fetched until one comes back with fewer than `page_size` rows.
+    ///
+    /// The statement should have an ORDER BY clause, otherwise the order of rows
+    /// is not guaranteed to be the same across pages. Fails if the statement is not
+    /// a SELECT or already has a LIMIT clause.
+    ///
+    /// # Arguments
+    /// * `stmt` - SELECT statement
+    /// * `page_size` - maximum number of rows in a page
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # async fn run() {
+    /// let db = libsql_client::Client::in_memory().unwrap();
+    /// # db.execute("create table foo(bar integer)").await.unwrap();
+    /// # db.execute("insert into foo values (1), (2), (3)").await.unwrap();
+    /// let mut pages = db.query_paged("select * from foo order by bar", 2).unwrap();
+    /// while let Some(page) = pages.next_page().await {
+    ///     let page = page.unwrap();
+    ///     assert!(page.rows.len() <= 2);
+    /// }
+    /// # }
+    /// ```
+    pub fn query_paged(&self, stmt: impl Into<Statement>, page_size: u64) -> Result<Pages> {
+        if page_size == 0 {
+            anyhow::bail!("Page size must be positive");
+        }
+        let stmt: Statement = stmt.into();
+        let stmt = Statement {
+            sql: pageable_sql(&stmt.sql)?,
+            args: stmt.args,
+        };
+        Ok(Pages {
+            client: self,
+            stmt,
+            page_size,
+            offset: 0,
+            done: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pageable_sql() {
+        assert_eq!(
+            pageable_sql("SELECT * FROM t ORDER BY id;  ").unwrap(),
+            "SELECT * FROM t ORDER BY id"
+        );
+        assert_eq!(
+            pageable_sql("SELECT x FROM t; -- all").unwrap(),
+            "SELECT x FROM t"
+        );
+        assert!(pageable_sql("SELECT * FROM t LIMIT 10").is_err());
+        assert!(pageable_sql("DELETE FROM t").is_err());
+        assert!(pageable_sql("SELECT 1; SELECT 2").is_err());
+    }
+}
@@ -2,11 +2,126 @@
 
 #[cfg(feature = "hrana_backend")]
 pub use hrana_client::proto::{
-    pipeline, Batch, BatchReq, BatchResp, BatchResult, ClientMsg, Col, Error, ExecuteReq,
-    ExecuteResp, OpenStreamReq, Request, Response, ServerMsg, Stmt, StmtResult, Value,
+    pipeline, Batch, BatchCond, BatchReq, BatchResp, BatchResult, ClientMsg, Col, Error,
+    ExecuteReq, ExecuteResp, OpenStreamReq, Request, Response, ServerMsg, Stmt, StmtResult, Value,
 };
 #[cfg(not(feature = "hrana_backend"))]
 pub use hrana_client_proto::{
-    pipeline, Batch, BatchReq, BatchResp, BatchResult, ClientMsg, Col, Error, ExecuteReq,
-    ExecuteResp, OpenStreamReq, Request, Response, ServerMsg, Stmt, StmtResult, Value,
+    pipeline, Batch, BatchCond, BatchReq, BatchResp, BatchResult, ClientMsg, Col, Error,
+    ExecuteReq, ExecuteResp, OpenStreamReq, Request, Response, ServerMsg, Stmt, StmtResult, Value,
 };
+
+/// Version of the Hrana protocol spoken over HTTP.
+///
+/// Newer versions are supersets of the older ones, so requests built by this crate
+/// are valid for every version; the version only selects the pipeline endpoint.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ProtocolVersion {
+    #[default]
+    V2,
+    V3,
+}
+
+impl ProtocolVersion {
+    /// Path of the pipeline endpoint for this version, relative to the database URL.
+    pub fn pipeline_path(&self) -> &'static str {
+        match self {
+            ProtocolVersion::V2 => "v2/pipeline",
+            ProtocolVersion::V3 => "v3/pipeline",
+        }
+    }
+}
+
+impl std::fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProtocolVersion::V2 => write!(f, "hrana v2"),
+            ProtocolVersion::V3 => write!(f, "hrana v3"),
+        }
+    }
+}
+
+/// Metadata of a statement, obtained without executing it, see [crate::Client::describe].
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+pub struct StmtDescription {
+    /// Parameters of the statement, in the order of their positions.
+    pub params: Vec<ParamDescription>,
+    /// Columns of the statement's result.
+    pub cols: Vec<ColDescription>,
+    /// Whether the statement is an `EXPLAIN`.
+    pub is_explain: bool,
+    /// Whether the statement leaves the database unchanged.
+    pub is_readonly: bool,
+}
+
+impl StmtDescription {
+    /// Returns the number of parameters of the statement.
+    pub fn param_count(&self) -> usize {
+        self.params.len()
+    }
+}
+
+/// Parameter of a described statement.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+pub struct ParamDescription {
+    /// Name of the parameter including its prefix, e.g. `:id`, or `None` for `?`.
+    pub name: Option<String>,
+}
+
+/// Result column of a described statement.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+pub struct ColDescription {
+    pub name: String,
+    /// Declared type of the column, if the column comes straight from a table.
+    pub decltype: Option<String>,
+}
+
+// Pipeline response to a `describe` request, which the protocol crate doesn't know about.
+// Only the parts needed to get the description out are parsed.
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct DescribeServerMsg {
+    pub results: Vec<DescribeStreamResult>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum DescribeStreamResult {
+    Ok { response: DescribeStreamResponse },
+    Error { error: Error },
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum DescribeStreamResponse {
+    Describe {
+        result: StmtDescription,
+    },
+    #[serde(other)]
+    Other,
+}
+
+// Pipeline response to requests storing SQL texts on the stream, see crate::sql_cache,
+// which the protocol crate doesn't know about either.
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct StoredSqlServerMsg {
+    pub baton: Option<String>,
+    pub base_url: Option<String>,
+    pub results: Vec<StoredSqlStreamResult>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum StoredSqlStreamResult {
+    Ok { response: StoredSqlStreamResponse },
+    Error { error: Error },
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum StoredSqlStreamResponse {
+    Execute {
+        result: StmtResult,
+    },
+    #[serde(other)]
+    Other,
+}
@@ -0,0 +1,128 @@
+//! `query` contains helpers for statements expected to return at most one row.
+
+use anyhow::Result;
+
+use crate::{Client, Error, Row, Statement, Value};
+
+impl Client {
+    /// Executes a statement expected to return at most one row, e.g. a lookup by primary key.
+    /// Returns `None` if no rows were returned, and fails with [Error::MultipleRows]
+    /// if more than one was.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # async fn run() {
+    /// let db = libsql_client::Client::in_memory().unwrap();
+    /// let row = db.query_one("select 1 as id").await.unwrap().unwrap();
+    /// assert_eq!(row.try_get::<usize>(0).unwrap(), 1);
+    /// assert!(db.query_one("select 1 where false").await.unwrap().is_none());
+    /// # }
+    /// ```
+    pub async fn query_one(&self, stmt: impl Into<Statement> + Send) -> Result<Option<Row>> {
+        let mut rows = self.execute(stmt).await?.rows;
+        match rows.len() {
+            0 => Ok(None),
+            1 => Ok(rows.pop()),
+            count => Err(Error::MultipleRows { count }.into()),
+        }
+    }
+
+    /// Executes a statement expected to return at most one row, and returns
+    /// its first column converted to `T`, e.g. for `SELECT COUNT(*)` queries.
+    /// Returns `None` if no rows were returned, and fails with [Error::MultipleRows]
+    /// if more than one was.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # async fn run() {
+    /// let db = libsql_client::Client::in_memory().unwrap();
+    /// let count: usize = db.query_scalar("select 42").await.unwrap().unwrap();
+    /// assert_eq!(count, 42);
+    /// # }
+    /// ```
+    pub async fn query_scalar<T>(&self, stmt: impl Into<Statement> + Send) -> Result<Option<T>>
+    where
+        T: for<'a> TryFrom<&'a Value, Error = String>,
+    {
+        match self.query_one(stmt).await? {
+            Some(row) => {
+                let value = row
+                    .values
+                    .first()
+                    .ok_or_else(|| anyhow::anyhow!("Query returned no columns"))?;
+                T::try_from(value).map(Some).map_err(|e| anyhow::anyhow!(e))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the number of rows a query returns, e.g. to size an export up front,
+    /// by executing `SELECT COUNT(*) FROM (<query>)`.
+    ///
+    /// The count isn't free: the server runs the query's joins and filters, only
+    /// without sending the rows back. `LIMIT` and `OFFSET` clauses are taken into account.
+    /// Fails with [Error::Misuse] if the statement isn't a single query;
+    /// a trailing semicolon is fine.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # async fn run() {
+    /// let db = libsql_client::Client::in_memory().unwrap();
+    /// let sql = "WITH RECURSIVE n(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM n LIMIT 10)
+    ///            SELECT x FROM n WHERE x > 3 ORDER BY x DESC LIMIT 5;";
+    /// assert_eq!(db.estimate_rows(sql).await.unwrap(), 5);
+    /// # }
+    /// ```
+    pub async fn estimate_rows(&self, stmt: impl Into<Statement> + Send) -> Result<usize> {
+        let Statement { sql, args } = stmt.into();
+        let query = match crate::script::split_statements(&sql).as_slice() {
+            [query] => query.to_string(),
+            [] => return Err(Error::Misuse("empty statement".to_string()).into()),
+            _ => {
+                return Err(
+                    Error::Misuse(format!("Only a single query can be counted: {sql}")).into(),
+                )
+            }
+        };
+        // The newline ends a trailing `--` comment, which would hide the parenthesis
+        let count = self
+            .query_scalar(Statement {
+                sql: format!("SELECT COUNT(*) FROM ({query}\n)"),
+                args,
+            })
+            .await?;
+        count.ok_or_else(|| anyhow::anyhow!("COUNT(*) returned no rows"))
+    }
+}
+
+#[cfg(all(test, feature = "local_backend"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_rows() {
+        let db = Client::in_memory().unwrap();
+        let count = |stmt: Statement| futures::executor::block_on(db.estimate_rows(stmt));
+        futures::executor::block_on(
+            db.execute_script("CREATE TABLE t(x); INSERT INTO t VALUES (1), (2), (3), (4);"),
+        )
+        .unwrap();
+        assert_eq!(
+            count(Statement::with_args(
+                "SELECT x FROM t WHERE x > ? -- bigger ones",
+                &[1]
+            ))
+            .unwrap(),
+            3
+        );
+        assert_eq!(
+            count("SELECT x FROM t ORDER BY x LIMIT 2 OFFSET 3;".into()).unwrap(),
+            1
+        );
+        let e = count("SELECT 1; SELECT 2".into()).unwrap_err();
+        assert!(matches!(e.downcast_ref::<Error>(), Some(Error::Misuse(_))));
+    }
+}
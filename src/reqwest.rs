@@ -1,6 +1,9 @@
 use anyhow::Result;
 
-use crate::proto::pipeline;
+use crate::{Config, Error};
+
+#[cfg(not(target_arch = "wasm32"))]
+const PEM_CERTIFICATE_HEADER: &[u8] = b"-----BEGIN CERTIFICATE-----";
 
 #[derive(Clone, Debug)]
 pub struct HttpClient {
@@ -8,38 +11,169 @@ pub struct HttpClient {
 }
 
 impl HttpClient {
+    /// Creates a client which negotiates gzip, brotli and deflate response compression
+    /// and transparently decompresses responses.
     pub fn new() -> Self {
         Self {
             inner: reqwest::Client::new(),
         }
     }
 
+    /// Creates a client which doesn't send `Accept-Encoding`, for environments where
+    /// a proxy mishandles compressed responses.
+    pub fn without_compression() -> Self {
+        let inner = reqwest::Client::builder()
+            .no_gzip()
+            .no_brotli()
+            .no_deflate()
+            .build()
+            .expect("Failed to build the HTTP client");
+        Self { inner }
+    }
+
+    /// Creates a client with the timeouts, TLS and connection pool settings of the [Config].
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let mut builder = reqwest::Client::builder();
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(timeout) = config.options.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(timeout) = config.options.request_timeout {
+            builder = builder.timeout(timeout);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if config.options.http2_prior_knowledge {
+            builder = builder.http2_prior_knowledge();
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(max) = config.options.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(interval) = config.options.http2_keep_alive_interval {
+            builder = builder
+                .http2_keep_alive_interval(interval)
+                .http2_keep_alive_while_idle(true);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        for pem in &config.options.root_certificates {
+            // With rustls, malformed PEM would be skipped silently when building the client
+            if !pem
+                .windows(PEM_CERTIFICATE_HEADER.len())
+                .any(|window| window == PEM_CERTIFICATE_HEADER)
+            {
+                anyhow::bail!("Invalid root certificate: no PEM-encoded certificate found");
+            }
+            let certificate = reqwest::Certificate::from_pem(pem)
+                .map_err(|e| anyhow::anyhow!("Invalid root certificate: {e}"))?;
+            builder = builder.add_root_certificate(certificate);
+        }
+        if config.options.danger_accept_invalid_certs {
+            tracing::warn!(
+                "TLS certificate verification is disabled for {}",
+                config.url
+            );
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+        Ok(Self {
+            inner: builder.build()?,
+        })
+    }
+
     pub async fn send(
         &self,
         url: String,
         auth: String,
+        headers: Vec<(String, String)>,
+        body: String,
+    ) -> Result<String> {
+        self.send_limited(url, auth, headers, body, usize::MAX)
+            .await
+    }
+
+    // Sends the request, and stops reading the response once it exceeds `max_bytes`
+    pub(crate) async fn send_limited(
+        &self,
+        url: String,
+        auth: String,
+        headers: Vec<(String, String)>,
         body: String,
-    ) -> Result<pipeline::ServerMsg> {
-        let response = self
+        max_bytes: usize,
+    ) -> Result<String> {
+        let mut request = self
             .inner
             .post(url)
             .body(body)
-            .header("Authorization", auth)
-            .send()
-            .await?;
+            .header("Authorization", auth);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        let response = request.send().await?;
+        if response.status() != reqwest::StatusCode::OK {
+            let status = response.status().as_u16();
+            let message = response.text().await.unwrap_or_default();
+            return Err(Error::from_status(status, message).into());
+        }
+        read_limited(response, max_bytes).await
+    }
+
+    pub async fn get(
+        &self,
+        url: String,
+        auth: String,
+        headers: Vec<(String, String)>,
+    ) -> Result<String> {
+        let mut request = self.inner.get(url).header("Authorization", auth);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        let response = request.send().await?;
         if response.status() != reqwest::StatusCode::OK {
-            let status = response.status();
-            let txt = response.text().await.unwrap_or_default();
-            anyhow::bail!("{status}: {txt}");
+            let status = response.status().as_u16();
+            let message = response.text().await.unwrap_or_default();
+            return Err(Error::from_status(status, message).into());
         }
-        let resp: String = response.text().await?;
-        let response: pipeline::ServerMsg = serde_json::from_str(&resp)?;
-        Ok(response)
+        Ok(response.text().await?)
     }
 }
 
+// Reads the response body, failing with Error::ResponseTooLarge as soon as it exceeds `max_bytes`.
+// Content-Length is only a hint: it's missing from compressed and chunked responses.
+async fn read_limited(mut response: reqwest::Response, max_bytes: usize) -> Result<String> {
+    let too_large = || Error::ResponseTooLarge { limit: max_bytes };
+    if response
+        .content_length()
+        .is_some_and(|len| len > max_bytes as u64)
+    {
+        return Err(too_large().into());
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if body.len() + chunk.len() > max_bytes {
+            return Err(too_large().into());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(String::from_utf8(body)?)
+}
+
 impl Default for HttpClient {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_root_certificate() {
+        let config = Config::new("https://db.example.com")
+            .unwrap()
+            .add_root_certificate("not a certificate");
+        let e = HttpClient::from_config(&config).unwrap_err();
+        assert!(e.to_string().starts_with("Invalid root certificate"));
+    }
+}
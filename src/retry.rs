@@ -0,0 +1,156 @@
+//! `RetryPolicy` decides whether and when failed HTTP requests are retried.
+
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+
+/// Retries of HTTP requests which the server rejected without processing them:
+/// responses with status 429 (Too Many Requests) or 503 (Service Unavailable).
+/// Other failures are not retried, since the statements might have been executed.
+/// Retrying 502 and 504 can be enabled with [RetryPolicy::with_gateway_retries].
+///
+/// The delay before the `n`-th retry is `initial_backoff * 2^(n-1)`, capped at `max_backoff`.
+/// Backoff requires a backend which runs on Tokio, reqwest or unix; other backends retry
+/// immediately.
+///
+/// # Examples
+///
+/// ```
+/// use libsql_client::RetryPolicy;
+/// use std::time::Duration;
+///
+/// let policy = RetryPolicy::new(3).with_initial_backoff(Duration::from_millis(50));
+/// assert_eq!(policy.backoff(2), Duration::from_millis(100));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// How many times a request is retried after its first attempt.
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Whether responses with status 502 (Bad Gateway) or 504 (Gateway Timeout)
+    /// are retried, see [RetryPolicy::with_gateway_retries]. Off by default.
+    pub retry_gateway_errors: bool,
+}
+
+impl Default for RetryPolicy {
+    /// No retries.
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl RetryPolicy {
+    /// Creates a policy retrying up to `max_retries` times, with backoff starting at 100ms.
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            retry_gateway_errors: false,
+        }
+    }
+
+    pub fn with_initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    pub fn with_max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Also retries requests failing with status 502 (Bad Gateway) or 504 (Gateway Timeout).
+    ///
+    /// **A proxy may give up on a request which sqld executes anyway**, so a retried
+    /// pipeline can be applied twice. Only enable this if all statements sent through
+    /// the client are idempotent, e.g. reads or upserts.
+    pub fn with_gateway_retries(mut self, enabled: bool) -> Self {
+        self.retry_gateway_errors = enabled;
+        self
+    }
+
+    /// Returns the delay before the given retry, counted from 1.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    // Whether the error means that the server didn't process the request,
+    // or that it may have, but the policy allows retrying it anyway
+    pub(crate) fn is_retryable(&self, e: &anyhow::Error) -> bool {
+        match e.downcast_ref::<crate::Error>() {
+            Some(crate::Error::Http {
+                status: 429 | 503, ..
+            }) => true,
+            Some(crate::Error::Http {
+                status: 502 | 504, ..
+            }) => self.retry_gateway_errors,
+            _ => false,
+        }
+    }
+}
+
+/// Waits out the delays between retries. Tests can substitute one which advances
+/// a fake clock instead of sleeping, see [`crate::http::Client::with_sleeper()`].
+pub trait Sleeper: std::fmt::Debug + Send + Sync {
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+/// The default [Sleeper]. Only the reqwest and unix backends are known to run on Tokio,
+/// so with other backends it returns immediately.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TokioSleeper;
+
+impl Sleeper for TokioSleeper {
+    fn sleep(&self, _duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(async move {
+            #[cfg(all(
+                any(feature = "reqwest_backend", feature = "unix_backend"),
+                not(target_arch = "wasm32")
+            ))]
+            tokio::time::sleep(_duration).await;
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_is_capped() {
+        let policy = RetryPolicy::new(10)
+            .with_initial_backoff(Duration::from_millis(100))
+            .with_max_backoff(Duration::from_secs(1));
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(5), Duration::from_secs(1));
+        assert_eq!(policy.backoff(100), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_retryable_statuses() {
+        let http = |status| -> anyhow::Error {
+            crate::Error::Http {
+                status,
+                message: String::new(),
+            }
+            .into()
+        };
+        let policy = RetryPolicy::new(3);
+        assert!(policy.is_retryable(&http(503)));
+        assert!(policy.is_retryable(&http(429)));
+        assert!(!policy.is_retryable(&http(500)));
+        assert!(!policy.is_retryable(&anyhow::anyhow!("connection reset")));
+        // The server may have executed requests which a gateway gave up on
+        assert!(!policy.is_retryable(&http(504)));
+        assert!(!policy.is_retryable(&http(502)));
+        let policy = policy.with_gateway_retries(true);
+        assert!(policy.is_retryable(&http(504)));
+        assert!(policy.is_retryable(&http(502)));
+    }
+}
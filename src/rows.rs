@@ -0,0 +1,97 @@
+//! `Rows` iterates over the rows of a [ResultSet], giving typed access
+//! to their cells by index or by column name.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::{utils, ResultSet, Row, Value};
+
+/// Iterator over the rows of a [ResultSet], created by iterating over `&ResultSet`.
+///
+/// The mapping from column names to indexes is computed once and shared by all rows,
+/// so looking a cell up by name doesn't scan the columns. If several columns share a name,
+/// the leftmost one keeps it and the others get a suffix with their occurrence, e.g. `id:1`.
+///
+/// # Examples
+/// ```
+/// let db = libsql_client::SyncClient::in_memory().unwrap();
+/// db.execute("create table example(num integer, str text)").unwrap();
+/// db.execute("insert into example (num, str) values (0, 'zero')").unwrap();
+/// let rs = db.execute("select * from example").unwrap();
+/// for row in &rs {
+///     let num: usize = row.get(0).unwrap();
+///     let text: &str = row.get_by_name("str").unwrap();
+/// }
+/// ```
+pub struct Rows<'a> {
+    rows: std::slice::Iter<'a, Row>,
+    column_indexes: Arc<HashMap<String, usize>>,
+}
+
+impl<'a> Iterator for Rows<'a> {
+    type Item = RowRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.rows.next().map(|row| RowRef {
+            row,
+            column_indexes: self.column_indexes.clone(),
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.rows.size_hint()
+    }
+}
+
+impl<'a> IntoIterator for &'a ResultSet {
+    type Item = RowRef<'a>;
+    type IntoIter = Rows<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let column_indexes = utils::disambiguate_columns(&self.columns)
+            .into_iter()
+            .enumerate()
+            .map(|(i, column)| (column, i))
+            .collect();
+        Rows {
+            rows: self.rows.iter(),
+            column_indexes: Arc::new(column_indexes),
+        }
+    }
+}
+
+/// A row of a [ResultSet], which knows the names of its columns.
+pub struct RowRef<'a> {
+    row: &'a Row,
+    column_indexes: Arc<HashMap<String, usize>>,
+}
+
+impl<'a> RowRef<'a> {
+    /// Try to get a value by index from this row and convert it to the desired type
+    ///
+    /// Will return an error if the index is invalid or if the value cannot be converted to the
+    /// desired type
+    pub fn get<V: TryFrom<&'a Value, Error = String>>(&self, index: usize) -> anyhow::Result<V> {
+        self.row.try_get(index)
+    }
+
+    /// Try to get a value given a column name from this row and convert it to the desired type
+    ///
+    /// Will return an error if the column name is invalid or if the value cannot be converted to the
+    /// desired type
+    pub fn get_by_name<V: TryFrom<&'a Value, Error = String>>(
+        &self,
+        column: &str,
+    ) -> anyhow::Result<V> {
+        let index = *self
+            .column_indexes
+            .get(column)
+            .ok_or_else(|| anyhow::anyhow!("column `{}` not present", column))?;
+        self.row.try_get(index)
+    }
+
+    /// Returns the underlying row.
+    pub fn row(&self) -> &'a Row {
+        self.row
+    }
+}
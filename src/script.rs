@@ -0,0 +1,326 @@
+//! `script` runs SQL scripts made of multiple statements, e.g. migration files.
+
+use anyhow::Result;
+
+use crate::batch::{BatchBuilder, Condition};
+use crate::{Client, Error, ResultSet, Statement};
+
+/// Splits an SQL script into statements, on semicolons which end a statement.
+///
+/// Semicolons inside string literals, quoted identifiers, comments and
+/// `BEGIN ... END` bodies of triggers don't split the script. The returned
+/// statements don't include the terminating semicolons, and fragments holding
+/// only whitespace and comments are skipped.
+///
+/// # Examples
+/// ```
+/// let stmts = libsql_client::script::split_statements(
+///     "CREATE TABLE t(x); -- create\nINSERT INTO t VALUES ('a;b');",
+/// );
+/// assert_eq!(stmts, ["CREATE TABLE t(x)", "-- create\nINSERT INTO t VALUES ('a;b')"]);
+/// ```
+pub fn split_statements(sql: &str) -> Vec<&str> {
+    let bytes = sql.as_bytes();
+    let mut stmts = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    // Whether the current statement has anything besides whitespace and comments
+    let mut has_tokens = false;
+    // The first keywords of the current statement, to detect CREATE TRIGGER
+    let mut leading_words: Vec<String> = Vec::new();
+    let mut in_trigger_body = false;
+    let mut case_depth = 0usize;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                i = find_from(bytes, i + 2, b"\n").map_or(bytes.len(), |end| end + 1);
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = find_from(bytes, i + 2, b"*/").map_or(bytes.len(), |end| end + 2);
+            }
+            quote @ (b'\'' | b'"' | b'`' | b'[') => {
+                let closing = if quote == b'[' { b']' } else { quote };
+                // A doubled quote is parsed as two adjacent literals, which is fine for splitting
+                i = find_from(bytes, i + 1, &[closing]).map_or(bytes.len(), |end| end + 1);
+                has_tokens = true;
+            }
+            b';' => {
+                if !in_trigger_body {
+                    if has_tokens {
+                        stmts.push(sql[start..i].trim());
+                    }
+                    has_tokens = false;
+                    leading_words.clear();
+                    case_depth = 0;
+                    start = i + 1;
+                }
+                i += 1;
+            }
+            c if c.is_ascii_alphabetic() || c == b'_' => {
+                let word_start = i;
+                while i < bytes.len()
+                    && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_' || bytes[i] == b'$')
+                {
+                    i += 1;
+                }
+                let word = sql[word_start..i].to_ascii_uppercase();
+                has_tokens = true;
+                if leading_words.len() < 3 {
+                    leading_words.push(word.clone());
+                }
+                if is_create_trigger(&leading_words) {
+                    match word.as_str() {
+                        "BEGIN" if !in_trigger_body => in_trigger_body = true,
+                        "CASE" if in_trigger_body => case_depth += 1,
+                        "END" if in_trigger_body && case_depth > 0 => case_depth -= 1,
+                        "END" if in_trigger_body => in_trigger_body = false,
+                        _ => {}
+                    }
+                }
+            }
+            c => {
+                if !c.is_ascii_whitespace() {
+                    has_tokens = true;
+                }
+                i += 1;
+            }
+        }
+    }
+    if has_tokens {
+        stmts.push(sql[start..].trim());
+    }
+    stmts
+}
+
+pub(crate) fn find_from(haystack: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|pos| from + pos)
+}
+
+fn is_create_trigger(words: &[String]) -> bool {
+    match words {
+        [create, trigger, ..] if create == "CREATE" && trigger == "TRIGGER" => true,
+        [create, temp, trigger] if create == "CREATE" && trigger == "TRIGGER" => {
+            temp == "TEMP" || temp == "TEMPORARY"
+        }
+        _ => false,
+    }
+}
+
+impl Client {
+    /// Executes an SQL script made of multiple statements separated by semicolons,
+    /// like the contents of a migration file. See [split_statements] for how
+    /// the script is split.
+    ///
+    /// All statements are sent in a single batch and executed in order, each in its
+    /// own implicit transaction unless the script has explicit `BEGIN` and `COMMIT` statements.
+    /// If a statement fails, the following ones are skipped and the error is [Error::Script],
+    /// holding its index.
+    ///
+    /// # Arguments
+    /// * `sql` - SQL script
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # async fn run() {
+    /// let db = libsql_client::Client::in_memory().unwrap();
+    /// let results = db
+    ///     .execute_script("CREATE TABLE t(x); INSERT INTO t VALUES (1); SELECT * FROM t;")
+    ///     .await
+    ///     .unwrap();
+    /// assert_eq!(results.len(), 3);
+    /// # }
+    /// ```
+    pub async fn execute_script(&self, sql: &str) -> Result<Vec<ResultSet>> {
+        let stmts = split_statements(sql);
+        let count = stmts.len();
+        // Each statement only runs if the previous one succeeded, like in a shell script
+        let mut batch = BatchBuilder::new();
+        for (index, stmt) in stmts.into_iter().enumerate() {
+            batch = batch.step(Statement::new(stmt));
+            if index > 0 {
+                batch = batch.when(Condition::Ok(index - 1));
+            }
+        }
+        let batch_result = self.execute_batch(batch).await?;
+        let mut results = Vec::with_capacity(count);
+        for (index, (result, error)) in batch_result
+            .step_results
+            .into_iter()
+            .zip(batch_result.step_errors)
+            .enumerate()
+        {
+            match (result, error) {
+                (_, Some(error)) => {
+                    return Err(Error::Script {
+                        index,
+                        message: error.message,
+                    }
+                    .into())
+                }
+                (Some(result), None) => results.push(ResultSet::from(result)),
+                (None, None) => {
+                    return Err(Error::Script {
+                        index,
+                        message: "statement was not executed".to_string(),
+                    }
+                    .into())
+                }
+            }
+        }
+        if results.len() != count {
+            anyhow::bail!(
+                "Expected results for {count} statements, got {}",
+                results.len()
+            );
+        }
+        Ok(results)
+    }
+
+    /// Reads an SQL script from a file, e.g. a migration, and executes it
+    /// with [Client::execute_script]. Returns the number of executed statements.
+    ///
+    /// If a statement fails, the error is [Error::ScriptFile], holding the line
+    /// of the file on which the statement starts.
+    ///
+    /// The file is read with blocking I/O, since the client doesn't depend on any
+    /// async runtime. Scripts are usually small, but on a busy executor, read large
+    /// ones yourself, e.g. with `tokio::fs`, and pass them to [Client::execute_script].
+    ///
+    /// Requires the `fs` feature, enabled by default. Not available on wasm targets,
+    /// which have no filesystem.
+    ///
+    /// # Arguments
+    /// * `path` - path of the SQL script
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # async fn run() -> anyhow::Result<()> {
+    /// let db = libsql_client::Client::from_env().await?;
+    /// let count = db.execute_file("migrations/0001_init.sql").await?;
+    /// println!("Executed {count} statements");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(all(feature = "fs", not(target_arch = "wasm32")))]
+    pub async fn execute_file(&self, path: impl AsRef<std::path::Path>) -> Result<usize> {
+        let path = path.as_ref();
+        let sql = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {e}", path.display()))?;
+        match self.execute_script(&sql).await {
+            Ok(results) => Ok(results.len()),
+            Err(e) => match e.downcast::<Error>() {
+                Ok(Error::Script { index, message }) => Err(Error::ScriptFile {
+                    path: path.display().to_string(),
+                    line: statement_line(&sql, index),
+                    index,
+                    message,
+                }
+                .into()),
+                Ok(e) => Err(e.into()),
+                Err(e) => Err(e),
+            },
+        }
+    }
+}
+
+// Returns the 1-based line on which the `index`-th statement of the script starts,
+// skipping the comments which precede it
+#[cfg_attr(any(not(feature = "fs"), target_arch = "wasm32"), allow(dead_code))]
+fn statement_line(sql: &str, index: usize) -> usize {
+    let Some(stmt) = split_statements(sql).get(index).copied() else {
+        return 1;
+    };
+    let mut rest = stmt;
+    loop {
+        rest = rest.trim_start();
+        if let Some(comment) = rest.strip_prefix("--") {
+            rest = comment.split_once('\n').map_or("", |(_, after)| after);
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            rest = comment.split_once("*/").map_or("", |(_, after)| after);
+        } else {
+            break;
+        }
+    }
+    // Statements are slices of the script, so their offsets can be recovered
+    let offset = rest.as_ptr() as usize - sql.as_ptr() as usize;
+    sql[..offset].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_simple() {
+        assert_eq!(
+            split_statements("SELECT 1; SELECT 2;\n"),
+            ["SELECT 1", "SELECT 2"]
+        );
+        assert_eq!(split_statements("SELECT 1"), ["SELECT 1"]);
+    }
+
+    #[test]
+    fn test_split_skips_empty_fragments() {
+        assert!(split_statements(";  ;\n-- nothing here\n/* nor here */").is_empty());
+    }
+
+    #[test]
+    fn test_split_respects_literals_and_comments() {
+        assert_eq!(
+            split_statements(
+                "INSERT INTO \"a;b\" VALUES ('it''s; fine'); /* ; */ SELECT [x;y] -- ;\nFROM t"
+            ),
+            [
+                "INSERT INTO \"a;b\" VALUES ('it''s; fine')",
+                "/* ; */ SELECT [x;y] -- ;\nFROM t"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_respects_trigger_bodies() {
+        let script = "CREATE TEMP TRIGGER tr AFTER INSERT ON t BEGIN \
+                      UPDATE t SET x = CASE WHEN x > 0 THEN 1 ELSE 0 END; \
+                      DELETE FROM u; \
+                      END; PRAGMA foreign_keys = ON;";
+        let stmts = split_statements(script);
+        assert_eq!(stmts.len(), 2);
+        assert!(stmts[0].ends_with("END"));
+        assert_eq!(stmts[1], "PRAGMA foreign_keys = ON");
+    }
+
+    #[test]
+    fn test_statement_line() {
+        let script = "CREATE TABLE t(x);\n\n-- seed\n/* two\nlines */ INSERT INTO t\nVALUES (1);";
+        assert_eq!(statement_line(script, 0), 1);
+        assert_eq!(statement_line(script, 1), 5);
+    }
+
+    #[cfg(all(feature = "local_backend", feature = "fs"))]
+    #[test]
+    fn test_execute_file() {
+        let path = std::env::temp_dir().join(format!("libsql-client-{}.sql", std::process::id()));
+        std::fs::write(
+            &path,
+            "CREATE TABLE t(x);\nINSERT INTO t VALUES (1);\n\nINSERT INTO u VALUES (2);",
+        )
+        .unwrap();
+        let db = Client::in_memory().unwrap();
+        let e = futures::executor::block_on(db.execute_file(&path)).unwrap_err();
+        match e.downcast_ref::<Error>() {
+            Some(Error::ScriptFile { line, index, .. }) => assert_eq!((*line, *index), (4, 2)),
+            _ => panic!("unexpected error: {e}"),
+        }
+        std::fs::write(&path, "INSERT INTO t VALUES (2); SELECT * FROM t;").unwrap();
+        let count = futures::executor::block_on(db.execute_file(&path)).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(count, 2);
+    }
+}
@@ -1,6 +1,6 @@
 use anyhow::Result;
 
-use crate::proto::pipeline;
+use crate::Error;
 
 #[derive(Clone, Debug)]
 pub struct HttpClient;
@@ -14,17 +14,49 @@ impl HttpClient {
         &self,
         url: String,
         auth: String,
+        headers: Vec<(String, String)>,
         body: String,
-    ) -> Result<pipeline::ServerMsg> {
-        let req = http::Request::builder()
+    ) -> Result<String> {
+        let mut builder = http::Request::builder()
             .uri(&url)
-            .header("Authorization", &auth)
+            .header("Authorization", &auth);
+        for (name, value) in headers {
+            builder = builder.header(name, value);
+        }
+        let req = builder
             .method("POST")
             .body(Some(bytes::Bytes::copy_from_slice(body.as_bytes())))?;
 
         let response: http::Response<String> = spin_sdk::http::send(req).await?;
-        let response: pipeline::ServerMsg = serde_json::from_str(&response.into_body())?;
-        Ok(response)
+        if response.status() != http::StatusCode::OK {
+            let status = response.status().as_u16();
+            let message = response.into_body();
+            return Err(Error::from_status(status, message).into());
+        }
+        Ok(response.into_body())
+    }
+
+    pub async fn get(
+        &self,
+        url: String,
+        auth: String,
+        headers: Vec<(String, String)>,
+    ) -> Result<String> {
+        let mut builder = http::Request::builder()
+            .uri(&url)
+            .header("Authorization", &auth);
+        for (name, value) in headers {
+            builder = builder.header(name, value);
+        }
+        let req = builder.method("GET").body(None::<bytes::Bytes>)?;
+
+        let response: http::Response<String> = spin_sdk::http::send(req).await?;
+        if response.status() != http::StatusCode::OK {
+            let status = response.status().as_u16();
+            let message = response.into_body();
+            return Err(Error::from_status(status, message).into());
+        }
+        Ok(response.into_body())
     }
 }
 
@@ -0,0 +1,234 @@
+//! `SqlCache` stores frequently executed SQL texts on the server, so that statements
+//! refer to them by id instead of sending the text again, see [`crate::http::Client::with_sql_cache()`].
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+// Execution counts are kept for at most this many SQL texts per stored text,
+// and are all forgotten once there are more, so that ad-hoc queries can't grow them forever
+const COUNTED_PER_STORED: usize = 16;
+
+/// Counters of SQL texts stored by a client and its clones.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SqlCacheStats {
+    /// Number of statements sent by the id of an SQL text stored earlier.
+    pub hits: u64,
+    /// Number of SQL texts stored on the server.
+    pub stores: u64,
+    /// Number of stored SQL texts closed to make room for others.
+    pub evictions: u64,
+}
+
+/// Decides which SQL texts get stored, shared by all clones of a client.
+#[derive(Debug)]
+pub(crate) struct SqlCache {
+    threshold: u32,
+    capacity: usize,
+    counts: Mutex<HashMap<String, u32>>,
+    hits: AtomicU64,
+    stores: AtomicU64,
+    evictions: AtomicU64,
+}
+
+/// SQL texts stored on one stream with their ids, least recently used first.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct StoredSql {
+    entries: VecDeque<(String, i32)>,
+    // Ids are never reused within a stream, even after the SQL text is closed
+    next_id: i32,
+}
+
+impl StoredSql {
+    // Forgets the stored SQL texts, e.g. when it's unknown whether storing them succeeded.
+    // The server keeps them until the stream is closed, but they are never referred to again.
+    pub(crate) fn forget(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// How a statement refers to its SQL text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum SqlRef {
+    /// The SQL text is sent with the statement.
+    Text,
+    /// The SQL text was stored earlier under this id.
+    Stored(i32),
+    /// The SQL text is stored under `sql_id` before the statement is executed,
+    /// after closing the least recently used text stored under `evict`, if any.
+    Store { sql_id: i32, evict: Option<i32> },
+}
+
+impl SqlCache {
+    pub(crate) fn new(threshold: u32, capacity: usize) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            capacity,
+            counts: Mutex::default(),
+            hits: AtomicU64::new(0),
+            stores: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    // Counts an execution of `sql` and decides how to send it on a stream which stored
+    // the texts in `stored`, updating `stored` as if the request is going to succeed.
+    pub(crate) fn plan(&self, sql: &str, stored: &mut StoredSql) -> SqlRef {
+        if self.capacity == 0 {
+            return SqlRef::Text;
+        }
+        if let Some(position) = stored.entries.iter().position(|(text, _)| text == sql) {
+            let entry = stored.entries.remove(position).unwrap();
+            let sql_id = entry.1;
+            stored.entries.push_back(entry);
+            return SqlRef::Stored(sql_id);
+        }
+        let count = {
+            let mut counts = self.counts.lock().unwrap();
+            if counts.len() >= self.capacity.saturating_mul(COUNTED_PER_STORED)
+                && !counts.contains_key(sql)
+            {
+                counts.clear();
+            }
+            let count = counts.entry(sql.to_string()).or_default();
+            *count = count.saturating_add(1);
+            *count
+        };
+        if count < self.threshold {
+            return SqlRef::Text;
+        }
+        let evict = if stored.entries.len() >= self.capacity {
+            stored.entries.pop_front().map(|(_, sql_id)| sql_id)
+        } else {
+            None
+        };
+        let sql_id = stored.next_id;
+        stored.next_id += 1;
+        stored.entries.push_back((sql.to_string(), sql_id));
+        SqlRef::Store { sql_id, evict }
+    }
+
+    // Updates the counters once a statement planned with plan() was executed
+    pub(crate) fn record(&self, sql_ref: SqlRef) {
+        match sql_ref {
+            SqlRef::Text => {}
+            SqlRef::Stored(_) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+            }
+            SqlRef::Store { evict, .. } => {
+                self.stores.fetch_add(1, Ordering::Relaxed);
+                if evict.is_some() {
+                    self.evictions.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn stats(&self) -> SqlCacheStats {
+        SqlCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            stores: self.stores.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stores_after_threshold() {
+        let cache = SqlCache::new(3, 10);
+        let mut stored = StoredSql::default();
+        let sql = "SELECT * FROM t WHERE id = ?";
+        assert_eq!(cache.plan(sql, &mut stored), SqlRef::Text);
+        assert_eq!(cache.plan(sql, &mut stored), SqlRef::Text);
+        assert_eq!(
+            cache.plan(sql, &mut stored),
+            SqlRef::Store {
+                sql_id: 0,
+                evict: None
+            }
+        );
+        assert_eq!(cache.plan(sql, &mut stored), SqlRef::Stored(0));
+        // Counts are shared by all streams, so another stream stores the text right away
+        let mut other = StoredSql::default();
+        assert_eq!(
+            cache.plan(sql, &mut other),
+            SqlRef::Store {
+                sql_id: 0,
+                evict: None
+            }
+        );
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let cache = SqlCache::new(1, 2);
+        let mut stored = StoredSql::default();
+        for sql in ["a", "b"] {
+            assert!(matches!(
+                cache.plan(sql, &mut stored),
+                SqlRef::Store { evict: None, .. }
+            ));
+        }
+        assert_eq!(cache.plan("a", &mut stored), SqlRef::Stored(0));
+        assert_eq!(
+            cache.plan("c", &mut stored),
+            SqlRef::Store {
+                sql_id: 2,
+                evict: Some(1)
+            }
+        );
+        assert_eq!(cache.plan("a", &mut stored), SqlRef::Stored(0));
+        assert!(matches!(
+            cache.plan("b", &mut stored),
+            SqlRef::Store {
+                sql_id: 3,
+                evict: Some(2)
+            }
+        ));
+    }
+
+    #[test]
+    fn test_stats() {
+        let cache = SqlCache::new(1, 1);
+        let mut stored = StoredSql::default();
+        for sql in ["a", "a", "b"] {
+            let sql_ref = cache.plan(sql, &mut stored);
+            cache.record(sql_ref);
+        }
+        assert_eq!(
+            cache.stats(),
+            SqlCacheStats {
+                hits: 1,
+                stores: 2,
+                evictions: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_forget_keeps_ids_unique() {
+        let cache = SqlCache::new(1, 4);
+        let mut stored = StoredSql::default();
+        cache.plan("a", &mut stored);
+        stored.forget();
+        assert_eq!(
+            cache.plan("a", &mut stored),
+            SqlRef::Store {
+                sql_id: 1,
+                evict: None
+            }
+        );
+    }
+
+    #[test]
+    fn test_zero_capacity_disables_storing() {
+        let cache = SqlCache::new(1, 0);
+        let mut stored = StoredSql::default();
+        assert_eq!(cache.plan("a", &mut stored), SqlRef::Text);
+        assert_eq!(cache.plan("a", &mut stored), SqlRef::Text);
+    }
+}
@@ -4,9 +4,21 @@
 use base64::prelude::BASE64_STANDARD_NO_PAD;
 use base64::Engine;
 
-use crate::Value;
+use crate::script::find_from;
+use crate::{Error, Value};
 
-/// SQL statement, possibly with bound parameters
+/// SQL statement, possibly with bound parameters.
+///
+/// Parameters are bound by position: the first one to `?1`, the second one to `?2`
+/// and so on. Anonymous `?` placeholders are numbered in order, so they take one parameter
+/// each, while numbered ones can refer to the same parameter several times.
+///
+/// # Examples
+///
+/// ```
+/// let stmt = libsql_client::Statement::with_args("SELECT * FROM t WHERE a = ?1 OR b = ?1", &[5]);
+/// ```
+#[derive(Clone, Debug)]
 pub struct Statement {
     pub(crate) sql: String,
     pub(crate) args: Vec<Value>,
@@ -40,6 +52,351 @@ impl Statement {
             args: params.iter().map(|p| p.clone().into()).collect(),
         }
     }
+
+    /// Creates a statement with parameters taken from an iterator, e.g. to build
+    /// parameter lists of a length only known at runtime.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let ids: Vec<i64> = vec![1, 2];
+    /// let stmt = libsql_client::Statement::with_params("SELECT * FROM t WHERE id IN (?, ?)", ids);
+    /// ```
+    pub fn with_params(
+        q: impl Into<String>,
+        params: impl IntoIterator<Item = impl Into<Value>>,
+    ) -> Statement {
+        Self {
+            sql: q.into(),
+            args: params.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Creates a statement ending with an `IN` list of one `?` placeholder per value,
+    /// appended to `sql_prefix`, and binds the values to them.
+    /// An empty list gives `()`, which SQLite accepts and which matches nothing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let stmt = libsql_client::Statement::with_in_clause("SELECT * FROM t WHERE id IN", [1, 2, 3]);
+    /// assert_eq!(stmt.placeholder_count(), Some(3));
+    /// assert!(stmt.to_string().starts_with(r#"{"sql": "SELECT * FROM t WHERE id IN (?, ?, ?)""#));
+    /// ```
+    pub fn with_in_clause(
+        sql_prefix: impl Into<String>,
+        values: impl IntoIterator<Item = impl Into<Value>>,
+    ) -> Statement {
+        let args: Vec<Value> = values.into_iter().map(Into::into).collect();
+        let placeholders = vec!["?"; args.len()].join(", ");
+        Self {
+            sql: format!("{} ({placeholders})", sql_prefix.into().trim_end()),
+            args,
+        }
+    }
+
+    /// Creates a `column IN (?, ?, ...)` condition with one placeholder per value,
+    /// to be combined with other SQL through [`Statement::append()`].
+    /// The column is inserted as is, so it may be qualified, e.g. `users.id`.
+    ///
+    /// An empty list gives `column IN ()`: SQLite accepts it, and it's false
+    /// for every row, while `NOT IN ()` is true. Unlike `IN (NULL)`, which is NULL,
+    /// so `NOT IN (NULL)` would filter out every row too.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libsql_client::Statement;
+    ///
+    /// let ids: Vec<i64> = vec![1, 2, 3];
+    /// let stmt = Statement::new("SELECT * FROM users WHERE")
+    ///     .append(Statement::in_clause("users.id", ids))
+    ///     .append(Statement::with_args("AND active = ?", &[1]));
+    /// assert_eq!(stmt.placeholder_count(), Some(4));
+    /// assert!(stmt.to_string().starts_with(
+    ///     r#"{"sql": "SELECT * FROM users WHERE users.id IN (?, ?, ?) AND active = ?""#
+    /// ));
+    /// ```
+    pub fn in_clause(
+        column: impl Into<String>,
+        values: impl IntoIterator<Item = impl Into<Value>>,
+    ) -> Statement {
+        Self::with_in_clause(format!("{} IN", column.into()), values)
+    }
+
+    /// Appends another statement, e.g. a fragment built with [`Statement::in_clause()`],
+    /// separated by a space. Its parameters are bound after the ones of this statement.
+    pub fn append(mut self, other: Statement) -> Statement {
+        self.sql.push(' ');
+        self.sql.push_str(&other.sql);
+        self.args.extend(other.args);
+        self
+    }
+
+    /// Binds one more parameter to the statement, taking ownership of it.
+    /// Unlike [`Statement::with_args()`], which clones its parameters,
+    /// this doesn't copy large blobs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libsql_client::{Statement, Value, ValueExt};
+    ///
+    /// let image: Vec<u8> = vec![0; 1024 * 1024];
+    /// let stmt = Statement::new("INSERT INTO images(id, data) VALUES (?, ?)")
+    ///     .bind(1)
+    ///     .bind(Value::from_blob(image));
+    /// ```
+    pub fn bind(mut self, param: impl Into<Value>) -> Statement {
+        self.args.push(param.into());
+        self
+    }
+
+    /// Tags the statement with a logical name, e.g. for cost attribution,
+    /// by prepending a `/* tag: name */` comment which shows up in the server's query logs.
+    /// A `*/` in the name is broken up, so that it can't end the comment early.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let stmt = libsql_client::Statement::with_args("SELECT * FROM users WHERE id = ?", &[1])
+    ///     .with_tag("get_user");
+    /// assert!(stmt.to_string().starts_with(r#"{"sql": "/* tag: get_user */ SELECT"#));
+    /// ```
+    pub fn with_tag(mut self, name: &str) -> Statement {
+        let name = name.replace("*/", "* /");
+        self.sql = format!("/* tag: {name} */ {}", self.sql);
+        self
+    }
+}
+
+impl Statement {
+    /// Returns the number of anonymous `?` placeholders in the SQL, ignoring those
+    /// inside string literals, quoted identifiers and comments. Returns `None`
+    /// if the statement uses numbered (`?1`) or named (`:name`, `@name`, `$name`) parameters.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let stmt = libsql_client::Statement::new("SELECT ?, '?' -- ?\n WHERE x = ?");
+    /// assert_eq!(stmt.placeholder_count(), Some(2));
+    /// let stmt = libsql_client::Statement::new("SELECT :name");
+    /// assert_eq!(stmt.placeholder_count(), None);
+    /// ```
+    pub fn placeholder_count(&self) -> Option<usize> {
+        match self.scan_placeholders() {
+            Placeholders {
+                anonymous,
+                max_numbered: None,
+                named: false,
+            } => Some(anonymous),
+            _ => None,
+        }
+    }
+
+    /// Renders the SQL with its placeholders replaced by the bound values, e.g. to
+    /// paste a failing query into the sqld shell. Text is quoted and blobs are shown
+    /// as `x'...'` hex literals. Placeholders without a bound value are kept.
+    ///
+    /// **The output is meant for debugging only.** Execute statements with their
+    /// parameters bound, never by running the rendered SQL, which defeats
+    /// the protection of parameters against SQL injection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libsql_client::{args, Statement};
+    ///
+    /// let stmt = Statement::with_args("INSERT INTO t VALUES (?, ?, ?)", args!(1, "it's", 0.5));
+    /// assert_eq!(stmt.to_debug_string(), "INSERT INTO t VALUES (1, 'it''s', 0.5)");
+    /// ```
+    pub fn to_debug_string(&self) -> String {
+        let mut rendered = String::with_capacity(self.sql.len());
+        let mut copied = 0;
+        // Parameter indexes are assigned the way SQLite does it: anonymous placeholders
+        // take the next one after the highest so far, and names keep their first one
+        let mut max_index = 0;
+        let mut named: Vec<&str> = Vec::new();
+        let mut named_indexes: Vec<usize> = Vec::new();
+        self.for_each_placeholder(|range, placeholder| {
+            let index = match placeholder {
+                Placeholder::Anonymous => max_index + 1,
+                Placeholder::Numbered(number) => number,
+                Placeholder::Named => {
+                    let name = &self.sql[range.clone()];
+                    match named.iter().position(|n| *n == name) {
+                        Some(pos) => named_indexes[pos],
+                        None => {
+                            named.push(name);
+                            named_indexes.push(max_index + 1);
+                            max_index + 1
+                        }
+                    }
+                }
+            };
+            max_index = max_index.max(index);
+            if let Some(value) = index.checked_sub(1).and_then(|i| self.args.get(i)) {
+                rendered.push_str(&self.sql[copied..range.start]);
+                rendered.push_str(&sql_literal(value));
+                copied = range.end;
+            }
+        });
+        rendered.push_str(&self.sql[copied..]);
+        rendered
+    }
+
+    // Finds the placeholders in the SQL, skipping string literals, quoted identifiers and comments
+    fn scan_placeholders(&self) -> Placeholders {
+        let mut placeholders = Placeholders::default();
+        self.for_each_placeholder(|_, placeholder| match placeholder {
+            Placeholder::Anonymous => placeholders.anonymous += 1,
+            Placeholder::Numbered(number) => {
+                placeholders.max_numbered = placeholders.max_numbered.max(Some(number))
+            }
+            Placeholder::Named => placeholders.named = true,
+        });
+        placeholders
+    }
+
+    // Calls `f` with the byte range of every placeholder in the SQL, in order,
+    // skipping string literals, quoted identifiers and comments
+    fn for_each_placeholder(&self, mut f: impl FnMut(std::ops::Range<usize>, Placeholder)) {
+        let bytes = self.sql.as_bytes();
+        let is_ident = |c: u8| c.is_ascii_alphanumeric() || c == b'_';
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                    i = find_from(bytes, i + 2, b"\n").map_or(bytes.len(), |end| end + 1);
+                }
+                b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                    i = find_from(bytes, i + 2, b"*/").map_or(bytes.len(), |end| end + 2);
+                }
+                quote @ (b'\'' | b'"' | b'`' | b'[') => {
+                    let closing = if quote == b'[' { b']' } else { quote };
+                    i = find_from(bytes, i + 1, &[closing]).map_or(bytes.len(), |end| end + 1);
+                }
+                b'?' if bytes.get(i + 1).is_some_and(u8::is_ascii_digit) => {
+                    let start = i;
+                    i += 1;
+                    while i < bytes.len() && bytes[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                    // Numbers too large for usize are rejected by SQLite anyway
+                    let number = self.sql[start + 1..i].parse().unwrap_or(usize::MAX);
+                    f(start..i, Placeholder::Numbered(number));
+                }
+                b'?' => {
+                    f(i..i + 1, Placeholder::Anonymous);
+                    i += 1;
+                }
+                b':' | b'@' | b'$' if bytes.get(i + 1).copied().is_some_and(is_ident) => {
+                    let start = i;
+                    i += 1;
+                    while i < bytes.len() && (is_ident(bytes[i]) || bytes[i] == b'$') {
+                        i += 1;
+                    }
+                    f(start..i, Placeholder::Named);
+                }
+                c if is_ident(c) => {
+                    // Skip whole words, which may contain `$`
+                    while i < bytes.len() && (is_ident(bytes[i]) || bytes[i] == b'$') {
+                        i += 1;
+                    }
+                }
+                _ => i += 1,
+            }
+        }
+    }
+
+    // Fails if the number of bound parameters can't match the placeholders,
+    // which saves a round trip to learn about the mistake from the server.
+    // Numbered placeholders need as many parameters as the highest number.
+    pub(crate) fn check_placeholders(&self) -> anyhow::Result<()> {
+        let expected = match self.scan_placeholders() {
+            Placeholders { named: true, .. } => return Ok(()),
+            Placeholders {
+                anonymous,
+                max_numbered: Some(_),
+                ..
+            } if anonymous > 0 => {
+                return Err(Error::Misuse(format!(
+                    "Statement mixes numbered (?1) and anonymous (?) placeholders: {}",
+                    self.sql
+                ))
+                .into())
+            }
+            Placeholders {
+                max_numbered: Some(max),
+                ..
+            } => max,
+            Placeholders { anonymous, .. } => anonymous,
+        };
+        if expected != self.args.len() {
+            return Err(Error::Misuse(format!(
+                "Statement has {expected} placeholder(s), but {} parameter(s) were bound: {}",
+                self.args.len(),
+                self.sql
+            ))
+            .into());
+        }
+        Ok(())
+    }
+
+    // Fails if the SQL holds nothing but whitespace, comments and semicolons,
+    // which the server would reject with a confusing error
+    pub(crate) fn check_not_empty(&self) -> anyhow::Result<()> {
+        if crate::script::split_statements(&self.sql).is_empty() {
+            return Err(Error::Misuse("empty statement".to_string()).into());
+        }
+        Ok(())
+    }
+
+    // SQLite names of the bound parameters' types, for error reporting
+    pub(crate) fn arg_types(&self) -> Vec<&'static str> {
+        self.args.iter().map(crate::utils::type_name).collect()
+    }
+}
+
+// Kind of a placeholder in the SQL of a statement
+enum Placeholder {
+    // `?`
+    Anonymous,
+    // `?NNN`
+    Numbered(usize),
+    // `:name`, `@name` or `$name`
+    Named,
+}
+
+// Renders a value as an SQL literal, for Statement::to_debug_string()
+fn sql_literal(value: &Value) -> String {
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::Integer { value } => value.to_string(),
+        // Debug formatting keeps a decimal point, so that 1.0 stays a REAL
+        Value::Float { value } if value.is_finite() => format!("{value:?}"),
+        // SQLite has no literals for infinities, but parses overflowing ones as such
+        Value::Float { value } if value.is_infinite() => if value.is_sign_positive() {
+            "9e999"
+        } else {
+            "-9e999"
+        }
+        .to_string(),
+        Value::Float { .. } => "NULL".to_string(),
+        Value::Text { value } => format!("'{}'", value.replace('\'', "''")),
+        Value::Blob { value } => format!("x'{}'", crate::utils::encode_hex(value)),
+    }
+}
+
+// Placeholders found in the SQL of a statement
+#[derive(Debug, Default)]
+struct Placeholders {
+    // Number of `?` placeholders
+    anonymous: usize,
+    // Highest number of `?NNN` placeholders
+    max_numbered: Option<usize>,
+    // Whether there are `:name`, `@name` or `$name` placeholders
+    named: bool,
 }
 
 impl From<String> for Statement {
@@ -84,3 +441,113 @@ impl std::fmt::Display for Statement {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_tag() {
+        let stmt = Statement::with_args("SELECT ?", &[1]).with_tag("evil */ DROP TABLE t; --");
+        assert_eq!(stmt.sql, "/* tag: evil * / DROP TABLE t; -- */ SELECT ?");
+        assert_eq!(stmt.placeholder_count(), Some(1));
+        assert!(stmt.check_placeholders().is_ok());
+        assert!(Statement::new("").with_tag("t").check_not_empty().is_err());
+    }
+
+    #[cfg(feature = "local_backend")]
+    #[test]
+    fn test_tagged_expression_executes() {
+        let db = crate::SyncClient::in_memory().unwrap();
+        let rs = db
+            .execute(Statement::new("SELECT 1 + 1").with_tag("sum"))
+            .unwrap();
+        assert_eq!(rs.rows[0].try_get::<i64>(0).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_numbered_placeholders() {
+        let stmt = Statement::with_args("SELECT * FROM t WHERE a = ?1 OR b = ?1", &[5]);
+        assert_eq!(stmt.placeholder_count(), None);
+        assert!(stmt.check_placeholders().is_ok());
+        assert!(Statement::with_args("SELECT ?2, ?1", &[1, 2])
+            .check_placeholders()
+            .is_ok());
+        // ?3 needs three parameters, even if ?2 isn't used
+        assert!(Statement::with_args("SELECT ?1, ?3", &[1, 3])
+            .check_placeholders()
+            .is_err());
+        let e = Statement::with_args("SELECT ?1, ?", &[1, 2])
+            .check_placeholders()
+            .unwrap_err();
+        assert!(e.to_string().contains("mixes numbered"));
+        assert!(Statement::with_args("SELECT :a, ?1", &[1, 2])
+            .check_placeholders()
+            .is_ok());
+    }
+
+    #[cfg(feature = "local_backend")]
+    #[test]
+    fn test_numbered_placeholders_execute() {
+        let db = crate::SyncClient::in_memory().unwrap();
+        let rs = db
+            .execute(Statement::with_args("SELECT ?1 + ?1, ?2", &[21, 7]))
+            .unwrap();
+        assert_eq!(rs.rows[0].try_get::<i64>(0).unwrap(), 42);
+        assert_eq!(rs.rows[0].try_get::<i64>(1).unwrap(), 7);
+    }
+
+    #[cfg(feature = "local_backend")]
+    #[test]
+    fn test_in_clause_executes() {
+        let db = crate::SyncClient::in_memory().unwrap();
+        db.execute("CREATE TABLE t(x)").unwrap();
+        db.execute("INSERT INTO t VALUES (1), (2), (3)").unwrap();
+        let count = |stmt: Statement| {
+            db.execute(Statement::new("SELECT count(*) FROM t WHERE").append(stmt))
+                .unwrap()
+                .rows[0]
+                .try_get::<i64>(0)
+                .unwrap()
+        };
+        assert_eq!(count(Statement::in_clause("x", [1, 3])), 2);
+        assert_eq!(count(Statement::in_clause("x", Vec::<i64>::new())), 0);
+        assert_eq!(count(Statement::in_clause("x NOT", Vec::<i64>::new())), 3);
+    }
+
+    #[test]
+    fn test_to_debug_string() {
+        let stmt = Statement::with_args(
+            "SELECT ?, '?', ?3, :a, ?, :a -- ?\n, x'00', ?",
+            &[
+                Value::Blob {
+                    value: vec![0xca, 0xfe],
+                },
+                Value::Null,
+                Value::Float { value: 1.0 },
+                Value::Text {
+                    value: "a'b".to_string(),
+                },
+                Value::Integer { value: -2 },
+            ],
+        );
+        assert_eq!(
+            stmt.to_debug_string(),
+            "SELECT x'cafe', '?', 1.0, 'a''b', -2, 'a''b' -- ?\n, x'00', ?"
+        );
+    }
+
+    #[test]
+    fn test_empty_statements() {
+        for sql in [";", "-- comment", "   ", "/* a */ ; ;\n"] {
+            let e = Statement::new(sql).check_not_empty().unwrap_err();
+            assert!(
+                matches!(e.downcast_ref::<Error>(), Some(Error::Misuse(m)) if m == "empty statement"),
+                "{sql:?} should be rejected"
+            );
+        }
+        assert!(Statement::new("SELECT 1; -- trailing")
+            .check_not_empty()
+            .is_ok());
+    }
+}
@@ -2,10 +2,41 @@
 
 use crate::{Client, ResultSet, Statement, SyncClient};
 use anyhow::Result;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+// Savepoints created in a transaction, oldest first.
+#[derive(Debug, Default)]
+struct Savepoints(Mutex<Vec<String>>);
+
+impl Savepoints {
+    fn push(&self, name: &str) {
+        self.0.lock().unwrap().push(name.to_string());
+    }
+
+    // Returns the position of the most recent savepoint with the given name
+    fn position(&self, name: &str) -> Result<usize> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .rposition(|n| n == name)
+            .ok_or_else(|| anyhow::anyhow!("No savepoint named {name} in this transaction"))
+    }
+
+    fn truncate(&self, len: usize) {
+        self.0.lock().unwrap().truncate(len);
+    }
+}
 
 pub struct Transaction<'a> {
     pub(crate) id: u64,
     pub(crate) client: &'a Client,
+    // Whether BEGIN was already sent to the server
+    begun: AtomicBool,
+    savepoints: Savepoints,
+    // Whether statements which write are rejected before being sent
+    read_only: bool,
 }
 
 impl<'a> Transaction<'a> {
@@ -13,7 +44,59 @@ impl<'a> Transaction<'a> {
         client
             .execute_in_transaction(id, Statement::from("BEGIN"))
             .await?;
-        Ok(Self { id, client })
+        Ok(Self {
+            id,
+            client,
+            begun: AtomicBool::new(true),
+            savepoints: Savepoints::default(),
+            read_only: false,
+        })
+    }
+
+    /// Creates a transaction which sends BEGIN only along with its first statement.
+    /// If no statement is ever executed, committing or rolling back is a no-op
+    /// which doesn't contact the server at all.
+    pub fn deferred(client: &'a Client, id: u64) -> Transaction<'a> {
+        Self {
+            id,
+            client,
+            begun: AtomicBool::new(false),
+            savepoints: Savepoints::default(),
+            read_only: false,
+        }
+    }
+
+    /// Makes the transaction reject statements which write, see [`Client::read_transaction()`].
+    pub(crate) fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    // Fails if the transaction is read-only and the statement may write.
+    fn check_read_only(&self, stmt: &Statement) -> Result<()> {
+        if self.read_only && !crate::utils::is_read_only(&stmt.sql) {
+            return Err(crate::Error::Misuse(format!(
+                "Statement is not allowed in a read-only transaction: {}",
+                stmt.sql
+            ))
+            .into());
+        }
+        Ok(())
+    }
+
+    // Sends BEGIN if this is the first statement of a deferred transaction.
+    async fn ensure_begun(&self) -> Result<()> {
+        if self.begun.swap(true, Ordering::AcqRel) {
+            return Ok(());
+        }
+        let result = self
+            .client
+            .execute_in_transaction(self.id, Statement::from("BEGIN"))
+            .await;
+        if result.is_err() {
+            self.begun.store(false, Ordering::Release);
+        }
+        result.map(|_| ())
     }
 
     /// Executes a statement within the current transaction.
@@ -35,18 +118,77 @@ impl<'a> Transaction<'a> {
     ///   # }
     /// ```
     pub async fn execute(&self, stmt: impl Into<Statement>) -> Result<ResultSet> {
-        self.client
-            .execute_in_transaction(self.id, stmt.into())
-            .await
+        let stmt = stmt.into();
+        self.check_read_only(&stmt)?;
+        self.ensure_begun().await?;
+        self.client.execute_in_transaction(self.id, stmt).await
+    }
+
+    /// Creates a savepoint, to which the transaction can later be partially
+    /// rolled back with [`Transaction::rollback_to()`].
+    /// # Example
+    ///
+    /// ```
+    ///   # async fn f() -> anyhow::Result<()> {
+    ///   let db = libsql_client::Client::in_memory()?;
+    ///   # db.execute("create table users(name text)").await?;
+    ///   let tx = db.transaction().await?;
+    ///   tx.execute("insert into users values ('John')").await?;
+    ///   tx.savepoint("before_jane").await?;
+    ///   tx.execute("insert into users values ('Jane')").await?;
+    ///   tx.rollback_to("before_jane").await?; // only Jane's insert is undone
+    ///   tx.commit().await?;
+    ///   # Ok(())
+    ///   # }
+    /// ```
+    pub async fn savepoint(&self, name: &str) -> Result<()> {
+        let sql = format!("SAVEPOINT {}", crate::utils::quote_identifier(name)?);
+        self.execute(sql).await?;
+        self.savepoints.push(name);
+        Ok(())
+    }
+
+    /// Releases a savepoint, keeping the changes made since it was created.
+    /// Savepoints created after it are released too.
+    /// Fails without contacting the server if the savepoint doesn't exist.
+    pub async fn release(&self, name: &str) -> Result<()> {
+        let position = self.savepoints.position(name)?;
+        let sql = format!(
+            "RELEASE SAVEPOINT {}",
+            crate::utils::quote_identifier(name)?
+        );
+        self.execute(sql).await?;
+        self.savepoints.truncate(position);
+        Ok(())
+    }
+
+    /// Rolls back the changes made since the savepoint was created. The savepoint
+    /// itself stays in place, while savepoints created after it are discarded.
+    /// Fails without contacting the server if the savepoint doesn't exist.
+    pub async fn rollback_to(&self, name: &str) -> Result<()> {
+        let position = self.savepoints.position(name)?;
+        let sql = format!(
+            "ROLLBACK TO SAVEPOINT {}",
+            crate::utils::quote_identifier(name)?
+        );
+        self.execute(sql).await?;
+        self.savepoints.truncate(position + 1);
+        Ok(())
     }
 
     /// Commits the transaction to the database.
     pub async fn commit(self) -> Result<()> {
+        if !self.begun.load(Ordering::Acquire) {
+            return Ok(());
+        }
         self.client.commit_transaction(self.id).await
     }
 
     /// Rolls back the transaction, cancelling any of its side-effects.
     pub async fn rollback(self) -> Result<()> {
+        if !self.begun.load(Ordering::Acquire) {
+            return Ok(());
+        }
         self.client.rollback_transaction(self.id).await
     }
 }
@@ -54,12 +196,68 @@ impl<'a> Transaction<'a> {
 pub struct SyncTransaction<'a> {
     pub(crate) id: u64,
     pub(crate) client: &'a SyncClient,
+    // Whether BEGIN was already sent to the server
+    begun: AtomicBool,
+    savepoints: Savepoints,
+    // Whether statements which write are rejected before being sent
+    read_only: bool,
 }
 
 impl<'a> SyncTransaction<'a> {
     pub fn new(client: &'a SyncClient, id: u64) -> Result<SyncTransaction<'a>> {
         client.execute_in_transaction(id, Statement::from("BEGIN"))?;
-        Ok(Self { id, client })
+        Ok(Self {
+            id,
+            client,
+            begun: AtomicBool::new(true),
+            savepoints: Savepoints::default(),
+            read_only: false,
+        })
+    }
+
+    /// Creates a transaction which sends BEGIN only along with its first statement.
+    /// If no statement is ever executed, committing or rolling back is a no-op
+    /// which doesn't contact the server at all.
+    pub fn deferred(client: &'a SyncClient, id: u64) -> SyncTransaction<'a> {
+        Self {
+            id,
+            client,
+            begun: AtomicBool::new(false),
+            savepoints: Savepoints::default(),
+            read_only: false,
+        }
+    }
+
+    /// Makes the transaction reject statements which write, see [`SyncClient::read_transaction()`].
+    pub(crate) fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    // Fails if the transaction is read-only and the statement may write.
+    fn check_read_only(&self, stmt: &Statement) -> Result<()> {
+        if self.read_only && !crate::utils::is_read_only(&stmt.sql) {
+            return Err(crate::Error::Misuse(format!(
+                "Statement is not allowed in a read-only transaction: {}",
+                stmt.sql
+            ))
+            .into());
+        }
+        Ok(())
+    }
+
+    // Sends BEGIN if this is the first statement of a deferred transaction.
+    fn ensure_begun(&self) -> Result<()> {
+        if self.begun.swap(true, Ordering::AcqRel) {
+            return Ok(());
+        }
+        let result = self
+            .client
+            .execute_in_transaction(self.id, Statement::from("BEGIN"));
+        if result.is_err() {
+            self.begun.store(false, Ordering::Release);
+        }
+        result.map(|_| ())
     }
 
     /// Executes a statement within the current transaction.
@@ -81,16 +279,62 @@ impl<'a> SyncTransaction<'a> {
     ///   # }
     /// ```
     pub fn execute(&self, stmt: impl Into<Statement>) -> Result<ResultSet> {
-        self.client.execute_in_transaction(self.id, stmt.into())
+        let stmt = stmt.into();
+        self.check_read_only(&stmt)?;
+        self.ensure_begun()?;
+        self.client.execute_in_transaction(self.id, stmt)
+    }
+
+    /// Creates a savepoint, to which the transaction can later be partially
+    /// rolled back with [`SyncTransaction::rollback_to()`].
+    pub fn savepoint(&self, name: &str) -> Result<()> {
+        let sql = format!("SAVEPOINT {}", crate::utils::quote_identifier(name)?);
+        self.execute(sql)?;
+        self.savepoints.push(name);
+        Ok(())
+    }
+
+    /// Releases a savepoint, keeping the changes made since it was created.
+    /// Savepoints created after it are released too.
+    /// Fails without contacting the server if the savepoint doesn't exist.
+    pub fn release(&self, name: &str) -> Result<()> {
+        let position = self.savepoints.position(name)?;
+        let sql = format!(
+            "RELEASE SAVEPOINT {}",
+            crate::utils::quote_identifier(name)?
+        );
+        self.execute(sql)?;
+        self.savepoints.truncate(position);
+        Ok(())
+    }
+
+    /// Rolls back the changes made since the savepoint was created. The savepoint
+    /// itself stays in place, while savepoints created after it are discarded.
+    /// Fails without contacting the server if the savepoint doesn't exist.
+    pub fn rollback_to(&self, name: &str) -> Result<()> {
+        let position = self.savepoints.position(name)?;
+        let sql = format!(
+            "ROLLBACK TO SAVEPOINT {}",
+            crate::utils::quote_identifier(name)?
+        );
+        self.execute(sql)?;
+        self.savepoints.truncate(position + 1);
+        Ok(())
     }
 
     /// Commits the transaction to the database.
     pub fn commit(self) -> Result<()> {
+        if !self.begun.load(Ordering::Acquire) {
+            return Ok(());
+        }
         self.client.commit_transaction(self.id)
     }
 
     /// Rolls back the transaction, cancelling any of its side-effects.
     pub fn rollback(self) -> Result<()> {
+        if !self.begun.load(Ordering::Acquire) {
+            return Ok(());
+        }
         self.client.rollback_transaction(self.id)
     }
 }
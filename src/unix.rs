@@ -0,0 +1,264 @@
+//! `HttpClient` sends the requests of [crate::http::Client] over a Unix domain socket,
+//! e.g. to a sqld sidecar, without the overhead of TCP.
+
+use anyhow::Result;
+use std::path::PathBuf;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+
+use crate::Error;
+
+/// Sends each request as HTTP/1.1 on a new connection to the socket,
+/// which the server closes once it responded.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn f() -> anyhow::Result<()> {
+/// use libsql_client::http::{Client, InnerClient};
+/// use libsql_client::unix::HttpClient;
+///
+/// // The host of the URL only ends up in the Host header
+/// let inner = InnerClient::Unix(HttpClient::new("/var/run/sqld.sock"));
+/// let db = Client::new(inner, "http://localhost", "");
+/// db.execute("SELECT 1").await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct HttpClient {
+    path: PathBuf,
+}
+
+impl HttpClient {
+    /// Creates a client connecting to the socket at `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub async fn send(
+        &self,
+        url: String,
+        auth: String,
+        headers: Vec<(String, String)>,
+        body: String,
+    ) -> Result<String> {
+        self.request("POST", &url, auth, headers, Some(body)).await
+    }
+
+    pub async fn get(
+        &self,
+        url: String,
+        auth: String,
+        headers: Vec<(String, String)>,
+    ) -> Result<String> {
+        self.request("GET", &url, auth, headers, None).await
+    }
+
+    async fn request(
+        &self,
+        method: &str,
+        url: &str,
+        auth: String,
+        headers: Vec<(String, String)>,
+        body: Option<String>,
+    ) -> Result<String> {
+        let url = url::Url::parse(url)?;
+        let mut target = url.path().to_string();
+        if let Some(query) = url.query() {
+            target = format!("{target}?{query}");
+        }
+        let host = url.host_str().unwrap_or("localhost");
+        check_header("Authorization", &auth)?;
+        for (name, value) in &headers {
+            check_header(name, value)?;
+        }
+        let mut request = format!(
+            "{method} {target} HTTP/1.1\r\nHost: {host}\r\nConnection: close\r\nAuthorization: {auth}\r\n"
+        );
+        for (name, value) in headers {
+            request.push_str(&format!("{name}: {value}\r\n"));
+        }
+        if let Some(body) = &body {
+            request.push_str(&format!(
+                "Content-Type: application/json\r\nContent-Length: {}\r\n",
+                body.len()
+            ));
+        }
+        request.push_str("\r\n");
+        request.push_str(body.as_deref().unwrap_or_default());
+
+        let mut stream = UnixStream::connect(&self.path)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to connect to {}: {e}", self.path.display()))?;
+        stream.write_all(request.as_bytes()).await?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await?;
+        let (status, body) = parse_response(&response)?;
+        if status != 200 {
+            return Err(Error::from_status(status, body).into());
+        }
+        Ok(body)
+    }
+}
+
+// Line breaks in a header would end it early and let the rest pass as other
+// headers or as the body, so they are rejected before anything is sent
+fn check_header(name: &str, value: &str) -> Result<()> {
+    if name.is_empty() || name.contains([':', '\r', '\n']) || value.contains(['\r', '\n']) {
+        return Err(Error::Misuse(format!("Invalid HTTP header `{}`", name.escape_debug())).into());
+    }
+    Ok(())
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+// Splits an HTTP/1.1 response into its status and body, decoding a chunked body
+fn parse_response(response: &[u8]) -> Result<(u16, String)> {
+    let malformed = |what: &str| anyhow::anyhow!("Malformed HTTP response: {what}");
+    let header_end = find(response, b"\r\n\r\n").ok_or_else(|| malformed("no end of headers"))?;
+    let head = std::str::from_utf8(&response[..header_end])?;
+    let mut lines = head.split("\r\n");
+    let status = lines
+        .next()
+        .and_then(|line| line.split(' ').nth(1))
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| malformed("no status"))?;
+    let mut chunked = false;
+    let mut content_length = None;
+    for (name, value) in lines.filter_map(|line| line.split_once(':')) {
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("transfer-encoding") {
+            chunked = value.eq_ignore_ascii_case("chunked");
+        } else if name.eq_ignore_ascii_case("content-length") {
+            content_length = value.parse::<usize>().ok();
+        }
+    }
+    let body = &response[header_end + 4..];
+    let body = match (chunked, content_length) {
+        (true, _) => decode_chunked(body).ok_or_else(|| malformed("invalid chunked body"))?,
+        (false, Some(len)) => body
+            .get(..len)
+            .ok_or_else(|| malformed("truncated body"))?
+            .to_vec(),
+        (false, None) => body.to_vec(),
+    };
+    Ok((status, String::from_utf8(body)?))
+}
+
+fn decode_chunked(mut body: &[u8]) -> Option<Vec<u8>> {
+    let mut decoded = Vec::new();
+    loop {
+        let line_end = find(body, b"\r\n")?;
+        let size = std::str::from_utf8(&body[..line_end]).ok()?;
+        // Chunk extensions follow the size after a semicolon
+        let size = size.split(';').next()?.trim();
+        let size = usize::from_str_radix(size, 16).ok()?;
+        body = &body[line_end + 2..];
+        if size == 0 {
+            return Some(decoded);
+        }
+        decoded.extend_from_slice(body.get(..size)?);
+        body = body.get(size + 2..)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_response() {
+        let response = b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\n{}";
+        assert_eq!(parse_response(response).unwrap(), (200, "{}".to_string()));
+        let response =
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n3\r\n{\"a\r\n4;ext=1\r\n\":1}\r\n0\r\n\r\n";
+        assert_eq!(
+            parse_response(response).unwrap(),
+            (200, "{\"a\":1}".to_string())
+        );
+        let response = b"HTTP/1.1 401 Unauthorized\r\n\r\nbad token";
+        assert_eq!(
+            parse_response(response).unwrap(),
+            (401, "bad token".to_string())
+        );
+        assert!(parse_response(b"HTTP/1.1 200 OK\r\ncontent-length: 5\r\n\r\n{}").is_err());
+        assert!(parse_response(b"garbage").is_err());
+    }
+
+    #[test]
+    fn test_check_header() {
+        assert!(check_header("x-request-id", "42").is_ok());
+        for (name, value) in [
+            ("x-request-id", "42\r\nX-Admin: 1"),
+            ("x-request-id", "42\n"),
+            ("x-request\r\nid", "42"),
+            ("x:id", "42"),
+            ("", "42"),
+        ] {
+            let e = check_header(name, value).unwrap_err();
+            assert!(matches!(e.downcast_ref::<Error>(), Some(Error::Misuse(_))));
+        }
+    }
+
+    #[test]
+    fn test_unix_urls() {
+        let config = || crate::Config::new("unix:///var/run/sqld.sock").unwrap();
+        let db = futures::executor::block_on(crate::Client::from_config(config())).unwrap();
+        assert!(matches!(db, crate::Client::Http(_)));
+        let e = futures::executor::block_on(crate::Client::from_config_with_backend(
+            config(),
+            "reqwest",
+        ))
+        .unwrap_err();
+        assert!(matches!(e.downcast_ref::<Error>(), Some(Error::Misuse(_))));
+    }
+
+    #[tokio::test]
+    async fn test_request_over_socket() {
+        use tokio::net::UnixListener;
+
+        let path = std::env::temp_dir().join(format!("libsql-client-{}.sock", std::process::id()));
+        std::fs::remove_file(&path).ok();
+        let listener = UnixListener::bind(&path).unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.ends_with(b"ping") {
+                let len = stream.read(&mut buf).await.unwrap();
+                assert!(
+                    len > 0,
+                    "connection closed before the whole request arrived"
+                );
+                request.extend_from_slice(&buf[..len]);
+            }
+            let request = String::from_utf8(request).unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\npong")
+                .await
+                .unwrap();
+            request
+        });
+        let client = HttpClient::new(&path);
+        let response = client
+            .send(
+                "http://localhost/v2/pipeline".to_string(),
+                "Bearer t".to_string(),
+                vec![],
+                "ping".to_string(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response, "pong");
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /v2/pipeline HTTP/1.1\r\n"));
+        assert!(request.contains("Authorization: Bearer t\r\n"));
+        assert!(request.ends_with("\r\n\r\nping"));
+        std::fs::remove_file(&path).ok();
+    }
+}
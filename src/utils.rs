@@ -1,5 +1,203 @@
+use base64::prelude::BASE64_STANDARD_NO_PAD;
+use base64::Engine;
+use fallible_iterator::FallibleIterator;
+use sqlite3_parser::ast::{Cmd, Stmt};
+use sqlite3_parser::lexer::sql::Parser;
 use url::Url;
 
+use crate::proto::StmtResult;
+use crate::{BatchResult, ResultSet, Value};
+
+/// Converts a [Value] into its natural JSON representation: integers and floats
+/// become numbers, text becomes a string and blobs become base64-encoded strings.
+#[cfg_attr(not(feature = "response_body"), allow(dead_code))]
+pub(crate) fn value_to_json(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Integer { value } => (*value).into(),
+        Value::Float { value } => serde_json::Number::from_f64(*value)
+            .map(serde_json::Value::Number)
+            .unwrap_or(serde_json::Value::Null),
+        Value::Text { value } => value.clone().into(),
+        Value::Blob { value } => BASE64_STANDARD_NO_PAD.encode(value).into(),
+    }
+}
+
+/// Returns the SQLite name of the value's type.
+pub(crate) fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "NULL",
+        Value::Integer { .. } => "INTEGER",
+        Value::Float { .. } => "REAL",
+        Value::Text { .. } => "TEXT",
+        Value::Blob { .. } => "BLOB",
+    }
+}
+
+/// Turns a server error about mismatched types into [crate::Error::TypeMismatch],
+/// which lists the types of the parameters bound to the statement.
+/// Other errors are returned unchanged.
+pub(crate) fn with_arg_types_on_mismatch(
+    e: anyhow::Error,
+    arg_types: Vec<&'static str>,
+) -> anyhow::Error {
+    let message = e.to_string();
+    let lowercase = message.to_lowercase();
+    if !arg_types.is_empty()
+        && (lowercase.contains("datatype mismatch") || lowercase.contains("cannot store"))
+    {
+        crate::Error::TypeMismatch { message, arg_types }.into()
+    } else {
+        e
+    }
+}
+
+/// Splits a [BatchResult] of `steps` statements into one result per step. Steps which
+/// were not executed, because their condition didn't hold or an earlier step
+/// aborted the batch, are reported as errors.
+pub(crate) fn split_batch_result(
+    batch_result: BatchResult,
+    steps: usize,
+) -> Vec<anyhow::Result<ResultSet>> {
+    split_batch_steps(batch_result, steps)
+        .into_iter()
+        .map(|step| step.map(ResultSet::from))
+        .collect()
+}
+
+/// Like [split_batch_result], but keeps the raw statement results.
+pub(crate) fn split_batch_steps(
+    batch_result: BatchResult,
+    steps: usize,
+) -> Vec<anyhow::Result<StmtResult>> {
+    let BatchResult {
+        step_results,
+        step_errors,
+    } = batch_result;
+    let mut step_results = step_results.into_iter();
+    let mut step_errors = step_errors.into_iter();
+    (0..steps)
+        .map(
+            |step| match (step_results.next().flatten(), step_errors.next().flatten()) {
+                (_, Some(error)) => Err(anyhow::anyhow!(error.message)),
+                (Some(result), None) => Ok(result),
+                (None, None) => Err(anyhow::anyhow!("Step {step} was not executed")),
+            },
+        )
+        .collect()
+}
+
+/// Makes column names unique by suffixing repeated ones with their occurrence:
+/// `[id, name, id]` becomes `[id, name, id:1]`. A suffixed name which collides with
+/// another column gets a higher suffix.
+pub(crate) fn disambiguate_columns(columns: &[String]) -> Vec<String> {
+    let mut seen = std::collections::HashSet::new();
+    let mut used: std::collections::HashSet<String> = columns.iter().cloned().collect();
+    columns
+        .iter()
+        .map(|column| {
+            if seen.insert(column.as_str()) {
+                return column.clone();
+            }
+            let name = (1..)
+                .map(|occurrence| format!("{column}:{occurrence}"))
+                .find(|name| !used.contains(name))
+                .unwrap();
+            used.insert(name.clone());
+            name
+        })
+        .collect()
+}
+
+/// Formats a value for display in a table: NULLs as `NULL`,
+/// blobs as hex literals and text without quotes.
+pub(crate) fn display_cell(value: &Value) -> String {
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::Integer { value } => value.to_string(),
+        Value::Float { value } => value.to_string(),
+        Value::Text { value } => value.clone(),
+        Value::Blob { value } => format!("x'{}'", encode_hex(value)),
+    }
+}
+
+/// Checks that every statement of `sql` only reads from the database: queries,
+/// `EXPLAIN` and savepoint management. Statements which fail to parse are not read-only.
+pub(crate) fn is_read_only(sql: &str) -> bool {
+    let mut parser = Parser::new(sql.as_bytes());
+    loop {
+        match parser.next() {
+            Ok(None) => return true,
+            Ok(Some(Cmd::Explain(_) | Cmd::ExplainQueryPlan(_))) => {}
+            Ok(Some(Cmd::Stmt(
+                Stmt::Select(_)
+                | Stmt::Savepoint(_)
+                | Stmt::Release(_)
+                | Stmt::Rollback {
+                    savepoint_name: Some(_),
+                    ..
+                },
+            ))) => {}
+            Ok(Some(_)) | Err(_) => return false,
+        }
+    }
+}
+
+/// Quotes an SQL identifier, e.g. a table or column name, doubling any embedded
+/// double quotes. Use it for dynamic SQL where identifiers can't be bound as parameters.
+/// Fails if the name contains a null byte, at which SQLite would truncate it.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(libsql_client::quote_identifier(r#"my "table""#).unwrap(), r#""my ""table""""#);
+/// assert!(libsql_client::quote_identifier("a\0b").is_err());
+/// ```
+pub fn quote_identifier(name: &str) -> anyhow::Result<String> {
+    if name.contains('\0') {
+        anyhow::bail!("Identifier contains a null byte: {name:?}");
+    }
+    Ok(format!("\"{}\"", name.replace('"', "\"\"")))
+}
+
+/// Quotes an SQL string literal, doubling any embedded single quotes.
+/// Prefer binding values as parameters: this is for SQL which can't take them,
+/// e.g. generated scripts or default values in `CREATE TABLE`.
+/// Fails if the text contains a null byte, at which SQLite would truncate it.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(libsql_client::quote_string_literal("it's").unwrap(), "'it''s'");
+/// ```
+pub fn quote_string_literal(text: &str) -> anyhow::Result<String> {
+    if text.contains('\0') {
+        anyhow::bail!("String literal contains a null byte: {text:?}");
+    }
+    Ok(format!("'{}'", text.replace('\'', "''")))
+}
+
+/// Encodes bytes as a lowercase hex string.
+pub(crate) fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Decodes a hex string, accepting both lowercase and uppercase digits.
+pub(crate) fn decode_hex(hex: &str) -> anyhow::Result<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        anyhow::bail!("Hex string has an odd length: {}", hex.len());
+    }
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| anyhow::anyhow!("Invalid hex digits in {hex:?}"))
+        })
+        .collect()
+}
+
 pub(crate) fn pop_query_param(url: &mut Url, param: String) -> Option<String> {
     let mut pairs: Vec<_> = url
         .query_pairs()
@@ -23,6 +221,89 @@ mod tests {
     use super::*;
     use url::Url;
 
+    #[test]
+    fn test_type_mismatch_error() {
+        let e = with_arg_types_on_mismatch(
+            anyhow::anyhow!("SQLITE_MISMATCH: datatype mismatch"),
+            vec!["TEXT", "INTEGER"],
+        );
+        assert_eq!(
+            e.to_string(),
+            "SQLITE_MISMATCH: datatype mismatch (bound parameter types: 1: TEXT, 2: INTEGER)"
+        );
+        let e = with_arg_types_on_mismatch(anyhow::anyhow!("no such table: t"), vec!["TEXT"]);
+        assert!(e.downcast_ref::<crate::Error>().is_none());
+    }
+
+    #[test]
+    fn test_split_batch_result() {
+        let batch_result = BatchResult {
+            step_results: vec![
+                Some(crate::proto::StmtResult {
+                    cols: vec![],
+                    rows: vec![],
+                    affected_row_count: 1,
+                    last_insert_rowid: None,
+                }),
+                None,
+                None,
+            ],
+            step_errors: vec![
+                None,
+                Some(crate::proto::Error {
+                    message: "no such table: t".to_string(),
+                }),
+                None,
+            ],
+        };
+        let results = split_batch_result(batch_result, 4);
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].as_ref().unwrap().rows_affected, 1);
+        assert_eq!(
+            results[1].as_ref().unwrap_err().to_string(),
+            "no such table: t"
+        );
+        assert!(results[2].is_err());
+        assert!(results[3].is_err());
+    }
+
+    #[test]
+    fn test_is_read_only() {
+        assert!(is_read_only("SELECT * FROM t; SELECT 1"));
+        assert!(is_read_only("EXPLAIN DELETE FROM t"));
+        assert!(is_read_only("SAVEPOINT a; ROLLBACK TO a; RELEASE a"));
+        assert!(!is_read_only("SELECT 1; DELETE FROM t"));
+        assert!(!is_read_only("ROLLBACK"));
+        assert!(!is_read_only("INSERT INTO t VALUES (1)"));
+        assert!(!is_read_only("CREATE TABLE t(x)"));
+        assert!(!is_read_only("SELEC 1"));
+    }
+
+    #[test]
+    fn test_disambiguate_columns() {
+        let columns = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            disambiguate_columns(&columns(&["id", "name", "id", "id"])),
+            ["id", "name", "id:1", "id:2"]
+        );
+        assert_eq!(
+            disambiguate_columns(&columns(&["id", "id:1", "id"])),
+            ["id", "id:1", "id:2"]
+        );
+    }
+
+    #[test]
+    fn test_quoting() {
+        assert_eq!(quote_identifier("t").unwrap(), r#""t""#);
+        assert_eq!(quote_identifier(r#"a"b"#).unwrap(), r#""a""b""#);
+        assert_eq!(quote_string_literal("").unwrap(), "''");
+        assert_eq!(
+            quote_string_literal("'; DROP TABLE t; --").unwrap(),
+            "'''; DROP TABLE t; --'"
+        );
+        assert!(quote_string_literal("a\0").is_err());
+    }
+
     #[test]
     fn test_pop_query_param_existing() {
         let mut url = Url::parse("http://turso.io/?super=yes&sqld=yo").unwrap();
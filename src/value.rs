@@ -0,0 +1,552 @@
+//! `ValueExt` extends [Value], which is defined in the wire protocol crate,
+//! with convenience methods.
+//!
+//! Every backend maps SQLite's storage classes to [Value] variants one to one,
+//! keeping the exact type the database returned, without any coercion:
+//!
+//! | SQLite    | [Value]            |
+//! |-----------|--------------------|
+//! | `NULL`    | `Value::Null`      |
+//! | `INTEGER` | `Value::Integer`   |
+//! | `REAL`    | `Value::Float`     |
+//! | `TEXT`    | `Value::Text`      |
+//! | `BLOB`    | `Value::Blob`      |
+//!
+//! So `SELECT 1.0` yields `Value::Float { value: 1.0 }` and `SELECT 1` yields
+//! `Value::Integer { value: 1 }`. Note that SQLite itself converts values stored
+//! in columns with `REAL` affinity, which then come back as floats.
+//!
+//! Optional parameters convert to [Value] too: `None` binds `NULL`,
+//! and `Some(value)` binds the value itself.
+//!
+//! ```
+//! use libsql_client::{args, Statement};
+//!
+//! let city: Option<&str> = None;
+//! let stmt = Statement::with_args("UPDATE users SET city = ? WHERE id = ?", args!(city, 1));
+//! ```
+//!
+//! The `From` conversions into [Value] live in the protocol crate along with it,
+//! and this crate can't add more for other foreign types. Integers of other widths
+//! and `f32` convert losslessly through `i64::from()` and `f64::from()`.
+//! `u64` and `usize` values may exceed `i64::MAX`, the largest `INTEGER` SQLite stores,
+//! so [ValueExt::try_from_u64] fails on those instead of wrapping them around.
+//!
+//! ```
+//! use libsql_client::{args, Statement, Value, ValueExt};
+//!
+//! let (count, ratio, len): (u32, f32, usize) = (3, 0.5, 7);
+//! let stmt = Statement::with_args(
+//!     "INSERT INTO stats VALUES (?, ?, ?)",
+//!     args!(i64::from(count), f64::from(ratio), Value::try_from_u64(len as u64).unwrap()),
+//! );
+//! ```
+
+use anyhow::Result;
+
+use crate::{utils, Value};
+
+/// Convenience methods for [Value]. Bring the trait into scope to use them:
+///
+/// ```
+/// use libsql_client::{Value, ValueExt};
+///
+/// let blob = Value::from_hex("deadbeef").unwrap();
+/// assert_eq!(blob.display_hex().to_string(), "deadbeef");
+/// assert_eq!(blob.as_blob(), Some(&[0xde, 0xad, 0xbe, 0xef][..]));
+/// assert_eq!(blob.as_text(), None);
+/// ```
+pub trait ValueExt {
+    /// Creates a blob value, taking ownership of the bytes. Passing a `Vec<u8>`
+    /// or a `Box<[u8]>` moves the buffer into the value without copying it,
+    /// which matters for multi-megabyte blobs.
+    fn from_blob(bytes: impl Into<Vec<u8>>) -> Value;
+
+    /// Creates a blob value from a hex-encoded string. Both lowercase
+    /// and uppercase digits are accepted.
+    fn from_hex(hex: &str) -> Result<Value>;
+
+    /// Creates an integer value from a `u64`. Fails with [crate::Error::Misuse]
+    /// if it exceeds `i64::MAX`, the largest integer SQLite stores, rather than
+    /// wrapping it around to a negative number. Such values can be bound as text instead.
+    fn try_from_u64(value: u64) -> Result<Value>;
+
+    /// Creates a `LIKE` pattern which matches `input` literally, escaping the `%` and `_`
+    /// wildcards and `escape` itself with `escape`. Returns the pattern, to be bound
+    /// as a parameter, and the `ESCAPE` clause which must follow it in the SQL.
+    /// Wildcards can then be added around the parameter in SQL.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libsql_client::{Statement, Value, ValueExt};
+    ///
+    /// let user_input = "100%";
+    /// let (pattern, escape) = Value::like_pattern(user_input, '\\');
+    /// let stmt = Statement::with_args(
+    ///     format!("SELECT * FROM counter WHERE city LIKE ? || '%' {escape}"),
+    ///     &[pattern],
+    /// );
+    /// ```
+    fn like_pattern(input: &str, escape: char) -> (Value, String);
+
+    /// Returns a wrapper which displays blobs as lowercase hex,
+    /// and all other values as usual.
+    fn display_hex(&self) -> HexDisplay<'_>;
+
+    /// Compares two values the way SQLite's `ORDER BY` does: NULLs first, then
+    /// numbers (integers and floats compared by value), then text, then blobs.
+    /// Text and blobs compare bytewise, and NaN sorts after all other numbers.
+    fn sql_cmp(&self, other: &Value) -> std::cmp::Ordering;
+
+    /// Returns the integer, if the value is one.
+    fn as_integer(&self) -> Option<i64>;
+
+    /// Returns the float, if the value is one. Integers are not converted, see [ValueExt::as_f64].
+    fn as_real(&self) -> Option<f64>;
+
+    /// Returns the number as a float, converting integers, e.g. for charting.
+    /// Integers beyond 2^53 lose precision.
+    fn as_f64(&self) -> Option<f64>;
+
+    /// Returns a reference to the text, if the value is text.
+    fn as_text(&self) -> Option<&str>;
+
+    /// Returns a reference to the bytes, if the value is a blob.
+    fn as_blob(&self) -> Option<&[u8]>;
+
+    /// Returns an [AsyncRead](futures::io::AsyncRead) over the bytes, if the value is a blob,
+    /// without copying them, e.g. to pass a blob to code expecting a reader.
+    ///
+    /// The blob is still received in full before it can be read: the Hrana protocol
+    /// sends each result in a single message, so there is no way to stream its bytes.
+    /// To hold a single copy of large blobs, disable the `mapping_names_to_values_in_rows`
+    /// feature, which copies every value into [crate::Row::value_map].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # futures::executor::block_on(async {
+    /// use futures::io::AsyncReadExt;
+    /// use libsql_client::{Value, ValueExt};
+    ///
+    /// let blob = Value::from_blob(vec![1, 2, 3]);
+    /// let mut bytes = Vec::new();
+    /// blob.as_reader().unwrap().read_to_end(&mut bytes).await.unwrap();
+    /// assert_eq!(bytes, [1, 2, 3]);
+    /// # });
+    /// ```
+    fn as_reader(&self) -> Option<futures::io::Cursor<&[u8]>>;
+
+    /// Like [ValueExt::as_reader], but takes ownership of the bytes.
+    fn into_reader(self) -> Option<futures::io::Cursor<Vec<u8>>>;
+
+    /// Returns the boolean stored the SQLite way: integer 0 is false and 1 is true.
+    /// Other values are not booleans.
+    fn as_bool(&self) -> Option<bool>;
+
+    fn is_null(&self) -> bool;
+}
+
+impl ValueExt for Value {
+    fn from_blob(bytes: impl Into<Vec<u8>>) -> Value {
+        Value::Blob {
+            value: bytes.into(),
+        }
+    }
+
+    fn try_from_u64(value: u64) -> Result<Value> {
+        match i64::try_from(value) {
+            Ok(value) => Ok(Value::Integer { value }),
+            Err(_) => Err(crate::Error::Misuse(format!(
+                "{value} exceeds i64::MAX, the largest integer SQLite stores"
+            ))
+            .into()),
+        }
+    }
+
+    fn from_hex(hex: &str) -> Result<Value> {
+        Ok(Value::Blob {
+            value: utils::decode_hex(hex)?,
+        })
+    }
+
+    fn like_pattern(input: &str, escape: char) -> (Value, String) {
+        let mut pattern = String::with_capacity(input.len());
+        for c in input.chars() {
+            if c == '%' || c == '_' || c == escape {
+                pattern.push(escape);
+            }
+            pattern.push(c);
+        }
+        let escape = escape.to_string().replace('\'', "''");
+        (Value::Text { value: pattern }, format!("ESCAPE '{escape}'"))
+    }
+
+    fn display_hex(&self) -> HexDisplay<'_> {
+        HexDisplay(self)
+    }
+
+    fn as_integer(&self) -> Option<i64> {
+        match self {
+            Value::Integer { value } => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn as_real(&self) -> Option<f64> {
+        match self {
+            Value::Float { value } => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Integer { value } => Some(*value as f64),
+            Value::Float { value } => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn as_text(&self) -> Option<&str> {
+        match self {
+            Value::Text { value } => Some(value),
+            _ => None,
+        }
+    }
+
+    fn as_blob(&self) -> Option<&[u8]> {
+        match self {
+            Value::Blob { value } => Some(value),
+            _ => None,
+        }
+    }
+
+    fn as_reader(&self) -> Option<futures::io::Cursor<&[u8]>> {
+        self.as_blob().map(futures::io::Cursor::new)
+    }
+
+    fn into_reader(self) -> Option<futures::io::Cursor<Vec<u8>>> {
+        match self {
+            Value::Blob { value } => Some(futures::io::Cursor::new(value)),
+            _ => None,
+        }
+    }
+
+    fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Integer { value: 0 } => Some(false),
+            Value::Integer { value: 1 } => Some(true),
+            _ => None,
+        }
+    }
+
+    fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    fn sql_cmp(&self, other: &Value) -> std::cmp::Ordering {
+        fn rank(value: &Value) -> u8 {
+            match value {
+                Value::Null => 0,
+                Value::Integer { .. } | Value::Float { .. } => 1,
+                Value::Text { .. } => 2,
+                Value::Blob { .. } => 3,
+            }
+        }
+        match (self, other) {
+            (Value::Integer { value: a }, Value::Integer { value: b }) => a.cmp(b),
+            (Value::Integer { value: a }, Value::Float { value: b }) => (*a as f64).total_cmp(b),
+            (Value::Float { value: a }, Value::Integer { value: b }) => a.total_cmp(&(*b as f64)),
+            (Value::Float { value: a }, Value::Float { value: b }) => {
+                match (a.is_nan(), b.is_nan()) {
+                    (false, false) => a.total_cmp(b),
+                    (a_nan, b_nan) => a_nan.cmp(&b_nan),
+                }
+            }
+            (Value::Text { value: a }, Value::Text { value: b }) => a.cmp(b),
+            (Value::Blob { value: a }, Value::Blob { value: b }) => a.cmp(b),
+            (a, b) => rank(a).cmp(&rank(b)),
+        }
+    }
+}
+
+/// Displays blobs as lowercase hex. See [ValueExt::display_hex].
+pub struct HexDisplay<'a>(&'a Value);
+
+impl std::fmt::Display for HexDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Value::Blob { value } => f.write_str(&utils::encode_hex(value)),
+            other => std::fmt::Display::fmt(other, f),
+        }
+    }
+}
+
+/// Wrapper which gives [Value] equality and hashing, e.g. to key a cache by
+/// the parameters of a query, or to compare values in tests. [Value] is defined
+/// in the wire protocol crate, so it can't implement these traits itself.
+///
+/// Values are equal if they have the same type and the same contents: unlike
+/// [ValueExt::sql_cmp], `1` and `1.0` differ. Floats are compared bitwise, so that
+/// equality stays reflexive: NaNs with the same bits are equal, and `0.0` differs from `-0.0`.
+///
+/// # Examples
+///
+/// ```
+/// use libsql_client::{Value, ValueKey};
+///
+/// let value = Value::Integer { value: 5 };
+/// assert_eq!(value, ValueKey(Value::Integer { value: 5 }));
+/// assert_ne!(value, ValueKey(Value::Float { value: 5.0 }));
+///
+/// let mut cache = std::collections::HashMap::new();
+/// cache.insert(ValueKey(value), "five");
+/// ```
+#[derive(Clone, Debug)]
+pub struct ValueKey(pub Value);
+
+impl PartialEq for ValueKey {
+    fn eq(&self, other: &Self) -> bool {
+        self == &other.0
+    }
+}
+
+impl Eq for ValueKey {}
+
+impl PartialEq<Value> for ValueKey {
+    fn eq(&self, other: &Value) -> bool {
+        match (&self.0, other) {
+            (Value::Null, Value::Null) => true,
+            (Value::Integer { value: a }, Value::Integer { value: b }) => a == b,
+            (Value::Float { value: a }, Value::Float { value: b }) => a.to_bits() == b.to_bits(),
+            (Value::Text { value: a }, Value::Text { value: b }) => a == b,
+            (Value::Blob { value: a }, Value::Blob { value: b }) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl PartialEq<ValueKey> for Value {
+    fn eq(&self, other: &ValueKey) -> bool {
+        other == self
+    }
+}
+
+impl std::hash::Hash for ValueKey {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        utils::type_name(&self.0).hash(state);
+        match &self.0 {
+            Value::Null => {}
+            Value::Integer { value } => value.hash(state),
+            Value::Float { value } => value.to_bits().hash(state),
+            Value::Text { value } => value.hash(state),
+            Value::Blob { value } => value.hash(state),
+        }
+    }
+}
+
+impl From<Value> for ValueKey {
+    fn from(value: Value) -> Self {
+        ValueKey(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value_key() {
+        use std::collections::HashSet;
+
+        let keys: HashSet<ValueKey> = [
+            Value::Null,
+            Value::Integer { value: 1 },
+            Value::Float { value: 1.0 },
+            Value::Float { value: f64::NAN },
+            Value::Float { value: f64::NAN },
+            Value::Float { value: -0.0 },
+            Value::Float { value: 0.0 },
+            Value::Text {
+                value: "1".to_string(),
+            },
+            Value::Blob { value: vec![1] },
+        ]
+        .into_iter()
+        .map(ValueKey)
+        .collect();
+        assert_eq!(keys.len(), 8);
+        assert!(keys.contains(&ValueKey(Value::Float { value: f64::NAN })));
+        assert_ne!(Value::Null, ValueKey(Value::Integer { value: 0 }));
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let value = Value::from_hex("00ff10AB").unwrap();
+        assert!(matches!(&value, Value::Blob { value } if value == &[0x00, 0xff, 0x10, 0xab]));
+        assert_eq!(value.display_hex().to_string(), "00ff10ab");
+    }
+
+    #[test]
+    fn test_accessors() {
+        let int = Value::Integer { value: 1 };
+        assert_eq!(int.as_integer(), Some(1));
+        assert_eq!(int.as_real(), None);
+        assert_eq!(int.as_f64(), Some(1.0));
+        assert_eq!(int.as_bool(), Some(true));
+        assert_eq!(Value::Integer { value: 2 }.as_bool(), None);
+        let text = Value::Text {
+            value: "hi".to_string(),
+        };
+        assert_eq!(text.as_text(), Some("hi"));
+        assert_eq!(text.as_f64(), None);
+        assert!(Value::Null.is_null());
+        assert!(!text.is_null());
+    }
+
+    #[test]
+    fn test_sql_cmp() {
+        use std::cmp::Ordering;
+
+        let int = |value| Value::Integer { value };
+        let float = |value| Value::Float { value };
+        let text = |value: &str| Value::Text {
+            value: value.to_string(),
+        };
+        assert_eq!(Value::Null.sql_cmp(&int(0)), Ordering::Less);
+        assert_eq!(int(2).sql_cmp(&float(10.5)), Ordering::Less);
+        assert_eq!(float(2.0).sql_cmp(&int(2)), Ordering::Equal);
+        assert_eq!(int(10).sql_cmp(&text("9")), Ordering::Less);
+        assert_eq!(text("10").sql_cmp(&text("9")), Ordering::Less);
+        assert_eq!(
+            text("z").sql_cmp(&Value::from_blob(vec![0])),
+            Ordering::Less
+        );
+        assert_eq!(float(f64::NAN).sql_cmp(&float(1.0)), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_large_blob_wire_round_trip() {
+        use base64::prelude::BASE64_STANDARD_NO_PAD;
+        use base64::Engine;
+
+        let bytes: Vec<u8> = (0..4 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+        let value = Value::from_blob(bytes.clone());
+        let json = serde_json::to_value(&value).unwrap();
+        assert_eq!(json["type"], "blob");
+        // sqld expects standard base64 without padding
+        let encoded = json["base64"].as_str().unwrap();
+        assert_eq!(BASE64_STANDARD_NO_PAD.decode(encoded).unwrap(), bytes);
+        let decoded: Value = serde_json::from_value(json).unwrap();
+        assert!(matches!(decoded, Value::Blob { value } if value == bytes));
+    }
+
+    #[test]
+    fn test_wire_numeric_types_preserved() {
+        let float: Value = serde_json::from_str(r#"{"type":"float","value":1.0}"#).unwrap();
+        assert!(matches!(float, Value::Float { value } if value == 1.0));
+        let int: Value = serde_json::from_str(r#"{"type":"integer","value":"1"}"#).unwrap();
+        assert!(matches!(int, Value::Integer { value: 1 }));
+    }
+
+    #[cfg(feature = "local_backend")]
+    #[test]
+    fn test_local_numeric_types_preserved() {
+        let db = crate::SyncClient::in_memory().unwrap();
+        let rs = db.execute("SELECT 1.0, 1, CAST(1 AS REAL)").unwrap();
+        let values = &rs.rows[0].values;
+        assert!(matches!(values[0], Value::Float { value } if value == 1.0));
+        assert!(matches!(values[1], Value::Integer { value: 1 }));
+        assert!(matches!(values[2], Value::Float { value } if value == 1.0));
+    }
+
+    #[test]
+    fn test_try_from_u64() {
+        assert!(matches!(
+            Value::try_from_u64(i64::MAX as u64).unwrap(),
+            Value::Integer { value: i64::MAX }
+        ));
+        let e = Value::try_from_u64(u64::MAX).unwrap_err();
+        assert!(matches!(
+            e.downcast_ref::<crate::Error>(),
+            Some(crate::Error::Misuse(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_option() {
+        assert!(matches!(
+            Value::from(Some(5i64)),
+            Value::Integer { value: 5 }
+        ));
+        assert!(matches!(Value::from(None::<i64>), Value::Null));
+        assert!(matches!(
+            Value::from(Some("Warsaw".to_string())),
+            Value::Text { value } if value == "Warsaw"
+        ));
+        assert!(matches!(Value::from(None::<String>), Value::Null));
+        assert!(matches!(Value::from(Some("Warsaw")), Value::Text { value } if value == "Warsaw"));
+        assert!(matches!(Value::from(None::<&str>), Value::Null));
+    }
+
+    #[test]
+    fn test_like_pattern() {
+        let (pattern, escape) = Value::like_pattern(r"50%_off\", '\\');
+        assert_eq!(pattern.as_text(), Some(r"50\%\_off\\"));
+        assert_eq!(escape, r"ESCAPE '\'");
+        let (pattern, escape) = Value::like_pattern("it's 5%", '\'');
+        assert_eq!(pattern.as_text(), Some("it''s 5'%"));
+        assert_eq!(escape, "ESCAPE ''''");
+    }
+
+    #[cfg(feature = "local_backend")]
+    #[test]
+    fn test_like_pattern_matches_literally() {
+        let db = crate::SyncClient::in_memory().unwrap();
+        let matches = |text: &str, input: &str| {
+            let (pattern, escape) = Value::like_pattern(input, '\\');
+            let sql = format!("SELECT ? LIKE ? || '%' {escape}");
+            let rs = db
+                .execute(crate::Statement::with_args(
+                    sql,
+                    &[Value::from(text), pattern],
+                ))
+                .unwrap();
+            rs.rows[0].values[0].as_bool().unwrap()
+        };
+        assert!(matches("50%_off sale", "50%_off"));
+        assert!(!matches("500 offers", "50%"));
+        assert!(!matches("a-b", "a_b"));
+        assert!(matches(r"C:\temp", r"C:\"));
+    }
+
+    #[test]
+    fn test_readers() {
+        use futures::io::AsyncReadExt;
+
+        let bytes: Vec<u8> = (0..=255).collect();
+        let blob = Value::from_blob(bytes.clone());
+        let mut head = [0; 4];
+        let mut reader = blob.as_reader().unwrap();
+        futures::executor::block_on(reader.read_exact(&mut head)).unwrap();
+        assert_eq!(head, [0, 1, 2, 3]);
+        let mut read = Vec::new();
+        futures::executor::block_on(blob.into_reader().unwrap().read_to_end(&mut read)).unwrap();
+        assert_eq!(read, bytes);
+        assert!(Value::Null.as_reader().is_none());
+        assert!(Value::Text {
+            value: "a".to_string()
+        }
+        .into_reader()
+        .is_none());
+    }
+
+    #[test]
+    fn test_from_hex_invalid() {
+        assert!(Value::from_hex("abc").is_err());
+        assert!(Value::from_hex("zz").is_err());
+        assert!(Value::from_hex("żż").is_err());
+    }
+}
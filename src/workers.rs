@@ -1,7 +1,7 @@
 use anyhow::Result;
 use worker::*;
 
-use crate::proto::pipeline;
+use crate::Error;
 
 #[derive(Clone, Debug)]
 pub struct HttpClient;
@@ -15,10 +15,28 @@ impl HttpClient {
         &self,
         url: String,
         auth: String,
+        extra_headers: Vec<(String, String)>,
         body: String,
-    ) -> Result<pipeline::ServerMsg> {
+    ) -> Result<String> {
+        self.send_limited(url, auth, extra_headers, body, usize::MAX)
+            .await
+    }
+
+    // Sends the request, and rejects responses larger than `max_bytes`.
+    // The body can only be read in full, so Content-Length is checked before reading it.
+    pub(crate) async fn send_limited(
+        &self,
+        url: String,
+        auth: String,
+        extra_headers: Vec<(String, String)>,
+        body: String,
+        max_bytes: usize,
+    ) -> Result<String> {
         let mut headers = Headers::new();
         headers.append("Authorization", &auth).ok();
+        for (name, value) in extra_headers {
+            headers.append(&name, &value).ok();
+        }
 
         let request_init = RequestInit {
             body: Some(wasm_bindgen::JsValue::from_str(&body)),
@@ -34,12 +52,57 @@ impl HttpClient {
             .await
             .map_err(|e| anyhow::anyhow!("{e}"))?;
         if response.status_code() != 200 {
-            anyhow::bail!("Status {}", response.status_code());
+            let status = response.status_code();
+            let message = response.text().await.unwrap_or_default();
+            return Err(Error::from_status(status, message).into());
         }
+        let content_length = response
+            .headers()
+            .get("content-length")
+            .ok()
+            .flatten()
+            .and_then(|len| len.parse::<usize>().ok());
+        if content_length.is_some_and(|len| len > max_bytes) {
+            return Err(Error::ResponseTooLarge { limit: max_bytes }.into());
+        }
+        let text = response.text().await.map_err(|e| anyhow::anyhow!("{e}"))?;
+        if text.len() > max_bytes {
+            return Err(Error::ResponseTooLarge { limit: max_bytes }.into());
+        }
+        Ok(text)
+    }
 
-        let resp: String = response.text().await.map_err(|e| anyhow::anyhow!("{e}"))?;
-        let response: pipeline::ServerMsg = serde_json::from_str(&resp)?;
-        Ok(response)
+    pub async fn get(
+        &self,
+        url: String,
+        auth: String,
+        extra_headers: Vec<(String, String)>,
+    ) -> Result<String> {
+        let mut headers = Headers::new();
+        headers.append("Authorization", &auth).ok();
+        for (name, value) in extra_headers {
+            headers.append(&name, &value).ok();
+        }
+
+        let request_init = RequestInit {
+            body: None,
+            headers,
+            cf: CfProperties::new(),
+            method: Method::Get,
+            redirect: RequestRedirect::Follow,
+        };
+        let req =
+            Request::new_with_init(&url, &request_init).map_err(|e| anyhow::anyhow!("{e}"))?;
+        let mut response = Fetch::Request(req)
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+        if response.status_code() != 200 {
+            let status = response.status_code();
+            let message = response.text().await.unwrap_or_default();
+            return Err(Error::from_status(status, message).into());
+        }
+        response.text().await.map_err(|e| anyhow::anyhow!("{e}"))
     }
 }
 